@@ -0,0 +1,6 @@
+//! Prints the JSON Schema of the template format, the source of `sigil-core/sigil.schema.json`.
+
+fn main() {
+    let schema = sigil_core::json_schema();
+    println!("{}", serde_json::to_string_pretty(&schema).expect("schemas serialize"));
+}
@@ -0,0 +1,156 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! The canvas background: a color, a gradient, an image or a tiled pattern.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{default_focal_point, Bindable, Fill, Gradient, ImageFilter, ImageFit, LinearGradient, RadialGradient, Rgba};
+
+/// What is drawn below the layers.
+///
+/// Colors serialize as a plain string and gradients like a [`Fill`], so templates written before typed
+/// backgrounds keep loading. A plain string that is not a color names an image resource, as it used to.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(from = "BackgroundRepr", into = "BackgroundRepr")]
+pub enum Background {
+    Solid(String),
+    Gradient(Gradient),
+    Image(BackgroundImage),
+    Pattern(BackgroundPattern),
+}
+
+/// An image covering the canvas, like CSS `background-image` with `background-size` and `background-position`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BackgroundImage {
+    pub source: String,
+    /// How the image is sized to the canvas. Defaults to `cover`.
+    #[serde(default = "default_background_fit")]
+    pub fit: ImageFit,
+    /// Where the image sits when it does not fill the canvas exactly, as with `ImageItem::focal_point`.
+    #[serde(default = "default_focal_point")]
+    pub focal_point: [Bindable<f32>; 2],
+    /// Color adjustments and blur, applied to the image as laid out on the canvas.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<ImageFilter>,
+    /// Drawn over the image and below the layers, such as a translucent black to keep text readable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overlay: Option<Fill>,
+}
+
+/// An image repeated across the canvas from its top left corner, like CSS `background-repeat: repeat`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BackgroundPattern {
+    pub source: String,
+    /// `[width, height]` of one tile. Tiles keep the image's own size when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tile_size: Option<[Bindable<f32>; 2]>,
+}
+
+fn default_background_fit() -> ImageFit {
+    ImageFit::Cover
+}
+
+/// The serialized form: a plain string for colors and legacy image names, a tagged object otherwise.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+enum BackgroundRepr {
+    Plain(String),
+    Typed(TypedBackground),
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TypedBackground {
+    Solid { color: String },
+    Linear(LinearGradient),
+    Radial(RadialGradient),
+    Image(BackgroundImage),
+    Pattern(BackgroundPattern),
+}
+
+impl From<BackgroundRepr> for Background {
+    fn from(repr: BackgroundRepr) -> Self {
+        match repr {
+            BackgroundRepr::Plain(value) => Background::from_plain(value),
+            BackgroundRepr::Typed(TypedBackground::Solid { color }) => Background::Solid(color),
+            BackgroundRepr::Typed(TypedBackground::Linear(linear)) => Background::Gradient(Gradient::Linear(linear)),
+            BackgroundRepr::Typed(TypedBackground::Radial(radial)) => Background::Gradient(Gradient::Radial(radial)),
+            BackgroundRepr::Typed(TypedBackground::Image(image)) => Background::Image(image),
+            BackgroundRepr::Typed(TypedBackground::Pattern(pattern)) => Background::Pattern(pattern),
+        }
+    }
+}
+
+impl From<Background> for BackgroundRepr {
+    fn from(background: Background) -> Self {
+        match background {
+            Background::Solid(color) => BackgroundRepr::Plain(color),
+            Background::Gradient(Gradient::Linear(linear)) => BackgroundRepr::Typed(TypedBackground::Linear(linear)),
+            Background::Gradient(Gradient::Radial(radial)) => BackgroundRepr::Typed(TypedBackground::Radial(radial)),
+            Background::Image(image) => BackgroundRepr::Typed(TypedBackground::Image(image)),
+            Background::Pattern(pattern) => BackgroundRepr::Typed(TypedBackground::Pattern(pattern)),
+        }
+    }
+}
+
+impl Background {
+    /// Reads a plain string background: a color, or an image resource covering the canvas. Strings with
+    /// unresolved variables stay colors until [`crate::Sigil::resolve`] substitutes them and reads them again.
+    pub fn from_plain(value: String) -> Self {
+        if value.contains('{') || Rgba::parse(&value).is_ok() {
+            Background::Solid(value)
+        } else {
+            Background::Image(BackgroundImage::new(value))
+        }
+    }
+
+    /// The background as a fill, if it is a color or gradient.
+    pub fn as_fill(&self) -> Option<Fill> {
+        match self {
+            Background::Solid(color) => Some(Fill::Solid(color.clone())),
+            Background::Gradient(gradient) => Some(Fill::Gradient(gradient.clone())),
+            Background::Image(_) | Background::Pattern(_) => None,
+        }
+    }
+}
+
+impl BackgroundImage {
+    /// The image covering the canvas from its center, without filters or overlay.
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            fit: default_background_fit(),
+            focal_point: default_focal_point(),
+            filters: Vec::new(),
+            overlay: None,
+        }
+    }
+}
+
+impl From<&str> for Background {
+    fn from(value: &str) -> Self {
+        Background::from_plain(value.to_string())
+    }
+}
+
+impl From<Fill> for Background {
+    fn from(fill: Fill) -> Self {
+        match fill {
+            Fill::Solid(color) => Background::Solid(color),
+            Fill::Gradient(gradient) => Background::Gradient(gradient),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! Numbers and flags that templates can bind to variables, such as a progress bar's `"value": "{xp}"`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::expr;
+
+/// A field holding either a literal or a binding: a template string like `"{xp / max_xp * 100}"` that
+/// [`crate::Sigil::resolve`] evaluates and parses as `T`.
+///
+/// Renderers read the value through [`Deref`]. A binding that has not been resolved, or whose result does not
+/// parse, reads as `T::default()`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema), schemars(rename = "Bindable_{T}"))]
+#[serde(
+    try_from = "BindableRepr<T>",
+    into = "BindableRepr<T>",
+    bound(serialize = "T: Serialize + Clone", deserialize = "T: Deserialize<'de> + FromStr + Default")
+)]
+pub struct Bindable<T> {
+    value: T,
+    binding: Option<String>,
+}
+
+/// The serialized form: the literal itself, or the binding as a string.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+enum BindableRepr<T> {
+    Value(T),
+    Binding(String),
+}
+
+impl<T> Bindable<T> {
+    /// A literal value.
+    pub const fn new(value: T) -> Self {
+        Self { value, binding: None }
+    }
+
+    /// A binding, read as `T::default()` until resolved.
+    pub fn bound(binding: impl Into<String>) -> Self
+    where
+        T: Default,
+    {
+        Self { value: T::default(), binding: Some(binding.into()) }
+    }
+
+    /// The unresolved template string, if the field is bound.
+    pub fn binding(&self) -> Option<&str> {
+        self.binding.as_deref()
+    }
+
+    /// Replaces the value, and any binding, with a literal.
+    pub fn set(&mut self, value: T) {
+        *self = Self::new(value);
+    }
+
+    /// Evaluates the binding, if any, keeping it unresolved when a variable is missing or the result is not a `T`.
+    pub(crate) fn resolve(&mut self, variables: &HashMap<String, String>)
+    where
+        T: FromStr,
+    {
+        if let Some(binding) = &self.binding
+            && let Ok(value) = expr::substitute(binding, variables).trim().parse()
+        {
+            self.set(value);
+        }
+    }
+}
+
+impl<T> Deref for Bindable<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> From<T> for Bindable<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Reads a literal, or a binding if the string has a `{...}` placeholder.
+impl<T: FromStr + Default> FromStr for Bindable<T> {
+    type Err = T::Err;
+
+    fn from_str(input: &str) -> Result<Self, T::Err> {
+        match input.trim().parse() {
+            Ok(value) => Ok(Self::new(value)),
+            Err(_) if input.contains('{') => Ok(Self::bound(input)),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Shows the binding if there is one, otherwise the value, so editors can show either in a text field.
+impl<T: fmt::Display> fmt::Display for Bindable<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.binding {
+            Some(binding) => f.write_str(binding),
+            None => self.value.fmt(f),
+        }
+    }
+}
+
+/// Quoted literals like `"12"` read as values, other strings must be templates.
+impl<T: FromStr + Default> TryFrom<BindableRepr<T>> for Bindable<T> {
+    type Error = String;
+
+    fn try_from(repr: BindableRepr<T>) -> Result<Self, String> {
+        match repr {
+            BindableRepr::Value(value) => Ok(Self::new(value)),
+            BindableRepr::Binding(binding) => {
+                binding.parse().map_err(|_| format!("expected a value or a {{...}} template, found \"{binding}\""))
+            }
+        }
+    }
+}
+
+impl<T> From<Bindable<T>> for BindableRepr<T> {
+    fn from(bindable: Bindable<T>) -> Self {
+        match bindable.binding {
+            Some(binding) => BindableRepr::Binding(binding),
+            None => BindableRepr::Value(bindable.value),
+        }
+    }
+}
@@ -0,0 +1,427 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! CSS color parsing shared by all renderers, so a template that previews in the browser also renders server-side.
+//!
+//! Supports `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`/`rgba()`, `hsl()`/`hsla()` in both the comma
+//! and the space separated syntax, the CSS named colors and `transparent`.
+
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// An sRGB color with straight (not premultiplied) alpha.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ColorParseError {
+    #[error("Empty color")]
+    Empty,
+
+    #[error("Invalid hex color: {0}")]
+    InvalidHex(String),
+
+    #[error("Invalid color function: {0}")]
+    InvalidFunction(String),
+
+    #[error("Unknown color name: {0}")]
+    UnknownName(String),
+}
+
+impl Rgba {
+    pub const TRANSPARENT: Rgba = Rgba::new(0, 0, 0, 0);
+    pub const BLACK: Rgba = Rgba::new(0, 0, 0, 255);
+    pub const WHITE: Rgba = Rgba::new(255, 255, 255, 255);
+
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub fn parse(input: &str) -> Result<Self, ColorParseError> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err(ColorParseError::Empty);
+        }
+
+        if let Some(hex) = input.strip_prefix('#') {
+            return parse_hex(hex).ok_or_else(|| ColorParseError::InvalidHex(input.to_string()));
+        }
+
+        let lower = input.to_ascii_lowercase();
+        if let Some(open) = lower.find('(') {
+            let name = lower[..open].trim();
+            let args = lower[open + 1..]
+                .strip_suffix(')')
+                .ok_or_else(|| ColorParseError::InvalidFunction(input.to_string()))?;
+            let parsed = match name {
+                "rgb" | "rgba" => parse_rgb(args),
+                "hsl" | "hsla" => parse_hsl(args),
+                _ => None,
+            };
+            return parsed.ok_or_else(|| ColorParseError::InvalidFunction(input.to_string()));
+        }
+
+        if lower == "transparent" {
+            return Ok(Rgba::TRANSPARENT);
+        }
+
+        NAMED_COLORS
+            .binary_search_by_key(&lower.as_str(), |&(name, _)| name)
+            .map(|index| {
+                let rgb = NAMED_COLORS[index].1;
+                Rgba::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255)
+            })
+            .map_err(|_| ColorParseError::UnknownName(input.to_string()))
+    }
+
+    /// `#rrggbb` for opaque colors, `#rrggbbaa` otherwise. Parses back to the same color.
+    pub fn to_hex(self) -> String {
+        if self.a == 255 {
+            self.to_hex_rgb()
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+        }
+    }
+
+    /// `#rrggbb`, dropping alpha, as expected by `<input type="color">`.
+    pub fn to_hex_rgb(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    /// Alpha as a fraction from 0.0 to 1.0.
+    pub fn alpha(self) -> f32 {
+        self.a as f32 / 255.0
+    }
+}
+
+impl FromStr for Rgba {
+    type Err = ColorParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rgba::parse(s)
+    }
+}
+
+impl fmt::Display for Rgba {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Rgba> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|d| d * 17);
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+    match hex.len() {
+        3 => Some(Rgba::new(digit(0)?, digit(1)?, digit(2)?, 255)),
+        4 => Some(Rgba::new(digit(0)?, digit(1)?, digit(2)?, digit(3)?)),
+        6 => Some(Rgba::new(byte(0)?, byte(2)?, byte(4)?, 255)),
+        8 => Some(Rgba::new(byte(0)?, byte(2)?, byte(4)?, byte(6)?)),
+        _ => None,
+    }
+}
+
+/// Splits function arguments in either the legacy `a, b, c, alpha` or the modern `a b c / alpha` syntax.
+fn split_args(args: &str) -> Option<(Vec<&str>, Option<&str>)> {
+    if args.contains(',') {
+        let mut parts: Vec<&str> = args.split(',').map(str::trim).collect();
+        let alpha = match parts.len() {
+            3 => None,
+            4 => parts.pop(),
+            _ => return None,
+        };
+        return Some((parts, alpha));
+    }
+
+    let (components, alpha) = match args.split_once('/') {
+        Some((components, alpha)) => (components, Some(alpha.trim())),
+        None => (args, None),
+    };
+    let parts: Vec<&str> = components.split_whitespace().collect();
+    (parts.len() == 3).then_some((parts, alpha))
+}
+
+/// A number, or a percentage of `percent_scale`.
+fn parse_number(value: &str, percent_scale: f32) -> Option<f32> {
+    let number = match value.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f32>().ok()? / 100.0 * percent_scale,
+        None => value.parse::<f32>().ok()?,
+    };
+    number.is_finite().then_some(number)
+}
+
+fn parse_alpha(alpha: Option<&str>) -> Option<u8> {
+    match alpha {
+        Some(alpha) => Some(to_channel(parse_number(alpha, 1.0)?.clamp(0.0, 1.0) * 255.0)),
+        None => Some(255),
+    }
+}
+
+fn to_channel(value: f32) -> u8 {
+    value.round().clamp(0.0, 255.0) as u8
+}
+
+fn parse_rgb(args: &str) -> Option<Rgba> {
+    let (parts, alpha) = split_args(args)?;
+    let channel = |i: usize| parse_number(parts[i], 255.0).map(to_channel);
+    Some(Rgba::new(channel(0)?, channel(1)?, channel(2)?, parse_alpha(alpha)?))
+}
+
+fn parse_hsl(args: &str) -> Option<Rgba> {
+    let (parts, alpha) = split_args(args)?;
+
+    let hue = parse_hue(parts[0])?;
+    let saturation = parse_number(parts[1], 1.0)?.clamp(0.0, 1.0);
+    let lightness = parse_number(parts[2], 1.0)?.clamp(0.0, 1.0);
+    let (r, g, b) = hsl_to_rgb(hue, saturation, lightness);
+
+    Some(Rgba::new(to_channel(r * 255.0), to_channel(g * 255.0), to_channel(b * 255.0), parse_alpha(alpha)?))
+}
+
+/// Hue in degrees, accepting the `deg`, `rad`, `grad` and `turn` units.
+fn parse_hue(value: &str) -> Option<f32> {
+    let units = [("deg", 1.0), ("grad", 0.9), ("rad", 180.0 / std::f32::consts::PI), ("turn", 360.0)];
+    let degrees = match units.iter().find_map(|(unit, scale)| value.strip_suffix(unit).map(|v| (v, scale))) {
+        Some((number, scale)) => number.trim().parse::<f32>().ok()? * scale,
+        None => value.parse::<f32>().ok()?,
+    };
+    degrees.is_finite().then_some(degrees.rem_euclid(360.0))
+}
+
+/// The CSS Color 4 `hslToRgb` algorithm; all values are fractions except the hue in degrees.
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> (f32, f32, f32) {
+    let f = |n: f32| {
+        let k = (n + hue / 30.0) % 12.0;
+        let a = saturation * lightness.min(1.0 - lightness);
+        lightness - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+    (f(0.0), f(8.0), f(4.0))
+}
+
+/// CSS named colors, sorted by name for binary search.
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Rgba {
+        Rgba::parse(input).unwrap_or_else(|e| panic!("{}: {}", input, e))
+    }
+
+    #[test]
+    fn parses_hex_forms() {
+        assert_eq!(parse("#f80"), Rgba::new(0xff, 0x88, 0x00, 255));
+        assert_eq!(parse("#f808"), Rgba::new(0xff, 0x88, 0x00, 0x88));
+        assert_eq!(parse("#1A2b3C"), Rgba::new(0x1a, 0x2b, 0x3c, 255));
+        assert_eq!(parse("#1a2b3c80"), Rgba::new(0x1a, 0x2b, 0x3c, 0x80));
+        assert!(matches!(Rgba::parse("#12345"), Err(ColorParseError::InvalidHex(_))));
+        assert!(matches!(Rgba::parse("#ggg"), Err(ColorParseError::InvalidHex(_))));
+    }
+
+    #[test]
+    fn parses_rgb_functions() {
+        assert_eq!(parse("rgb(255, 0, 128)"), Rgba::new(255, 0, 128, 255));
+        assert_eq!(parse("rgba(255, 0, 128, 0.5)"), Rgba::new(255, 0, 128, 128));
+        assert_eq!(parse("RGB(100% 0% 50% / 25%)"), Rgba::new(255, 0, 128, 64));
+        assert_eq!(parse("rgb(300 -5 0)"), Rgba::new(255, 0, 0, 255));
+        assert!(Rgba::parse("rgb(1, 2)").is_err());
+        assert!(Rgba::parse("rgb(1, 2, 3").is_err());
+    }
+
+    #[test]
+    fn parses_hsl_functions() {
+        assert_eq!(parse("hsl(0, 100%, 50%)"), Rgba::new(255, 0, 0, 255));
+        assert_eq!(parse("hsl(120deg 100% 25%)"), Rgba::new(0, 128, 0, 255));
+        assert_eq!(parse("hsla(240, 100%, 50%, 0.5)"), Rgba::new(0, 0, 255, 128));
+        assert_eq!(parse("hsl(0.5turn 100% 50%)"), Rgba::new(0, 255, 255, 255));
+        assert_eq!(parse("hsl(-120 100% 50%)"), Rgba::new(0, 0, 255, 255));
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!(parse("transparent"), Rgba::TRANSPARENT);
+        assert_eq!(parse("RebeccaPurple"), Rgba::new(0x66, 0x33, 0x99, 255));
+        assert_eq!(parse(" white "), Rgba::WHITE);
+        assert!(NAMED_COLORS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(matches!(Rgba::parse("avatar.png"), Err(ColorParseError::UnknownName(_))));
+        assert_eq!(Rgba::parse(""), Err(ColorParseError::Empty));
+    }
+
+    #[test]
+    fn round_trips_through_hex() {
+        for input in ["#000", "#fff8", "#123456", "#12345678", "rgba(10, 20, 30, 0.3)", "hsl(200 50% 40%)", "teal", "transparent"] {
+            let color = parse(input);
+            assert_eq!(parse(&color.to_hex()), color, "{}", input);
+            assert_eq!(color.to_string().parse::<Rgba>(), Ok(color));
+        }
+        assert_eq!(parse("#abc").to_hex(), "#aabbcc");
+        assert_eq!(parse("rgb(255 0 0 / 50%)").to_hex(), "#ff000080");
+    }
+}
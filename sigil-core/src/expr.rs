@@ -0,0 +1,845 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! Template expressions: the `{...}` placeholders in texts, colors and image sources.
+//!
+//! A placeholder holds a variable, or arithmetic on variables and numbers, followed by filters that format the
+//! result: `{username | upper}`, `{xp / max_xp * 100 | round}`, `{xp | number:"0,0"}`,
+//! `{name | truncate:16}` or `{title | default:"Guest"}`. Expressions can only read the given variables and
+//! call the filters listed in [`FILTERS`], so templates cannot run code.
+//!
+//! Comparisons (`==`, `!=`, `<`, `<=`, `>`, `>=`), `&&`, `||` and `!` give `true` or `false`, for boolean
+//! fields and layer conditions. A condition is a whole expression rather than a template, in which `{...}` may
+//! wrap variables as in text: `{level} >= 10` and `level >= 10` are the same.
+//!
+//! Variables are strings and are read as numbers where arithmetic needs them. A placeholder that is exactly a
+//! variable name is replaced by its value even if the name is not a valid identifier, as before expressions.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+use thiserror::Error;
+
+/// The filters expressions can use after `|`.
+pub const FILTERS: [&str; 13] = [
+    "upper", "lower", "capitalize", "trim", "truncate", "default", "number", "round", "floor", "ceil", "abs", "clamp",
+    "percent",
+];
+
+/// How deeply parentheses and unary minus may nest, so that hostile templates cannot exhaust the stack.
+const MAX_DEPTH: usize = 32;
+
+/// An expression that failed to parse or evaluate, with the character position where it went wrong.
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("{kind} at position {position}")]
+pub struct ExprError {
+    /// Characters from the start of the template (or of the expression, for [`parse`]).
+    pub position: usize,
+    pub kind: ExprErrorKind,
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ExprErrorKind {
+    #[error("Unclosed '{{'")]
+    UnclosedPlaceholder,
+
+    #[error("Unexpected character '{0}'")]
+    UnexpectedChar(char),
+
+    #[error("Unterminated string")]
+    UnterminatedString,
+
+    #[error("Expected {expected}, found {found}")]
+    Expected { expected: &'static str, found: String },
+
+    #[error("Expression nests too deeply")]
+    TooDeep,
+
+    #[error("Missing variable '{0}'")]
+    MissingVariable(String),
+
+    #[error("Unknown filter '{0}'")]
+    UnknownFilter(String),
+
+    #[error("Filter '{filter}' {message}")]
+    InvalidArgument { filter: String, message: String },
+
+    #[error("'{0}' is not a number")]
+    NotANumber(String),
+
+    #[error("Division by zero")]
+    DivisionByZero,
+}
+
+/// A parsed expression. Positions count characters from the start of the template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr(Node);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Number(f64),
+    Text(String),
+    Variable { name: String, position: usize },
+    Negate { operand: Box<Node>, position: usize },
+    Not { operand: Box<Node>, position: usize },
+    Binary { op: BinaryOp, lhs: Box<Node>, rhs: Box<Node>, position: usize },
+    Filter { input: Box<Node>, name: String, args: Vec<Node>, position: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    And,
+    Or,
+}
+
+/// An intermediate result. Missing variables are only an error once something needs their value, so that
+/// `default` can replace them.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Missing { name: String, position: usize },
+    Number(f64),
+    Text(String),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Ident(String),
+    Symbol(&'static str),
+    End,
+}
+
+/// Part of a template.
+enum Segment {
+    Literal(Range<usize>),
+    /// The characters between the braces.
+    Placeholder(Range<usize>),
+    /// A `{` without its `}`.
+    Unclosed(usize),
+}
+
+/// Replaces every placeholder in `template`, failing on the first one that does not parse or evaluate.
+pub fn render(template: &str, variables: &HashMap<String, String>) -> Result<String, ExprError> {
+    expand(template, variables, true)
+}
+
+/// Replaces the placeholders in `template` that evaluate, and keeps the others as written, such as those
+/// with variables that were not given.
+pub fn substitute(template: &str, variables: &HashMap<String, String>) -> String {
+    expand(template, variables, false).unwrap_or_else(|_| template.to_string())
+}
+
+/// Checks that every placeholder in `template` parses and only uses known filters, without evaluating it.
+pub fn check(template: &str) -> Result<(), ExprError> {
+    let chars: Vec<char> = template.chars().collect();
+    for segment in segments(&chars) {
+        match segment {
+            Segment::Literal(_) => {}
+            Segment::Placeholder(range) => parse_at(&chars[range.clone()], range.start)?.0.check_filters()?,
+            Segment::Unclosed(position) => return Err(ExprError { position, kind: ExprErrorKind::UnclosedPlaceholder }),
+        }
+    }
+    Ok(())
+}
+
+/// Parses the contents of a placeholder, without the braces.
+pub fn parse(expression: &str) -> Result<Expr, ExprError> {
+    let chars: Vec<char> = expression.chars().collect();
+    parse_at(&chars, 0)
+}
+
+/// Evaluates a condition such as `{is_premium} == true` or `{level} >= 10`. Besides `true` and `false`, numbers are true unless zero and text is true unless empty.
+pub fn condition(condition: &str, variables: &HashMap<String, String>) -> Result<bool, ExprError> {
+    parse(condition)?.is_true(variables)
+}
+
+impl Expr {
+    /// Evaluates the expression and formats the result as text.
+    pub fn evaluate(&self, variables: &HashMap<String, String>) -> Result<String, ExprError> {
+        self.0.evaluate(variables)?.into_text()
+    }
+
+    /// Evaluates the expression as a condition.
+    pub fn is_true(&self, variables: &HashMap<String, String>) -> Result<bool, ExprError> {
+        self.0.evaluate(variables)?.into_bool()
+    }
+
+    /// The names of the variables the expression reads, in order of first use.
+    pub fn variables(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.0.collect_variables(&mut names);
+        names
+    }
+}
+
+fn expand(template: &str, variables: &HashMap<String, String>, strict: bool) -> Result<String, ExprError> {
+    let chars: Vec<char> = template.chars().collect();
+    let mut output = String::with_capacity(template.len());
+
+    for segment in segments(&chars) {
+        match segment {
+            Segment::Literal(range) => output.extend(&chars[range]),
+            Segment::Placeholder(range) => {
+                let source: String = chars[range.clone()].iter().collect();
+                let result = match variables.get(&source) {
+                    Some(value) => Ok(value.clone()),
+                    None => parse_at(&chars[range.clone()], range.start).and_then(|expr| expr.evaluate(variables)),
+                };
+                match result {
+                    Ok(text) => output.push_str(&text),
+                    Err(err) if strict => return Err(err),
+                    Err(_) => output.extend(&chars[range.start - 1..=range.end]),
+                }
+            }
+            Segment::Unclosed(position) if strict => {
+                return Err(ExprError { position, kind: ExprErrorKind::UnclosedPlaceholder });
+            }
+            Segment::Unclosed(_) => output.push('{'),
+        }
+    }
+    Ok(output)
+}
+
+/// Splits a template at its placeholders. A placeholder ends at the first `}` outside a string literal; a `{`
+/// met before it leaves the first one unclosed.
+fn segments(chars: &[char]) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut literal_start = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] != '{' {
+            i += 1;
+            continue;
+        }
+        if literal_start < i {
+            segments.push(Segment::Literal(literal_start..i));
+        }
+        match closing_brace(chars, i + 1) {
+            Some(end) => {
+                segments.push(Segment::Placeholder(i + 1..end));
+                i = end + 1;
+            }
+            None => {
+                segments.push(Segment::Unclosed(i));
+                i += 1;
+            }
+        }
+        literal_start = i;
+    }
+    if literal_start < chars.len() {
+        segments.push(Segment::Literal(literal_start..chars.len()));
+    }
+    segments
+}
+
+fn closing_brace(chars: &[char], start: usize) -> Option<usize> {
+    let mut quote = None;
+    let mut i = start;
+    while i < chars.len() {
+        match (quote, chars[i]) {
+            (Some(_), '\\') => i += 1,
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(chars[i]),
+            (None, '}') => return Some(i),
+            (None, '{') => return None,
+            (None, _) => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+fn parse_at(chars: &[char], offset: usize) -> Result<Expr, ExprError> {
+    let tokens = tokenize(chars, offset)?;
+    let mut parser = Parser { tokens, index: 0, depth: 0 };
+    let node = parser.pipeline()?;
+    parser.expect_end()?;
+    Ok(Expr(node))
+}
+
+/// Operators and punctuation, longest first so that `<=` is not read as `<`.
+const SYMBOLS: [&str; 21] =
+    ["==", "!=", "<=", ">=", "&&", "||", "<", ">", "!", "+", "-", "*", "/", "%", "|", ":", ",", "(", ")", "{", "}"];
+
+fn tokenize(chars: &[char], offset: usize) -> Result<Vec<(Token, usize)>, ExprError> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let position = offset + i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let token = if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            if chars.get(i) == Some(&'.') && chars.get(i + 1).is_some_and(char::is_ascii_digit) {
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let digits: String = chars[start..i].iter().collect();
+            Token::Number(digits.parse().expect("digits with at most one dot"))
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                i += 1;
+            }
+            Token::Ident(chars[start..i].iter().collect())
+        } else if c == '"' || c == '\'' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(ExprError { position, kind: ExprErrorKind::UnterminatedString }),
+                    Some(&q) if q == c => break,
+                    Some('\\') if i + 1 < chars.len() => {
+                        text.push(chars[i + 1]);
+                        i += 1;
+                    }
+                    Some(&other) => text.push(other),
+                }
+                i += 1;
+            }
+            i += 1;
+            Token::Text(text)
+        } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| chars[i..].iter().copied().take(symbol.len()).eq(symbol.chars())) {
+            i += symbol.len();
+            Token::Symbol(symbol)
+        } else {
+            return Err(ExprError { position, kind: ExprErrorKind::UnexpectedChar(c) });
+        };
+        tokens.push((token, position));
+    }
+
+    tokens.push((Token::End, offset + chars.len()));
+    Ok(tokens)
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{n}"),
+            Token::Text(text) => write!(f, "{text:?}"),
+            Token::Ident(name) => write!(f, "'{name}'"),
+            Token::Symbol(symbol) => write!(f, "'{symbol}'"),
+            Token::End => f.write_str("end of expression"),
+        }
+    }
+}
+
+/// A recursive descent parser. From loosest to tightest: `|` filters, `||`, `&&`, comparisons, `+` and `-`,
+/// `*`, `/` and `%`, unary `-` and `!`, then numbers, strings, `true` and `false`, variables, parentheses and braces.
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &(Token, usize) {
+        &self.tokens[self.index]
+    }
+
+    fn next(&mut self) -> (Token, usize) {
+        let token = self.tokens[self.index].clone();
+        if token.0 != Token::End {
+            self.index += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, symbol: &str) -> Option<usize> {
+        match self.peek() {
+            (Token::Symbol(found), position) if *found == symbol => {
+                let position = *position;
+                self.index += 1;
+                Some(position)
+            }
+            _ => None,
+        }
+    }
+
+    fn error(&self, expected: &'static str) -> ExprError {
+        let (token, position) = self.peek();
+        ExprError { position: *position, kind: ExprErrorKind::Expected { expected, found: token.to_string() } }
+    }
+
+    fn expect_end(&self) -> Result<(), ExprError> {
+        match self.peek() {
+            (Token::End, _) => Ok(()),
+            _ => Err(self.error("an operator, '|' or the end of the expression")),
+        }
+    }
+
+    fn nest(&mut self, position: usize) -> Result<(), ExprError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(ExprError { position, kind: ExprErrorKind::TooDeep });
+        }
+        Ok(())
+    }
+
+    fn pipeline(&mut self) -> Result<Node, ExprError> {
+        let mut node = self.or()?;
+        while self.eat("|").is_some() {
+            let (name, position) = match self.next() {
+                (Token::Ident(name), position) => (name, position),
+                _ => {
+                    self.index -= 1;
+                    return Err(self.error("a filter name"));
+                }
+            };
+            let mut args = Vec::new();
+            if self.eat(":").is_some() {
+                loop {
+                    args.push(self.unary()?);
+                    if self.eat(",").is_none() {
+                        break;
+                    }
+                }
+            }
+            node = Node::Filter { input: Box::new(node), name, args, position };
+        }
+        Ok(node)
+    }
+
+    fn or(&mut self) -> Result<Node, ExprError> {
+        let mut node = self.and()?;
+        while let Some(position) = self.eat("||") {
+            let rhs = self.and()?;
+            node = Node::Binary { op: BinaryOp::Or, lhs: Box::new(node), rhs: Box::new(rhs), position };
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node, ExprError> {
+        let mut node = self.comparison()?;
+        while let Some(position) = self.eat("&&") {
+            let rhs = self.comparison()?;
+            node = Node::Binary { op: BinaryOp::And, lhs: Box::new(node), rhs: Box::new(rhs), position };
+        }
+        Ok(node)
+    }
+
+    /// At most one comparison, since `a < b < c` would compare a boolean with `c`.
+    fn comparison(&mut self) -> Result<Node, ExprError> {
+        const COMPARISONS: [(&str, BinaryOp); 6] = [
+            ("==", BinaryOp::Equal),
+            ("!=", BinaryOp::NotEqual),
+            ("<=", BinaryOp::LessOrEqual),
+            (">=", BinaryOp::GreaterOrEqual),
+            ("<", BinaryOp::Less),
+            (">", BinaryOp::Greater),
+        ];
+        let node = self.sum()?;
+        for (symbol, op) in COMPARISONS {
+            if let Some(position) = self.eat(symbol) {
+                let rhs = self.sum()?;
+                return Ok(Node::Binary { op, lhs: Box::new(node), rhs: Box::new(rhs), position });
+            }
+        }
+        Ok(node)
+    }
+
+    fn sum(&mut self) -> Result<Node, ExprError> {
+        let mut node = self.product()?;
+        loop {
+            let (op, position) = if let Some(position) = self.eat("+") {
+                (BinaryOp::Add, position)
+            } else if let Some(position) = self.eat("-") {
+                (BinaryOp::Subtract, position)
+            } else {
+                return Ok(node);
+            };
+            let rhs = self.product()?;
+            node = Node::Binary { op, lhs: Box::new(node), rhs: Box::new(rhs), position };
+        }
+    }
+
+    fn product(&mut self) -> Result<Node, ExprError> {
+        let mut node = self.unary()?;
+        loop {
+            let (op, position) = if let Some(position) = self.eat("*") {
+                (BinaryOp::Multiply, position)
+            } else if let Some(position) = self.eat("/") {
+                (BinaryOp::Divide, position)
+            } else if let Some(position) = self.eat("%") {
+                (BinaryOp::Remainder, position)
+            } else {
+                return Ok(node);
+            };
+            let rhs = self.unary()?;
+            node = Node::Binary { op, lhs: Box::new(node), rhs: Box::new(rhs), position };
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node, ExprError> {
+        if let Some(position) = self.eat("-") {
+            self.nest(position)?;
+            let operand = self.unary()?;
+            self.depth -= 1;
+            return Ok(Node::Negate { operand: Box::new(operand), position });
+        }
+        if let Some(position) = self.eat("!") {
+            self.nest(position)?;
+            let operand = self.unary()?;
+            self.depth -= 1;
+            return Ok(Node::Not { operand: Box::new(operand), position });
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Node, ExprError> {
+        match self.next() {
+            (Token::Number(n), _) => Ok(Node::Number(n)),
+            (Token::Text(text), _) => Ok(Node::Text(text)),
+            (Token::Ident(name), _) if name == "true" || name == "false" => Ok(Node::Text(name)),
+            (Token::Ident(name), position) => Ok(Node::Variable { name, position }),
+            (Token::Symbol(open @ ("(" | "{")), position) => {
+                self.nest(position)?;
+                let node = self.pipeline()?;
+                let close = if open == "(" { ")" } else { "}" };
+                if self.eat(close).is_none() {
+                    return Err(self.error(if open == "(" { "')'" } else { "'}'" }));
+                }
+                self.depth -= 1;
+                Ok(node)
+            }
+            (Token::End, _) => Err(self.error("a value")),
+            _ => {
+                self.index -= 1;
+                Err(self.error("a value"))
+            }
+        }
+    }
+}
+
+impl Node {
+    fn check_filters(&self) -> Result<(), ExprError> {
+        match self {
+            Node::Number(_) | Node::Text(_) | Node::Variable { .. } => Ok(()),
+            Node::Negate { operand, .. } | Node::Not { operand, .. } => operand.check_filters(),
+            Node::Binary { lhs, rhs, .. } => {
+                lhs.check_filters()?;
+                rhs.check_filters()
+            }
+            Node::Filter { input, name, args, position } => {
+                if !FILTERS.contains(&name.as_str()) {
+                    return Err(ExprError { position: *position, kind: ExprErrorKind::UnknownFilter(name.clone()) });
+                }
+                input.check_filters()?;
+                args.iter().try_for_each(Node::check_filters)
+            }
+        }
+    }
+
+    fn collect_variables<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Node::Number(_) | Node::Text(_) => {}
+            Node::Variable { name, .. } => {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+            Node::Negate { operand, .. } | Node::Not { operand, .. } => operand.collect_variables(names),
+            Node::Binary { lhs, rhs, .. } => {
+                lhs.collect_variables(names);
+                rhs.collect_variables(names);
+            }
+            Node::Filter { input, args, .. } => {
+                input.collect_variables(names);
+                for arg in args {
+                    arg.collect_variables(names);
+                }
+            }
+        }
+    }
+
+    fn evaluate(&self, variables: &HashMap<String, String>) -> Result<Value, ExprError> {
+        Ok(match self {
+            Node::Number(n) => Value::Number(*n),
+            Node::Text(text) => Value::Text(text.clone()),
+            Node::Variable { name, position } => match variables.get(name) {
+                Some(value) => Value::Text(value.clone()),
+                None => Value::Missing { name: name.clone(), position: *position },
+            },
+            Node::Negate { operand, position } => Value::Number(-operand.evaluate(variables)?.into_number(*position)?),
+            Node::Not { operand, .. } => Value::Bool(!operand.evaluate(variables)?.into_bool()?),
+            // Short-circuits, so that `{has_boost && boost > 1}` needs no `boost` without a boost
+            Node::Binary { op: op @ (BinaryOp::And | BinaryOp::Or), lhs, rhs, .. } => {
+                let lhs = lhs.evaluate(variables)?.into_bool()?;
+                if lhs == (*op == BinaryOp::Or) {
+                    Value::Bool(lhs)
+                } else {
+                    Value::Bool(rhs.evaluate(variables)?.into_bool()?)
+                }
+            }
+            Node::Binary { op: op @ (BinaryOp::Equal | BinaryOp::NotEqual), lhs, rhs, .. } => {
+                let (lhs, rhs) = (lhs.evaluate(variables)?, rhs.evaluate(variables)?);
+                Value::Bool(lhs.equals(rhs)? == (*op == BinaryOp::Equal))
+            }
+            Node::Binary { op, lhs, rhs, position } => {
+                let lhs = lhs.evaluate(variables)?.into_number(*position)?;
+                let rhs = rhs.evaluate(variables)?.into_number(*position)?;
+                let zero_divisor = rhs == 0.0 && matches!(op, BinaryOp::Divide | BinaryOp::Remainder);
+                if zero_divisor {
+                    return Err(ExprError { position: *position, kind: ExprErrorKind::DivisionByZero });
+                }
+                match op {
+                    BinaryOp::Add => Value::Number(lhs + rhs),
+                    BinaryOp::Subtract => Value::Number(lhs - rhs),
+                    BinaryOp::Multiply => Value::Number(lhs * rhs),
+                    BinaryOp::Divide => Value::Number(lhs / rhs),
+                    BinaryOp::Remainder => Value::Number(lhs % rhs),
+                    BinaryOp::Less => Value::Bool(lhs < rhs),
+                    BinaryOp::LessOrEqual => Value::Bool(lhs <= rhs),
+                    BinaryOp::Greater => Value::Bool(lhs > rhs),
+                    BinaryOp::GreaterOrEqual => Value::Bool(lhs >= rhs),
+                    BinaryOp::Equal | BinaryOp::NotEqual | BinaryOp::And | BinaryOp::Or => unreachable!("handled above"),
+                }
+            }
+            Node::Filter { input, name, args, position } => {
+                let input = input.evaluate(variables)?;
+                let args = args.iter().map(|arg| arg.evaluate(variables)).collect::<Result<Vec<_>, _>>()?;
+                apply_filter(name, *position, input, args)?
+            }
+        })
+    }
+}
+
+impl Value {
+    fn into_number(self, position: usize) -> Result<f64, ExprError> {
+        match self {
+            Value::Number(n) => Ok(n),
+            Value::Text(text) => text.trim().parse::<f64>().ok().filter(|n| n.is_finite()).ok_or(ExprError {
+                position,
+                kind: ExprErrorKind::NotANumber(text),
+            }),
+            Value::Bool(value) => Err(ExprError { position, kind: ExprErrorKind::NotANumber(value.to_string()) }),
+            Value::Missing { name, position } => Err(ExprError { position, kind: ExprErrorKind::MissingVariable(name) }),
+        }
+    }
+
+    /// Text that reads as `true` or `false` counts as such; other text is true unless empty.
+    fn into_bool(self) -> Result<bool, ExprError> {
+        match self {
+            Value::Bool(value) => Ok(value),
+            Value::Number(n) => Ok(n != 0.0),
+            Value::Text(text) => Ok(match text.trim() {
+                "true" => true,
+                "false" | "" => false,
+                other => other.parse::<f64>().ok().is_none_or(|n| n != 0.0),
+            }),
+            Value::Missing { name, position } => Err(ExprError { position, kind: ExprErrorKind::MissingVariable(name) }),
+        }
+    }
+
+    /// Compares as numbers when both sides are numbers, and as text otherwise, so that `{flag} == true` and
+    /// `{count} == 3.0` both hold for the values `"true"` and `"3"`.
+    fn equals(self, other: Value) -> Result<bool, ExprError> {
+        let (lhs, rhs) = (self.into_text()?, other.into_text()?);
+        match (lhs.trim().parse::<f64>(), rhs.trim().parse::<f64>()) {
+            (Ok(lhs), Ok(rhs)) => Ok(lhs == rhs),
+            _ => Ok(lhs == rhs),
+        }
+    }
+
+    fn into_text(self) -> Result<String, ExprError> {
+        match self {
+            Value::Number(n) => Ok(format_number(n)),
+            Value::Text(text) => Ok(text),
+            Value::Bool(value) => Ok(value.to_string()),
+            Value::Missing { name, position } => Err(ExprError { position, kind: ExprErrorKind::MissingVariable(name) }),
+        }
+    }
+}
+
+fn apply_filter(name: &str, position: usize, input: Value, args: Vec<Value>) -> Result<Value, ExprError> {
+    let invalid = |message: String| ExprError {
+        position,
+        kind: ExprErrorKind::InvalidArgument { filter: name.to_string(), message },
+    };
+    let count = args.len();
+    let arity = |min: usize, max: usize| {
+        if (min..=max).contains(&count) {
+            Ok(())
+        } else if min == max {
+            Err(invalid(format!("takes {min} argument(s), got {count}")))
+        } else {
+            Err(invalid(format!("takes {min} to {max} arguments, got {count}")))
+        }
+    };
+    let mut args = args.into_iter();
+    let mut number_arg = || args.next().map(|arg| arg.into_number(position)).transpose();
+
+    Ok(match name {
+        "upper" | "lower" | "capitalize" | "trim" => {
+            arity(0, 0)?;
+            let text = input.into_text()?;
+            Value::Text(match name {
+                "upper" => text.to_uppercase(),
+                "lower" => text.to_lowercase(),
+                "trim" => text.trim().to_string(),
+                _ => {
+                    let mut chars = text.chars();
+                    chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default()
+                }
+            })
+        }
+        "truncate" => {
+            arity(1, 2)?;
+            let length = number_arg()?.unwrap_or_default();
+            if length < 0.0 || length.fract() != 0.0 {
+                return Err(invalid(format!("needs a whole, non-negative length, got {length}")));
+            }
+            let suffix = match args.next() {
+                Some(suffix) => suffix.into_text()?,
+                None => "…".to_string(),
+            };
+            let text = input.into_text()?;
+            let length = length as usize;
+            if text.chars().count() <= length {
+                Value::Text(text)
+            } else {
+                let kept = length.saturating_sub(suffix.chars().count());
+                Value::Text(text.chars().take(kept).chain(suffix.chars()).collect())
+            }
+        }
+        "default" => {
+            arity(1, 1)?;
+            let fallback = args.next().expect("one argument");
+            match input {
+                Value::Missing { .. } => fallback,
+                Value::Text(text) if text.is_empty() => fallback,
+                value => value,
+            }
+        }
+        "number" => {
+            arity(0, 1)?;
+            let pattern = match args.next() {
+                Some(pattern) => pattern.into_text()?,
+                None => "0,0".to_string(),
+            };
+            let n = input.into_number(position)?;
+            Value::Text(format_pattern(n, &pattern).ok_or_else(|| invalid(format!("cannot read the pattern {pattern:?}")))?)
+        }
+        "round" => {
+            arity(0, 1)?;
+            let digits = number_arg()?.unwrap_or_default().clamp(0.0, 10.0) as i32;
+            let scale = 10f64.powi(digits);
+            Value::Number((input.into_number(position)? * scale).round() / scale)
+        }
+        "floor" | "ceil" | "abs" => {
+            arity(0, 0)?;
+            let n = input.into_number(position)?;
+            Value::Number(match name {
+                "floor" => n.floor(),
+                "ceil" => n.ceil(),
+                _ => n.abs(),
+            })
+        }
+        "clamp" => {
+            arity(2, 2)?;
+            let (min, max) = (number_arg()?.unwrap_or_default(), number_arg()?.unwrap_or_default());
+            if min > max {
+                return Err(invalid(format!("needs a minimum below the maximum, got {min} and {max}")));
+            }
+            Value::Number(input.into_number(position)?.clamp(min, max))
+        }
+        "percent" => {
+            arity(0, 1)?;
+            let total = number_arg()?.unwrap_or(1.0);
+            if total == 0.0 {
+                return Err(ExprError { position, kind: ExprErrorKind::DivisionByZero });
+            }
+            Value::Number(input.into_number(position)? / total * 100.0)
+        }
+        _ => return Err(ExprError { position, kind: ExprErrorKind::UnknownFilter(name.to_string()) }),
+    })
+}
+
+/// Formats a computed number, rounded to six decimals so that float noise like `0.30000000000000004` stays out
+/// of rendered text.
+fn format_number(n: f64) -> String {
+    let rounded = (n * 1e6).round() / 1e6;
+    if rounded == 0.0 { "0".to_string() } else { rounded.to_string() }
+}
+
+/// Formats a number with a pattern in the style of numeral.js: `0` for whole numbers, `0,0` for thousands
+/// separators, `.00` for a fixed number of decimals and a trailing `a` to abbreviate to `k`, `m`, `b` or `t`.
+fn format_pattern(n: f64, pattern: &str) -> Option<String> {
+    const UNITS: [(f64, &str); 5] = [(1.0, ""), (1e3, "k"), (1e6, "m"), (1e9, "b"), (1e12, "t")];
+
+    let (pattern, abbreviate) = match pattern.strip_suffix('a') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let (integer, fraction) = pattern.split_once('.').unwrap_or((pattern, ""));
+    if !integer.starts_with('0') || !integer.chars().all(|c| c == '0' || c == ',') || !fraction.chars().all(|c| c == '0') {
+        return None;
+    }
+    let decimals = fraction.len();
+
+    let mut unit = if abbreviate { UNITS.iter().rposition(|(size, _)| n.abs() >= *size).unwrap_or(0) } else { 0 };
+    let mut fixed = format!("{:.*}", decimals, n / UNITS[unit].0);
+    // Rounding may carry into the next unit, as 999,950 does to 1000.0k
+    if abbreviate && unit + 1 < UNITS.len() && fixed.parse::<f64>().is_ok_and(|value| value.abs() >= 1000.0) {
+        unit += 1;
+        fixed = format!("{:.*}", decimals, n / UNITS[unit].0);
+    }
+    if fixed.parse::<f64>() == Ok(0.0) {
+        fixed = fixed.trim_start_matches('-').to_string();
+    }
+
+    let (sign, digits) = match fixed.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", fixed.as_str()),
+    };
+    let (whole, decimal) = match digits.split_once('.') {
+        Some((whole, decimal)) => (whole, format!(".{decimal}")),
+        None => (digits, String::new()),
+    };
+    let whole = if integer.contains(',') {
+        let mut grouped = String::new();
+        for (i, c) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(c);
+        }
+        grouped
+    } else {
+        whole.to_string()
+    };
+    Some(format!("{sign}{whole}{decimal}{}", UNITS[unit].1))
+}
@@ -0,0 +1,157 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! Paints for color fields: a solid color or a linear or radial gradient.
+
+use serde::{Deserialize, Serialize};
+
+/// A solid color or gradient. Solid colors serialize as a plain string, so templates written
+/// before gradients existed keep loading unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+pub enum Fill {
+    Solid(String),
+    Gradient(Gradient),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Gradient {
+    Linear(LinearGradient),
+    Radial(RadialGradient),
+}
+
+/// A gradient along a line through the center of the box, like CSS `linear-gradient()`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LinearGradient {
+    /// Direction in degrees: 0 points up, 90 to the right. Defaults to 180 (top to bottom), as in CSS.
+    #[serde(default = "default_angle")]
+    pub angle: f32,
+    pub stops: Vec<GradientStop>,
+}
+
+/// A circular gradient reaching the farthest corner of the box, like CSS
+/// `radial-gradient(circle farthest-corner at x y, ...)`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RadialGradient {
+    /// Horizontal position of the center as a fraction of the box width. Defaults to 0.5.
+    #[serde(default = "default_center")]
+    pub center_x: f32,
+    /// Vertical position of the center as a fraction of the box height. Defaults to 0.5.
+    #[serde(default = "default_center")]
+    pub center_y: f32,
+    pub stops: Vec<GradientStop>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GradientStop {
+    pub color: String,
+    /// Position along the gradient from 0.0 to 1.0.
+    pub offset: f32,
+}
+
+fn default_angle() -> f32 {
+    180.0
+}
+
+fn default_center() -> f32 {
+    0.5
+}
+
+impl Default for Fill {
+    fn default() -> Self {
+        Fill::Solid(String::new())
+    }
+}
+
+impl From<&str> for Fill {
+    fn from(color: &str) -> Self {
+        Fill::Solid(color.to_string())
+    }
+}
+
+impl From<String> for Fill {
+    fn from(color: String) -> Self {
+        Fill::Solid(color)
+    }
+}
+
+impl Gradient {
+    pub fn stops(&self) -> &[GradientStop] {
+        match self {
+            Gradient::Linear(linear) => &linear.stops,
+            Gradient::Radial(radial) => &radial.stops,
+        }
+    }
+
+    pub fn stops_mut(&mut self) -> &mut Vec<GradientStop> {
+        match self {
+            Gradient::Linear(linear) => &mut linear.stops,
+            Gradient::Radial(radial) => &mut radial.stops,
+        }
+    }
+}
+
+impl LinearGradient {
+    /// Start and end of the gradient line over a `width` x `height` box, as in CSS: through the center
+    /// and long enough that the corners get the end colors.
+    pub fn endpoints(&self, width: f32, height: f32) -> ([f32; 2], [f32; 2]) {
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        let half_length = (width * sin.abs() + height * cos.abs()) / 2.0;
+        let (cx, cy) = (width / 2.0, height / 2.0);
+        (
+            [cx - sin * half_length, cy + cos * half_length],
+            [cx + sin * half_length, cy - cos * half_length],
+        )
+    }
+}
+
+impl RadialGradient {
+    /// Center and radius of the gradient over a `width` x `height` box; the radius reaches the farthest corner.
+    pub fn geometry(&self, width: f32, height: f32) -> ([f32; 2], f32) {
+        let center = [self.center_x * width, self.center_y * height];
+        let radius = [(0.0, 0.0), (width, 0.0), (0.0, height), (width, height)]
+            .into_iter()
+            .map(|(x, y)| ((x - center[0]).powi(2) + (y - center[1]).powi(2)).sqrt())
+            .fold(0.0, f32::max);
+        (center, radius)
+    }
+}
+
+impl Fill {
+    pub fn as_solid(&self) -> Option<&str> {
+        match self {
+            Fill::Solid(color) => Some(color),
+            Fill::Gradient(_) => None,
+        }
+    }
+
+    /// The solid color, or the first stop of a gradient. Used where only a single color makes sense,
+    /// such as text decorations and color pickers.
+    pub fn primary_color(&self) -> &str {
+        match self {
+            Fill::Solid(color) => color,
+            Fill::Gradient(gradient) => gradient.stops().first().map_or("", |stop| stop.color.as_str()),
+        }
+    }
+
+    /// Every color string in the fill, for variable substitution.
+    pub fn colors_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Fill::Solid(color) => vec![color],
+            Fill::Gradient(gradient) => gradient.stops_mut().iter_mut().map(|stop| &mut stop.color).collect(),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! Color adjustments and blur for images, following the CSS `filter` functions.
+//!
+//! Every filter but blur is a color matrix as defined by the Filter Effects spec, so that the raster renderer
+//! can apply exactly what browsers do and the HTML renderers can fall back to an SVG `<feColorMatrix>`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::Rgba;
+
+/// An adjustment applied to an image's pixels. Filters apply in order, like the functions of a CSS `filter`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ImageFilter {
+    /// Removes color: 0 leaves the image unchanged, 1 makes it fully gray.
+    Grayscale { amount: f32 },
+    /// Browns the image: 0 leaves it unchanged, 1 is fully sepia.
+    Sepia { amount: f32 },
+    /// Inverts colors: 0 leaves the image unchanged, 1 is a negative.
+    Invert { amount: f32 },
+    /// Multiplies colors: 1 leaves the image unchanged, 0 makes it black.
+    Brightness { amount: f32 },
+    /// Scales colors away from mid-gray: 1 leaves the image unchanged, 0 makes it gray.
+    Contrast { amount: f32 },
+    /// 1 leaves the image unchanged, 0 removes all color and higher values make colors stronger.
+    Saturate { amount: f32 },
+    /// Turns every hue around the color wheel.
+    HueRotate { degrees: f32 },
+    /// Recolors the image in shades of `color`, from black at dark pixels to the color at white ones.
+    /// `amount` mixes between the original (0) and the tinted image (1).
+    Tint { color: String, amount: f32 },
+    /// Gaussian blur. Like the layer effects, `radius` is a shadow blur radius, i.e. a standard deviation of half of it.
+    Blur { radius: f32 },
+}
+
+/// A 4x5 matrix in the layout of SVG `<feColorMatrix type="matrix">`: one row per output channel (red, green,
+/// blue, alpha), each with a weight per input channel followed by an offset. Channels range from 0 to 1 and
+/// are not premultiplied.
+pub type ColorMatrix = [f32; 20];
+
+impl ImageFilter {
+    /// The filter as a color matrix, or `None` for blur and tints with an invalid color.
+    pub fn color_matrix(&self) -> Option<ColorMatrix> {
+        let matrix = match self {
+            ImageFilter::Grayscale { amount } => {
+                let k = 1.0 - amount.clamp(0.0, 1.0);
+                rgb_matrix([
+                    [0.2126 + 0.7874 * k, 0.7152 - 0.7152 * k, 0.0722 - 0.0722 * k],
+                    [0.2126 - 0.2126 * k, 0.7152 + 0.2848 * k, 0.0722 - 0.0722 * k],
+                    [0.2126 - 0.2126 * k, 0.7152 - 0.7152 * k, 0.0722 + 0.9278 * k],
+                ])
+            }
+            ImageFilter::Sepia { amount } => {
+                let k = 1.0 - amount.clamp(0.0, 1.0);
+                rgb_matrix([
+                    [0.393 + 0.607 * k, 0.769 - 0.769 * k, 0.189 - 0.189 * k],
+                    [0.349 - 0.349 * k, 0.686 + 0.314 * k, 0.168 - 0.168 * k],
+                    [0.272 - 0.272 * k, 0.534 - 0.534 * k, 0.131 + 0.869 * k],
+                ])
+            }
+            ImageFilter::Invert { amount } => {
+                let amount = amount.clamp(0.0, 1.0);
+                linear_matrix(1.0 - 2.0 * amount, amount)
+            }
+            ImageFilter::Brightness { amount } => linear_matrix(amount.max(0.0), 0.0),
+            ImageFilter::Contrast { amount } => {
+                let amount = amount.max(0.0);
+                linear_matrix(amount, 0.5 - 0.5 * amount)
+            }
+            ImageFilter::Saturate { amount } => {
+                let s = amount.max(0.0);
+                rgb_matrix([
+                    [0.213 + 0.787 * s, 0.715 - 0.715 * s, 0.072 - 0.072 * s],
+                    [0.213 - 0.213 * s, 0.715 + 0.285 * s, 0.072 - 0.072 * s],
+                    [0.213 - 0.213 * s, 0.715 - 0.715 * s, 0.072 + 0.928 * s],
+                ])
+            }
+            ImageFilter::HueRotate { degrees } => {
+                let (sin, cos) = degrees.to_radians().sin_cos();
+                rgb_matrix([
+                    [0.213 + cos * 0.787 - sin * 0.213, 0.715 - cos * 0.715 - sin * 0.715, 0.072 - cos * 0.072 + sin * 0.928],
+                    [0.213 - cos * 0.213 + sin * 0.143, 0.715 + cos * 0.285 + sin * 0.140, 0.072 - cos * 0.072 - sin * 0.283],
+                    [0.213 - cos * 0.213 - sin * 0.787, 0.715 - cos * 0.715 + sin * 0.715, 0.072 + cos * 0.928 + sin * 0.072],
+                ])
+            }
+            ImageFilter::Tint { color, amount } => {
+                let color = Rgba::parse(color).ok()?;
+                let k = 1.0 - amount.clamp(0.0, 1.0);
+                let row = |channel: u8, identity: usize| {
+                    let c = channel as f32 / 255.0 * (1.0 - k);
+                    let mut row = [0.2126 * c, 0.7152 * c, 0.0722 * c];
+                    row[identity] += k;
+                    row
+                };
+                rgb_matrix([row(color.r, 0), row(color.g, 1), row(color.b, 2)])
+            }
+            ImageFilter::Blur { .. } => return None,
+        };
+        Some(matrix)
+    }
+
+    /// The CSS `filter` function, or `None` for tints, which have no CSS equivalent and need [`Self::color_matrix`].
+    pub fn css_function(&self) -> Option<String> {
+        Some(match self {
+            ImageFilter::Grayscale { amount } => format!("grayscale({amount})"),
+            ImageFilter::Sepia { amount } => format!("sepia({amount})"),
+            ImageFilter::Invert { amount } => format!("invert({amount})"),
+            ImageFilter::Brightness { amount } => format!("brightness({amount})"),
+            ImageFilter::Contrast { amount } => format!("contrast({amount})"),
+            ImageFilter::Saturate { amount } => format!("saturate({amount})"),
+            ImageFilter::HueRotate { degrees } => format!("hue-rotate({degrees}deg)"),
+            ImageFilter::Blur { radius } => format!("blur({}px)", radius / 2.0),
+            ImageFilter::Tint { .. } => return None,
+        })
+    }
+}
+
+/// Applies a color matrix to a pixel with channels from 0 to 1, clamping the result.
+pub fn apply_color_matrix(matrix: &ColorMatrix, [r, g, b, a]: [f32; 4]) -> [f32; 4] {
+    std::array::from_fn(|row| {
+        let m = &matrix[row * 5..row * 5 + 5];
+        (m[0] * r + m[1] * g + m[2] * b + m[3] * a + m[4]).clamp(0.0, 1.0)
+    })
+}
+
+/// A matrix mixing the color channels with the given weights and keeping alpha.
+fn rgb_matrix(rows: [[f32; 3]; 3]) -> ColorMatrix {
+    let mut matrix = [0.0; 20];
+    for (i, row) in rows.iter().enumerate() {
+        matrix[i * 5..i * 5 + 3].copy_from_slice(row);
+    }
+    matrix[18] = 1.0;
+    matrix
+}
+
+/// A matrix mapping every color channel `c` to `slope * c + intercept` and keeping alpha.
+fn linear_matrix(slope: f32, intercept: f32) -> ColorMatrix {
+    let mut matrix = rgb_matrix([[slope, 0.0, 0.0], [0.0, slope, 0.0], [0.0, 0.0, slope]]);
+    for row in 0..3 {
+        matrix[row * 5 + 4] = intercept;
+    }
+    matrix
+}
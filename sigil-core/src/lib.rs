@@ -9,113 +9,1103 @@
 */
 
 
+mod background;
+mod bindable;
+pub mod color;
+pub mod expr;
+mod fill;
+mod filter;
+mod mask;
+mod migration;
+pub mod path;
+mod repeat;
+#[cfg(feature = "schema")]
+mod schema;
+mod shape;
+mod validation;
+mod variable;
+
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
+pub use background::{Background, BackgroundImage, BackgroundPattern};
+pub use bindable::Bindable;
+pub use color::{ColorParseError, Rgba};
+pub use expr::{ExprError, ExprErrorKind};
+pub use fill::{Fill, Gradient, GradientStop, LinearGradient, RadialGradient};
+pub use filter::{apply_color_matrix, ColorMatrix, ImageFilter};
+pub use mask::{LayerMask, MaskMode, MaskSource};
+pub use migration::{migrate, MigrationError, FORMAT_VERSION};
+pub use path::{PathDataError, PathSegment};
+pub use repeat::{RepeatDirection, RepeatItem};
+#[cfg(feature = "schema")]
+pub use schema::json_schema;
+pub use shape::{EllipseItem, FillRule, LineCap, LineItem, PathItem, PolygonItem, RegularPolygonItem, StarItem, MAX_CORNERS};
+pub use validation::{Severity, ValidationIssue};
+pub use variable::{variables_from_json, VariableDef, VariableError, VariableType};
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Sigil {
+    /// The version of the JSON format. Templates from before versioning have none and read as 0; load stored
+    /// templates with [`migrate`] to upgrade them to [`FORMAT_VERSION`].
+    #[serde(default)]
+    pub version: u32,
     pub width: u32,
     pub height: u32,
-    pub background: String,
+    pub background: Background,
+    /// The variables the template uses, with their types and defaults.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variables: Vec<VariableDef>,
     pub layers: Vec<Layer>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Layer {
     pub id: String,
-    pub x: f32,
-    pub y: f32,
+    pub x: Bindable<f32>,
+    pub y: Bindable<f32>,
     #[serde(default)]
-    pub rotation: f32,
+    pub rotation: Bindable<f32>,
     #[serde(default = "default_true")]
-    pub visible: bool,
+    pub visible: Bindable<bool>,
+    /// Opacity of the whole layer from 0.0 to 1.0, applied after the item is drawn like CSS `opacity`.
+    #[serde(default = "default_opacity")]
+    pub opacity: Bindable<f32>,
+    /// How the layer is composited onto the layers below it, like CSS `mix-blend-mode`.
+    #[serde(default)]
+    pub blend_mode: BlendMode,
+    /// Shadows and blurs drawn with the layer.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub effects: Vec<Effect>,
+    /// Limits where the layer shows, together with its effects.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mask: Option<LayerMask>,
+    /// An expression such as `{is_premium} == true` or `{level} >= 10`. [`Sigil::resolve`] removes the layer,
+    /// with its children, when it does not hold.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
     pub item: Item,
 }
 
-fn default_true() -> bool {
-    true
+fn default_true() -> Bindable<bool> {
+    Bindable::new(true)
+}
+
+fn default_opacity() -> Bindable<f32> {
+    Bindable::new(1.0)
+}
+
+impl Layer {
+    /// A visible, fully opaque layer at the origin, without effects, mask or condition.
+    pub fn new(id: impl Into<String>, item: Item) -> Self {
+        Self {
+            id: id.into(),
+            x: 0.0.into(),
+            y: 0.0.into(),
+            rotation: 0.0.into(),
+            visible: true.into(),
+            opacity: 1.0.into(),
+            blend_mode: BlendMode::Normal,
+            effects: Vec::new(),
+            mask: None,
+            condition: None,
+            item,
+        }
+    }
+
+    /// Whether the layer can be drawn straight onto the canvas instead of through an intermediate layer.
+    pub fn is_opaque_normal(&self) -> bool {
+        *self.opacity >= 1.0 && self.blend_mode == BlendMode::Normal && self.effects.is_empty() && self.mask.is_none()
+    }
+
+    /// Whether the layer has no condition or its condition holds. A condition that does not parse, or that reads
+    /// a missing variable, does not hold, so a template shows no optional layers when its data is incomplete.
+    pub fn condition_holds(&self, variables: &HashMap<String, String>) -> bool {
+        self.condition.as_deref().is_none_or(|condition| expr::condition(condition, variables).unwrap_or(false))
+    }
+
+    /// The layer followed by the layers nested in it, including repeater templates, depth first.
+    pub fn flatten(&self) -> Vec<&Layer> {
+        let mut layers = vec![self];
+        if let Item::Group(GroupItem { children, .. }) | Item::Repeat(RepeatItem { template: children, .. }) = &self.item {
+            layers.extend(children.iter().flat_map(Layer::flatten));
+        }
+        layers
+    }
+}
+
+/// Calls `f` on every layer, including the ones nested in groups and repeaters, parents before their children.
+fn for_each_layer_mut(layers: &mut [Layer], f: &mut impl FnMut(&mut Layer)) {
+    for layer in layers {
+        f(layer);
+        if let Item::Group(GroupItem { children, .. }) | Item::Repeat(RepeatItem { template: children, .. }) = &mut layer.item {
+            for_each_layer_mut(children, f);
+        }
+    }
+}
+
+/// Separable and non-separable blend modes from the CSS Compositing spec.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
+
+impl BlendMode {
+    pub const ALL: [BlendMode; 16] = [
+        BlendMode::Normal,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+        BlendMode::Overlay,
+        BlendMode::Darken,
+        BlendMode::Lighten,
+        BlendMode::ColorDodge,
+        BlendMode::ColorBurn,
+        BlendMode::HardLight,
+        BlendMode::SoftLight,
+        BlendMode::Difference,
+        BlendMode::Exclusion,
+        BlendMode::Hue,
+        BlendMode::Saturation,
+        BlendMode::Color,
+        BlendMode::Luminosity,
+    ];
+
+    /// The CSS `mix-blend-mode` keyword.
+    pub fn css_name(self) -> &'static str {
+        match self {
+            BlendMode::Normal => "normal",
+            BlendMode::Multiply => "multiply",
+            BlendMode::Screen => "screen",
+            BlendMode::Overlay => "overlay",
+            BlendMode::Darken => "darken",
+            BlendMode::Lighten => "lighten",
+            BlendMode::ColorDodge => "color-dodge",
+            BlendMode::ColorBurn => "color-burn",
+            BlendMode::HardLight => "hard-light",
+            BlendMode::SoftLight => "soft-light",
+            BlendMode::Difference => "difference",
+            BlendMode::Exclusion => "exclusion",
+            BlendMode::Hue => "hue",
+            BlendMode::Saturation => "saturation",
+            BlendMode::Color => "color",
+            BlendMode::Luminosity => "luminosity",
+        }
+    }
+
+    /// Parses a CSS `mix-blend-mode` keyword.
+    pub fn from_css_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.css_name() == name)
+    }
+}
+
+/// A layer effect, applied to everything the layer draws. Blur radii are CSS `box-shadow` blur radii,
+/// i.e. a Gaussian with a standard deviation of half the radius.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Effect {
+    /// Shadow cast behind the layer, like `box-shadow`.
+    DropShadow(Shadow),
+    /// Shadow cast inside the layer's edges, like an `inset` `box-shadow`.
+    InnerShadow(Shadow),
+    /// Blurs the layer itself, including its shadows.
+    LayerBlur { radius: Bindable<f32> },
+    /// Blurs whatever is behind the layer, within its shape, like `backdrop-filter`.
+    BackgroundBlur { radius: Bindable<f32> },
+}
+
+/// Offsets follow the layer's rotation; a positive `spread` grows the shadow's shape and a negative one shrinks it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Shadow {
+    pub color: String,
+    #[serde(default)]
+    pub offset_x: Bindable<f32>,
+    #[serde(default)]
+    pub offset_y: Bindable<f32>,
+    #[serde(default)]
+    pub blur: Bindable<f32>,
+    #[serde(default)]
+    pub spread: Bindable<f32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", content = "data")]
+// Items live in layer lists rather than being moved around, so their size doesn't matter.
+#[allow(clippy::large_enum_variant)]
 pub enum Item {
     Text(TextItem),
     Image(ImageItem),
     Rect(RectItem),
     Slider(SliderItem),
+    Ellipse(EllipseItem),
+    Line(LineItem),
+    Polygon(PolygonItem),
+    Star(StarItem),
+    RegularPolygon(RegularPolygonItem),
+    Path(PathItem),
+    Group(GroupItem),
+    Repeat(RepeatItem),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+impl Item {
+    /// Size of the item's box, or `None` for text, whose size depends on its layout. A repeater's box is one cell.
+    pub fn size(&self) -> Option<(f32, f32)> {
+        match self {
+            Item::Text(_) => None,
+            Item::Image(img) => Some((*img.width, *img.height)),
+            Item::Rect(rect) => Some((*rect.width, *rect.height)),
+            Item::Slider(slider) => Some((*slider.width, *slider.height)),
+            Item::Ellipse(ellipse) => Some((*ellipse.width, *ellipse.height)),
+            Item::Line(line) => Some(line.size()),
+            Item::Polygon(polygon) => Some(polygon.size()),
+            Item::Star(star) => Some((*star.width, *star.height)),
+            Item::RegularPolygon(polygon) => Some((*polygon.width, *polygon.height)),
+            Item::Path(path) => Some((*path.width, *path.height)),
+            Item::Group(group) => Some((*group.width, *group.height)),
+            Item::Repeat(repeat) => Some((*repeat.width, *repeat.height)),
+        }
+    }
+
+    /// Resizes the item's box, scaling lines and polygons to fit. Text, group children and repeater templates are left alone.
+    pub fn resize(&mut self, width: f32, height: f32) {
+        match self {
+            Item::Text(_) => {}
+            Item::Image(img) => (img.width, img.height) = (width.into(), height.into()),
+            Item::Rect(rect) => (rect.width, rect.height) = (width.into(), height.into()),
+            Item::Slider(slider) => (slider.width, slider.height) = (width.into(), height.into()),
+            Item::Ellipse(ellipse) => (ellipse.width, ellipse.height) = (width.into(), height.into()),
+            Item::Line(line) => line.resize(width, height),
+            Item::Polygon(polygon) => polygon.resize(width, height),
+            Item::Star(star) => (star.width, star.height) = (width.into(), height.into()),
+            Item::RegularPolygon(polygon) => (polygon.width, polygon.height) = (width.into(), height.into()),
+            Item::Path(path) => (path.width, path.height) = (width.into(), height.into()),
+            Item::Group(group) => (group.width, group.height) = (width.into(), height.into()),
+            Item::Repeat(repeat) => (repeat.width, repeat.height) = (width.into(), height.into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TextItem {
     pub text: String,
-    pub font_size: f32,
-    pub color: String,
+    pub font_size: Bindable<f32>,
+    pub color: Fill,
     pub font_family: String,
+    /// Width of the text box. Lines longer than this wrap; without it the text is laid out on a single line per paragraph.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_width: Option<Bindable<f32>>,
+    /// Height of the text box. Lines that do not fit are dropped and `vertical_align` positions the text inside it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_height: Option<Bindable<f32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_lines: Option<Bindable<u32>>,
+    /// Line height as a multiple of `font_size`, like the unitless CSS `line-height`. Defaults to 1.2.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_height: Option<Bindable<f32>>,
+    #[serde(default)]
+    pub align: TextAlign,
+    #[serde(default)]
+    pub vertical_align: VerticalAlign,
+    /// Shrinks the font to fit a box instead of using `font_size` as is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fit: Option<TextFit>,
+    /// CSS-style numeric weight from 100 (thin) to 900 (black). Defaults to 400.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font_weight: Option<Bindable<u16>>,
+    #[serde(default)]
+    pub font_style: FontStyle,
+    /// Extra space between characters in pixels.
+    #[serde(default)]
+    pub letter_spacing: Bindable<f32>,
+    #[serde(default)]
+    pub underline: Bindable<bool>,
+    #[serde(default)]
+    pub strikethrough: Bindable<bool>,
+    #[serde(default)]
+    pub text_transform: TextTransform,
+    /// Outline drawn centered on the glyph edges, on top of the fill.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stroke: Option<TextStroke>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadow: Option<TextShadow>,
+    /// Styled runs laid out as one paragraph in place of `text`. Ignored when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<TextSpan>,
+}
+
+/// A run of text inside a `TextItem` with its own styling; unset fields inherit from the item.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TextSpan {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font_weight: Option<Bindable<u16>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font_size: Option<Bindable<f32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font_family: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TextStroke {
+    pub color: String,
+    pub width: Bindable<f32>,
+}
+
+/// Shadow behind the text; offsets follow the layer's rotation and `blur` is a CSS blur radius.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TextShadow {
+    pub color: String,
+    #[serde(default)]
+    pub offset_x: Bindable<f32>,
+    #[serde(default)]
+    pub offset_y: Bindable<f32>,
+    #[serde(default)]
+    pub blur: Bindable<f32>,
+}
+
+pub const DEFAULT_LINE_HEIGHT: f32 = 1.2;
+pub const DEFAULT_FONT_WEIGHT: u16 = 400;
+
+impl TextItem {
+    pub fn line_height_px(&self) -> f32 {
+        *self.font_size * self.line_height.as_deref().copied().unwrap_or(DEFAULT_LINE_HEIGHT)
+    }
+
+    /// The text as it should be drawn, with `text_transform` applied.
+    pub fn display_text(&self) -> String {
+        self.text_transform.apply(&self.plain_text())
+    }
+
+    /// The unstyled text of the item: `text`, or the concatenated span texts when spans are used.
+    pub fn plain_text(&self) -> String {
+        if self.spans.is_empty() {
+            self.text.clone()
+        } else {
+            self.spans.iter().map(|span| span.text.as_str()).collect()
+        }
+    }
+
+    /// The spans as they should be drawn, with `text_transform` applied. Plain text yields a single unstyled span.
+    pub fn display_spans(&self) -> Vec<TextSpan> {
+        if self.spans.is_empty() {
+            return vec![TextSpan { text: self.display_text(), ..Default::default() }];
+        }
+
+        self.spans
+            .iter()
+            .map(|span| TextSpan { text: self.text_transform.apply(&span.text), ..span.clone() })
+            .collect()
+    }
+
+    /// Largest font size used by the item or any of its spans.
+    pub fn max_font_size(&self) -> f32 {
+        self.spans.iter().filter_map(|span| span.font_size.as_deref().copied()).fold(*self.font_size, f32::max)
+    }
+
+    /// Changes the font size, scaling explicit span sizes by the same factor so they keep their proportions.
+    pub fn set_font_size(&mut self, font_size: f32) {
+        if *self.font_size > 0.0 {
+            let scale = font_size / *self.font_size;
+            for span in &mut self.spans {
+                if let Some(size) = &mut span.font_size {
+                    size.set(**size * scale);
+                }
+            }
+        }
+        self.font_size.set(font_size);
+    }
+
+    /// Replaces the fit settings with the measured outcome, turning this into a plain fixed-size text box.
+    pub fn apply_fit(&mut self, fitted: &FittedText) {
+        if let Some(fit) = self.fit.take() {
+            self.max_width = Some(fit.box_width);
+            self.max_height = Some(fit.box_height);
+        }
+        self.set_font_size(fitted.font_size);
+        self.text = fitted.text.clone();
+        if !self.spans.is_empty() {
+            self.spans = fitted.spans.clone();
+        }
+    }
+}
+
+/// Auto-fit settings: the renderer picks the largest font size in `min_font_size..=max_font_size`
+/// at which the wrapped text fits in `box_width` x `box_height`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TextFit {
+    pub min_font_size: Bindable<f32>,
+    pub max_font_size: Bindable<f32>,
+    pub box_width: Bindable<f32>,
+    pub box_height: Bindable<f32>,
+    /// Truncate with "…" when the text still overflows at `min_font_size`.
+    #[serde(default)]
+    pub ellipsis: bool,
+}
+
+/// Outcome of fitting a text item, as chosen by a renderer that can measure text.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FittedText {
+    pub font_size: f32,
+    /// The text actually drawn, ellipsized if it did not fit at the minimum size.
+    pub text: String,
+    /// The spans actually drawn, for items using rich text. Sizes are already scaled to `font_size`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<TextSpan>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum FontStyle {
+    #[default]
+    Normal,
+    Italic,
+    Oblique,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TextTransform {
+    #[default]
+    None,
+    Uppercase,
+    Lowercase,
+    Capitalize,
+}
+
+impl TextTransform {
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            TextTransform::None => text.to_string(),
+            TextTransform::Uppercase => text.to_uppercase(),
+            TextTransform::Lowercase => text.to_lowercase(),
+            TextTransform::Capitalize => {
+                let mut result = String::with_capacity(text.len());
+                let mut at_word_start = true;
+                for c in text.chars() {
+                    if at_word_start && c.is_alphabetic() {
+                        result.extend(c.to_uppercase());
+                        at_word_start = false;
+                    } else {
+                        result.push(c);
+                        if c.is_whitespace() {
+                            at_word_start = true;
+                        }
+                    }
+                }
+                result
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    Justify,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ImageItem {
     pub source: String,
-    pub width: f32,
-    pub height: f32,
-    pub border_radius: f32,
+    pub width: Bindable<f32>,
+    pub height: Bindable<f32>,
+    pub border_radius: Bindable<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stroke: Option<ShapeStroke>,
+    /// How the image is sized into the item's box, like CSS `object-fit`.
+    #[serde(default)]
+    pub fit: ImageFit,
+    /// Where the image sits in the box when it does not fill it exactly, as fractions of the leftover space
+    /// like CSS `object-position` percentages. `[0.5, 0.5]` centers it; with `cover` it is the point kept in view.
+    #[serde(default = "default_focal_point")]
+    pub focal_point: [Bindable<f32>; 2],
+    /// Part of the source image to show instead of the whole image, in source pixels. Must lie within the image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crop: Option<ImageCrop>,
+    /// Color adjustments and blur, applied in order to the image as laid out in its box.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<ImageFilter>,
+}
+
+fn default_focal_point() -> [Bindable<f32>; 2] {
+    [Bindable::new(0.5), Bindable::new(0.5)]
+}
+
+/// How an image is sized into its box, named after the CSS `object-fit` values.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ImageFit {
+    /// Stretched to the box, ignoring its aspect ratio.
+    #[default]
+    Fill,
+    /// Scaled to fit inside the box, leaving empty bands.
+    Contain,
+    /// Scaled to cover the box, cropping the overflow.
+    Cover,
+    /// Drawn at its own size.
+    None,
+    /// Like `None`, or `Contain` if that is smaller.
+    ScaleDown,
+}
+
+impl ImageFit {
+    pub const ALL: [ImageFit; 5] = [ImageFit::Fill, ImageFit::Contain, ImageFit::Cover, ImageFit::None, ImageFit::ScaleDown];
+
+    /// The CSS `object-fit` keyword.
+    pub fn css_name(self) -> &'static str {
+        match self {
+            ImageFit::Fill => "fill",
+            ImageFit::Contain => "contain",
+            ImageFit::Cover => "cover",
+            ImageFit::None => "none",
+            ImageFit::ScaleDown => "scale-down",
+        }
+    }
+
+    /// Parses a CSS `object-fit` keyword.
+    pub fn from_css_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|fit| fit.css_name() == name)
+    }
+
+    /// Where a `source` sized image lands in a box of `size`, as `[x, y, width, height]`, with `focal_point`
+    /// placing it like CSS `object-position` percentages.
+    pub fn fit_rect(self, focal_point: [f32; 2], size: [f32; 2], source: [f32; 2]) -> [f32; 4] {
+        let ([box_width, box_height], [source_width, source_height]) = (size, source);
+        if source_width <= 0.0 || source_height <= 0.0 {
+            return [0.0, 0.0, 0.0, 0.0];
+        }
+
+        let contain = f32::min(box_width / source_width, box_height / source_height);
+        let (width, height) = match self {
+            ImageFit::Fill => (box_width, box_height),
+            ImageFit::Contain => (source_width * contain, source_height * contain),
+            ImageFit::Cover => {
+                let cover = f32::max(box_width / source_width, box_height / source_height);
+                (source_width * cover, source_height * cover)
+            }
+            ImageFit::None => (source_width, source_height),
+            ImageFit::ScaleDown => {
+                let scale = contain.min(1.0);
+                (source_width * scale, source_height * scale)
+            }
+        };
+
+        let [focal_x, focal_y] = focal_point;
+        [(box_width - width) * focal_x, (box_height - height) * focal_y, width, height]
+    }
+}
+
+/// A rectangle of the source image, in pixels.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ImageCrop {
+    pub x: Bindable<f32>,
+    pub y: Bindable<f32>,
+    pub width: Bindable<f32>,
+    pub height: Bindable<f32>,
+}
+
+impl ImageItem {
+    /// Where an image of `source_width` x `source_height` (the crop's size, if cropped) lands in the item's box,
+    /// as `[x, y, width, height]`. It may extend past the box, which clips it. Follows CSS `object-fit` and
+    /// `object-position`, so both renderers lay images out alike.
+    pub fn fit_rect(&self, source_width: f32, source_height: f32) -> [f32; 4] {
+        let focal_point = [*self.focal_point[0], *self.focal_point[1]];
+        self.fit.fit_rect(focal_point, [*self.width, *self.height], [source_width, source_height])
+    }
+
+    /// The part of a `width` x `height` source image that is shown, as `[x, y, width, height]`:
+    /// the crop kept within the image, or the whole image.
+    pub fn source_rect(&self, width: f32, height: f32) -> [f32; 4] {
+        match &self.crop {
+            Some(crop) => {
+                let x = crop.x.clamp(0.0, width);
+                let y = crop.y.clamp(0.0, height);
+                [x, y, crop.width.clamp(0.0, width - x), crop.height.clamp(0.0, height - y)]
+            }
+            None => [0.0, 0.0, width, height],
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RectItem {
-    pub width: f32,
-    pub height: f32,
-    pub color: String,
-    pub border_radius: f32,
+    pub width: Bindable<f32>,
+    pub height: Bindable<f32>,
+    pub color: Fill,
+    pub border_radius: Bindable<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stroke: Option<ShapeStroke>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SliderItem {
-    pub width: f32,
-    pub height: f32,
-    pub value: f32,
-    pub max_value: f32,
+    pub width: Bindable<f32>,
+    pub height: Bindable<f32>,
+    pub value: Bindable<f32>,
+    pub max_value: Bindable<f32>,
     pub background_color: String,
-    pub fill_color: String,
-    pub border_radius: f32,
+    pub fill_color: Fill,
+    pub border_radius: Bindable<f32>,
+    /// Outline around the whole track.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stroke: Option<ShapeStroke>,
+}
+
+/// Layers moved, rotated and composited together. Children are positioned relative to the group's top left corner.
+///
+/// The group rotates around the center of its `width` x `height` box; children may extend past it unless `clip` is set.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GroupItem {
+    pub width: Bindable<f32>,
+    pub height: Bindable<f32>,
+    /// Hides whatever the children draw outside the group's box, like CSS `overflow: hidden`.
+    #[serde(default)]
+    pub clip: Bindable<bool>,
+    /// Drawn in order, like `Sigil::layers`.
+    pub children: Vec<Layer>,
+}
+
+/// Outline of a shape item, following its rounded corners or path.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ShapeStroke {
+    pub color: String,
+    pub width: Bindable<f32>,
+    #[serde(default)]
+    pub align: StrokeAlign,
+    /// Alternating dash and gap lengths in pixels, like SVG `stroke-dasharray`. Solid when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dash: Vec<f32>,
+}
+
+/// Where the stroke sits relative to the shape's edge. Defaults to inside, like a CSS border.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum StrokeAlign {
+    #[default]
+    Inside,
+    Center,
+    Outside,
+}
+
+impl ShapeStroke {
+    /// How far the stroke's center line is moved outwards from the shape's edge.
+    pub fn center_offset(&self) -> f32 {
+        match self.align {
+            StrokeAlign::Inside => -*self.width / 2.0,
+            StrokeAlign::Center => 0.0,
+            StrokeAlign::Outside => *self.width / 2.0,
+        }
+    }
 }
 
 
 impl Sigil {
+    /// Evaluates `{...}` placeholders (see [`expr`]), using the declared defaults for variables that are not given.
+    /// Structured data can be given with [`variables_from_json`], or with [`Sigil::resolve_json`].
     pub fn resolve(&self, variables: &HashMap<String, String>) -> Self {
         let mut new_sigil = self.clone();
+        let variables = &self.with_defaults(variables);
 
-        new_sigil.background = replace_vars(&new_sigil.background, variables);
+        resolve_background(&mut new_sigil.background, variables);
 
-        for layer in &mut new_sigil.layers {
-            match &mut layer.item {
-                Item::Text(text) => {
-                    text.text = replace_vars(&text.text, variables);
-                    text.color = replace_vars(&text.color, variables);
-                },
-                Item::Image(img) => {
-                    img.source = replace_vars(&img.source, variables);
-                },
-                Item::Rect(rect) => {
-                    rect.color = replace_vars(&rect.color, variables);
-                },
-                Item::Slider(slider) => {
-                    slider.background_color = replace_vars(&slider.background_color, variables);
-                    slider.fill_color = replace_vars(&slider.fill_color, variables);
+        resolve_layers(&mut new_sigil.layers, variables);
+        new_sigil
+    }
+
+    /// Resolves with the fields of a JSON object as variables, lists and objects included.
+    pub fn resolve_json(&self, data: &serde_json::Value) -> Self {
+        self.resolve(&variables_from_json(data))
+    }
+
+    /// Resolves a single layer of this sigil as [`Sigil::resolve`] would, or `None` if its condition does not hold.
+    pub fn resolve_layer(&self, layer: &Layer, variables: &HashMap<String, String>) -> Option<Layer> {
+        let mut layers = vec![layer.clone()];
+        resolve_layers(&mut layers, &self.with_defaults(variables));
+        layers.pop()
+    }
+
+    /// Applies fit results reported by a renderer (keyed by layer id), so that renderers which cannot
+    /// measure text, like the HTML ones, reproduce the same font sizes.
+    pub fn with_fitted_text(&self, fitted: &HashMap<String, FittedText>) -> Self {
+        let mut new_sigil = self.clone();
+
+        for_each_layer_mut(&mut new_sigil.layers, &mut |layer| {
+            if let (Item::Text(text), Some(fitted)) = (&mut layer.item, fitted.get(&layer.id)) {
+                text.apply_fit(fitted);
+            }
+        });
+        new_sigil
+    }
+
+    /// Declared variables without a default, which callers must give.
+    pub fn required_variables(&self) -> impl Iterator<Item = &VariableDef> {
+        self.variables.iter().filter(|def| def.is_required())
+    }
+
+    /// Checks the caller's values against the declared variables, reporting every problem at once.
+    /// Templates that declare no variables accept any values.
+    pub fn validate_variables(&self, values: &HashMap<String, String>) -> Result<(), Vec<VariableError>> {
+        if self.variables.is_empty() {
+            return Ok(());
+        }
+        variable::validate_variables(&self.variables, values)
+    }
+
+    /// Checks the template for problems that would make rendering fail or draw something unintended, such as
+    /// invalid colors, non-positive sizes, duplicate layer ids and layers outside the canvas.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        validation::validate(self, false)
+    }
+
+    /// Checks a sigil returned by [`Sigil::resolve`] like [`Sigil::validate`], also reporting bindings that could
+    /// not be resolved, which would draw as zero or `false`.
+    pub fn validate_resolved(&self) -> Vec<ValidationIssue> {
+        validation::validate(self, true)
+    }
+
+    /// Checks that text uses known fonts and images name given resources, for a renderer with these font
+    /// families and resource names. Generic families like `sans-serif`, URLs and placeholders always pass.
+    pub fn validate_resources(&self, fonts: &[String], resources: &[String]) -> Vec<ValidationIssue> {
+        validation::validate_resources(self, fonts, resources)
+    }
+
+    /// The given values, plus the defaults of declared variables that are left out.
+    fn with_defaults(&self, variables: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut values = variables.clone();
+        for def in &self.variables {
+            if let Some(default) = &def.default {
+                values.entry(def.name.clone()).or_insert_with(|| default.clone());
+            }
+        }
+        values
+    }
+
+    /// Every layer, including the ones nested in groups, in drawing order.
+    pub fn all_layers(&self) -> impl Iterator<Item = &Layer> {
+        self.layers.iter().flat_map(Layer::flatten)
+    }
+}
+
+fn resolve_background(background: &mut Background, variables: &HashMap<String, String>) {
+    match background {
+        // A variable may stand for a color or, as before typed backgrounds, an image resource
+        Background::Solid(value) => *background = Background::from_plain(replace_vars(value, variables)),
+        Background::Gradient(gradient) => {
+            for stop in gradient.stops_mut() {
+                stop.color = replace_vars(&stop.color, variables);
+            }
+        }
+        Background::Image(image) => {
+            image.source = replace_vars(&image.source, variables);
+            for field in &mut image.focal_point {
+                field.resolve(variables);
+            }
+            replace_filter_vars(&mut image.filters, variables);
+            if let Some(overlay) = &mut image.overlay {
+                replace_fill_vars(overlay, variables);
+            }
+        }
+        Background::Pattern(pattern) => {
+            pattern.source = replace_vars(&pattern.source, variables);
+            for field in pattern.tile_size.iter_mut().flatten() {
+                field.resolve(variables);
+            }
+        }
+    }
+}
+
+/// Removes the layers whose condition does not hold and resolves the rest, including the children of groups.
+/// Repeaters become groups holding a cell for each entry.
+fn resolve_layers(layers: &mut Vec<Layer>, variables: &HashMap<String, String>) {
+    layers.retain(|layer| layer.condition_holds(variables));
+    for layer in layers {
+        layer.condition = None;
+        resolve_layer(layer, variables);
+        match &mut layer.item {
+            Item::Group(group) => resolve_layers(&mut group.children, variables),
+            Item::Repeat(repeat) => layer.item = Item::Group(expand_repeat(&layer.id, repeat, variables)),
+            _ => {}
+        }
+    }
+}
+
+/// Lays out a copy of the template for each entry, each in a group of its own so that the template's
+/// positions are relative to the entry's cell.
+fn expand_repeat(id: &str, repeat: &RepeatItem, variables: &HashMap<String, String>) -> GroupItem {
+    let cells: Vec<Layer> = repeat
+        .entries(variables)
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let mut children = repeat.template.clone();
+            suffix_layer_ids(&mut children, &format!("-{index}"));
+            resolve_layers(&mut children, entry);
+            let (x, y) = repeat.cell_position(index);
+            Layer {
+                id: format!("{id}-{index}"),
+                x: x.into(),
+                y: y.into(),
+                rotation: 0.0.into(),
+                visible: true.into(),
+                opacity: 1.0.into(),
+                blend_mode: BlendMode::Normal,
+                effects: Vec::new(),
+                mask: None,
+                condition: None,
+                item: Item::Group(GroupItem { width: repeat.width.clone(), height: repeat.height.clone(), clip: false.into(), children }),
+            }
+        })
+        .collect();
+
+    let (width, height) = repeat.size_for(cells.len());
+    GroupItem { width: width.into(), height: height.into(), clip: false.into(), children: cells }
+}
+
+/// Keeps the ids of repeated layers unique, along with the masks that refer to them.
+fn suffix_layer_ids(layers: &mut [Layer], suffix: &str) {
+    let ids: Vec<String> = layers.iter().flat_map(Layer::flatten).map(|layer| layer.id.clone()).collect();
+    for_each_layer_mut(layers, &mut |layer| {
+        layer.id.push_str(suffix);
+        if let Some(LayerMask { source: MaskSource::Layer { id }, .. }) = &mut layer.mask
+            && ids.contains(id)
+        {
+            id.push_str(suffix);
+        }
+    });
+}
+
+/// Substitutes variables in one layer, leaving the children of groups to the caller.
+fn resolve_layer(layer: &mut Layer, variables: &HashMap<String, String>) {
+    layer.x.resolve(variables);
+    layer.y.resolve(variables);
+    layer.rotation.resolve(variables);
+    layer.visible.resolve(variables);
+    layer.opacity.resolve(variables);
+    for effect in &mut layer.effects {
+        match effect {
+            Effect::DropShadow(shadow) | Effect::InnerShadow(shadow) => {
+                shadow.color = replace_vars(&shadow.color, variables);
+                for field in [&mut shadow.offset_x, &mut shadow.offset_y, &mut shadow.blur, &mut shadow.spread] {
+                    field.resolve(variables);
                 }
             }
+            Effect::LayerBlur { radius } | Effect::BackgroundBlur { radius } => radius.resolve(variables),
         }
-        new_sigil
     }
+
+    if let Some(LayerMask { source: MaskSource::Shape { item, .. }, .. }) = &mut layer.mask {
+        resolve_item(item, variables);
+    }
+    resolve_item(&mut layer.item, variables);
 }
 
-fn replace_vars(input: &str, vars: &HashMap<String, String>) -> String {
-    let mut result = input.to_string();
-    for (k, v) in vars {
-        let placeholder = format!("{{{}}}", k);
-        result = result.replace(&placeholder, v);
+fn resolve_item(item: &mut Item, variables: &HashMap<String, String>) {
+    match item {
+        Item::Text(text) => {
+            text.text = replace_vars(&text.text, variables);
+            text.font_size.resolve(variables);
+            text.letter_spacing.resolve(variables);
+            text.underline.resolve(variables);
+            text.strikethrough.resolve(variables);
+            for field in [&mut text.max_width, &mut text.max_height, &mut text.line_height].into_iter().flatten() {
+                field.resolve(variables);
+            }
+            if let Some(max_lines) = &mut text.max_lines {
+                max_lines.resolve(variables);
+            }
+            if let Some(font_weight) = &mut text.font_weight {
+                font_weight.resolve(variables);
+            }
+            if let Some(fit) = &mut text.fit {
+                for field in [&mut fit.min_font_size, &mut fit.max_font_size, &mut fit.box_width, &mut fit.box_height] {
+                    field.resolve(variables);
+                }
+            }
+            replace_fill_vars(&mut text.color, variables);
+            for span in &mut text.spans {
+                span.text = replace_vars(&span.text, variables);
+                if let Some(color) = &mut span.color {
+                    *color = replace_vars(color, variables);
+                }
+                if let Some(font_size) = &mut span.font_size {
+                    font_size.resolve(variables);
+                }
+                if let Some(font_weight) = &mut span.font_weight {
+                    font_weight.resolve(variables);
+                }
+            }
+            if let Some(stroke) = &mut text.stroke {
+                stroke.color = replace_vars(&stroke.color, variables);
+                stroke.width.resolve(variables);
+            }
+            if let Some(shadow) = &mut text.shadow {
+                shadow.color = replace_vars(&shadow.color, variables);
+                for field in [&mut shadow.offset_x, &mut shadow.offset_y, &mut shadow.blur] {
+                    field.resolve(variables);
+                }
+            }
+        },
+        Item::Image(img) => {
+            img.source = replace_vars(&img.source, variables);
+            resolve_size(&mut img.width, &mut img.height, variables);
+            img.border_radius.resolve(variables);
+            for field in &mut img.focal_point {
+                field.resolve(variables);
+            }
+            if let Some(crop) = &mut img.crop {
+                for field in [&mut crop.x, &mut crop.y, &mut crop.width, &mut crop.height] {
+                    field.resolve(variables);
+                }
+            }
+            replace_stroke_vars(&mut img.stroke, variables);
+            replace_filter_vars(&mut img.filters, variables);
+        },
+        Item::Rect(rect) => {
+            resolve_size(&mut rect.width, &mut rect.height, variables);
+            rect.border_radius.resolve(variables);
+            replace_fill_vars(&mut rect.color, variables);
+            replace_stroke_vars(&mut rect.stroke, variables);
+        },
+        Item::Slider(slider) => {
+            resolve_size(&mut slider.width, &mut slider.height, variables);
+            slider.value.resolve(variables);
+            slider.max_value.resolve(variables);
+            slider.border_radius.resolve(variables);
+            slider.background_color = replace_vars(&slider.background_color, variables);
+            replace_fill_vars(&mut slider.fill_color, variables);
+            replace_stroke_vars(&mut slider.stroke, variables);
+        }
+        Item::Ellipse(ellipse) => {
+            resolve_size(&mut ellipse.width, &mut ellipse.height, variables);
+            replace_fill_vars(&mut ellipse.color, variables);
+            replace_stroke_vars(&mut ellipse.stroke, variables);
+        }
+        Item::Line(line) => {
+            for field in [&mut line.x1, &mut line.y1, &mut line.x2, &mut line.y2, &mut line.stroke_width] {
+                field.resolve(variables);
+            }
+            line.color = replace_vars(&line.color, variables);
+        }
+        Item::Polygon(polygon) => {
+            replace_fill_vars(&mut polygon.color, variables);
+            replace_stroke_vars(&mut polygon.stroke, variables);
+        }
+        Item::Star(star) => {
+            resolve_size(&mut star.width, &mut star.height, variables);
+            star.points.resolve(variables);
+            star.inner_ratio.resolve(variables);
+            replace_fill_vars(&mut star.color, variables);
+            replace_stroke_vars(&mut star.stroke, variables);
+        }
+        Item::RegularPolygon(polygon) => {
+            resolve_size(&mut polygon.width, &mut polygon.height, variables);
+            polygon.sides.resolve(variables);
+            replace_fill_vars(&mut polygon.color, variables);
+            replace_stroke_vars(&mut polygon.stroke, variables);
+        }
+        Item::Path(path) => {
+            resolve_size(&mut path.width, &mut path.height, variables);
+            path.d = replace_vars(&path.d, variables);
+            if let Some(fill) = &mut path.fill {
+                replace_fill_vars(fill, variables);
+            }
+            replace_stroke_vars(&mut path.stroke, variables);
+        }
+        Item::Group(group) => {
+            resolve_size(&mut group.width, &mut group.height, variables);
+            group.clip.resolve(variables);
+        }
+        Item::Repeat(repeat) => {
+            resolve_size(&mut repeat.width, &mut repeat.height, variables);
+            repeat.gap.resolve(variables);
+        }
     }
-    result
+}
+
+fn resolve_size(width: &mut Bindable<f32>, height: &mut Bindable<f32>, variables: &HashMap<String, String>) {
+    width.resolve(variables);
+    height.resolve(variables);
+}
+
+fn replace_fill_vars(fill: &mut Fill, vars: &HashMap<String, String>) {
+    for color in fill.colors_mut() {
+        *color = replace_vars(color, vars);
+    }
+}
+
+fn replace_stroke_vars(stroke: &mut Option<ShapeStroke>, vars: &HashMap<String, String>) {
+    if let Some(stroke) = stroke {
+        stroke.color = replace_vars(&stroke.color, vars);
+        stroke.width.resolve(vars);
+    }
+}
+
+fn replace_filter_vars(filters: &mut [ImageFilter], vars: &HashMap<String, String>) {
+    for filter in filters {
+        if let ImageFilter::Tint { color, .. } = filter {
+            *color = replace_vars(color, vars);
+        }
+    }
+}
+
+/// Evaluates the `{...}` placeholders that can be, leaving the others for a later pass or for the renderer to reject.
+fn replace_vars(input: &str, vars: &HashMap<String, String>) -> String {
+    expr::substitute(input, vars)
 }
 
 #[cfg(test)]
@@ -125,32 +1115,52 @@ mod tests {
     #[test]
     fn it_serializes_correctly() {
         let sigil = Sigil {
+            version: FORMAT_VERSION,
             width: 800,
             height: 400,
-            background: "#1a1a1a".to_string(),
+            background: "#1a1a1a".into(),
+            variables: Vec::new(),
             layers: vec![
                 Layer {
                     id: "avatar_layer".to_string(),
-                    x: 50.0,
-                    y: 50.0,
-                    rotation: 0.0,
+                    x: 50.0.into(),
+                    y: 50.0.into(),
+                    rotation: 0.0.into(),
+                    visible: true.into(),
+                    opacity: 1.0.into(),
+                    blend_mode: BlendMode::Normal,
+                    effects: Vec::new(),
+                    mask: None,
+                    condition: None,
                     item: Item::Image(ImageItem {
                         source: "{avatar}".to_string(),
-                        width: 100.0,
-                        height: 100.0,
-                        border_radius: 50.0,
+                        width: 100.0.into(),
+                        height: 100.0.into(),
+                        border_radius: 50.0.into(),
+                        stroke: None,
+                        fit: ImageFit::Cover,
+                        focal_point: [0.5.into(), 0.5.into()],
+                        crop: None,
+                        filters: Vec::new(),
                     }),
                 },
                 Layer {
                     id: "welcome_text".to_string(),
-                    x: 170.0,
-                    y: 100.0,
-                    rotation: 0.0,
+                    x: 170.0.into(),
+                    y: 100.0.into(),
+                    rotation: 0.0.into(),
+                    visible: true.into(),
+                    opacity: 1.0.into(),
+                    blend_mode: BlendMode::Normal,
+                    effects: Vec::new(),
+                    mask: None,
+                    condition: None,
                     item: Item::Text(TextItem {
                         text: "Welcome {username}!".to_string(),
-                        font_size: 48.0,
-                        color: "#ffffff".to_string(),
+                        font_size: 48.0.into(),
+                        color: "#ffffff".into(),
                         font_family: "Roboto".to_string(),
+                        ..Default::default()
                     }),
                 },
             ],
@@ -159,4 +1169,922 @@ mod tests {
         let json = serde_json::to_string_pretty(&sigil).unwrap();
         println!("{}", json);
     }
+
+    #[test]
+    fn text_layout_fields_are_optional() {
+        let json = r##"{"text": "Hi", "font_size": 16.0, "color": "#ffffff", "font_family": "Roboto"}"##;
+        let text: TextItem = serde_json::from_str(json).unwrap();
+        assert_eq!(text.max_width, None);
+        assert_eq!(text.align, TextAlign::Left);
+        assert_eq!(text.vertical_align, VerticalAlign::Top);
+        assert_eq!(text.line_height_px(), 16.0 * DEFAULT_LINE_HEIGHT);
+
+        let json = r##"{"text": "Hi", "font_size": 10.0, "color": "#ffffff", "font_family": "Roboto",
+            "max_width": 200.0, "max_lines": 2, "line_height": 1.5, "align": "justify", "vertical_align": "middle"}"##;
+        let text: TextItem = serde_json::from_str(json).unwrap();
+        assert_eq!(text.max_width, Some(200.0.into()));
+        assert_eq!(text.max_lines, Some(2.into()));
+        assert_eq!(text.align, TextAlign::Justify);
+        assert_eq!(text.vertical_align, VerticalAlign::Middle);
+        assert_eq!(text.line_height_px(), 15.0);
+    }
+
+    #[test]
+    fn fitted_text_replaces_fit_settings() {
+        let mut text = TextItem {
+            text: "A very long username".to_string(),
+            font_size: 32.0.into(),
+            fit: Some(TextFit {
+                min_font_size: 10.0.into(),
+                max_font_size: 32.0.into(),
+                box_width: 200.0.into(),
+                box_height: 40.0.into(),
+                ellipsis: true,
+            }),
+            ..Default::default()
+        };
+
+        text.apply_fit(&FittedText { font_size: 14.5, text: "A very long us…".to_string(), spans: Vec::new() });
+
+        assert_eq!(text.fit, None);
+        assert_eq!(text.font_size, 14.5.into());
+        assert_eq!(text.text, "A very long us…");
+        assert_eq!(text.max_width, Some(200.0.into()));
+        assert_eq!(text.max_height, Some(40.0.into()));
+    }
+
+    #[test]
+    fn text_transform_matches_css() {
+        assert_eq!(TextTransform::Uppercase.apply("hello world"), "HELLO WORLD");
+        assert_eq!(TextTransform::Lowercase.apply("Hello World"), "hello world");
+        assert_eq!(TextTransform::Capitalize.apply("welcome  to the \"club\""), "Welcome  To The \"Club\"");
+        assert_eq!(TextTransform::None.apply("MiXeD"), "MiXeD");
+    }
+
+    #[test]
+    fn resolve_substitutes_variables_per_span() {
+        let sigil = Sigil {
+            version: FORMAT_VERSION,
+            width: 400,
+            height: 100,
+            background: "#000000".into(),
+            variables: Vec::new(),
+            layers: vec![Layer {
+                id: "welcome".to_string(),
+                x: 0.0.into(),
+                y: 0.0.into(),
+                rotation: 0.0.into(),
+                visible: true.into(),
+                opacity: 1.0.into(),
+                blend_mode: BlendMode::Normal,
+                effects: Vec::new(),
+                mask: None,
+                condition: None,
+                item: Item::Text(TextItem {
+                    font_size: 20.0.into(),
+                    color: "#ffffff".into(),
+                    spans: vec![
+                        TextSpan { text: "Welcome ".to_string(), ..Default::default() },
+                        TextSpan {
+                            text: "{username}".to_string(),
+                            color: Some("{accent}".to_string()),
+                            font_weight: Some(700.into()),
+                            ..Default::default()
+                        },
+                        TextSpan { text: " to {server}".to_string(), ..Default::default() },
+                    ],
+                    ..Default::default()
+                }),
+            }],
+        };
+
+        let vars = HashMap::from([
+            ("username".to_string(), "meetzli".to_string()),
+            ("accent".to_string(), "#ff8800".to_string()),
+            ("server".to_string(), "Sigil".to_string()),
+        ]);
+        let resolved = sigil.resolve(&vars);
+
+        let Item::Text(text) = &resolved.layers[0].item else { unreachable!() };
+        assert_eq!(text.spans[1].text, "meetzli");
+        assert_eq!(text.spans[1].color.as_deref(), Some("#ff8800"));
+        assert_eq!(text.plain_text(), "Welcome meetzli to Sigil");
+
+        let json = serde_json::to_value(text).unwrap();
+        assert_eq!(json["spans"][0], serde_json::json!({ "text": "Welcome " }));
+    }
+
+    #[test]
+    fn set_font_size_scales_span_sizes() {
+        let mut text = TextItem {
+            font_size: 20.0.into(),
+            spans: vec![
+                TextSpan { text: "a".to_string(), ..Default::default() },
+                TextSpan { text: "b".to_string(), font_size: Some(40.0.into()), ..Default::default() },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(text.max_font_size(), 40.0);
+        text.set_font_size(10.0);
+        assert_eq!(text.font_size, 10.0.into());
+        assert_eq!(text.spans[0].font_size, None);
+        assert_eq!(text.spans[1].font_size, Some(20.0.into()));
+    }
+
+    #[test]
+    fn layer_compositing_defaults() {
+        let json = r##"{"id": "bg", "x": 0.0, "y": 0.0, "item": {"type": "Rect", "data":
+            {"width": 10.0, "height": 10.0, "color": "#ffffff", "border_radius": 0.0}}}"##;
+        let layer: Layer = serde_json::from_str(json).unwrap();
+        assert_eq!(layer.opacity, 1.0.into());
+        assert_eq!(layer.blend_mode, BlendMode::Normal);
+        assert!(layer.is_opaque_normal());
+        // Layers made in code start out with the same defaults
+        assert_eq!(Layer::new("bg", layer.item.clone()), layer);
+
+        let json = json.replacen('{', r#"{"opacity": 0.5, "blend_mode": "color_dodge", "#, 1);
+        let layer: Layer = serde_json::from_str(&json).unwrap();
+        assert_eq!(layer.opacity, 0.5.into());
+        assert_eq!(layer.blend_mode, BlendMode::ColorDodge);
+        assert_eq!(BlendMode::from_css_name(layer.blend_mode.css_name()), Some(BlendMode::ColorDodge));
+    }
+
+    #[test]
+    fn fill_accepts_plain_color_strings_and_gradients() {
+        let json = r##"{"width": 10.0, "height": 10.0, "color": "#ff0000", "border_radius": 0.0}"##;
+        let rect: RectItem = serde_json::from_str(json).unwrap();
+        assert_eq!(rect.color, Fill::Solid("#ff0000".to_string()));
+        assert_eq!(serde_json::to_value(&rect).unwrap()["color"], "#ff0000");
+
+        let json = r##"{"width": 10.0, "height": 10.0, "border_radius": 0.0, "color":
+            {"type": "linear", "angle": 90.0, "stops": [{"color": "{start}", "offset": 0.0}, {"color": "#0000ff", "offset": 1.0}]}}"##;
+        let rect: RectItem = serde_json::from_str(json).unwrap();
+        let Fill::Gradient(Gradient::Linear(linear)) = &rect.color else { panic!("expected a linear gradient") };
+        assert_eq!(linear.angle, 90.0);
+        assert_eq!(rect.color.primary_color(), "{start}");
+
+        let json = r##"{"type": "radial", "stops": [{"color": "#ffffff", "offset": 0.0}]}"##;
+        let Fill::Gradient(Gradient::Radial(radial)) = serde_json::from_str(json).unwrap() else { panic!("expected a radial gradient") };
+        assert_eq!((radial.center_x, radial.center_y), (0.5, 0.5));
+
+        let mut fill = rect.color.clone();
+        replace_fill_vars(&mut fill, &HashMap::from([("start".to_string(), "#ff0000".to_string())]));
+        assert_eq!(fill.primary_color(), "#ff0000");
+    }
+
+    #[test]
+    fn shape_stroke_defaults_and_offsets() {
+        let json = r##"{"width": 10.0, "height": 10.0, "color": "#ff0000", "border_radius": 0.0,
+            "stroke": {"color": "{accent}", "width": 4.0}}"##;
+        let rect: RectItem = serde_json::from_str(json).unwrap();
+        let stroke = rect.stroke.as_ref().unwrap();
+        assert_eq!(stroke.align, StrokeAlign::Inside);
+        assert!(stroke.dash.is_empty());
+        assert_eq!(stroke.center_offset(), -2.0);
+        assert!(serde_json::to_value(stroke).unwrap().get("dash").is_none());
+
+        let outside = ShapeStroke { align: StrokeAlign::Outside, ..stroke.clone() };
+        assert_eq!(outside.center_offset(), 2.0);
+
+        let mut stroke = rect.stroke.clone();
+        replace_stroke_vars(&mut stroke, &HashMap::from([("accent".to_string(), "#00ff00".to_string())]));
+        assert_eq!(stroke.unwrap().color, "#00ff00");
+    }
+
+    #[test]
+    fn shape_items_report_and_change_their_size() {
+        let json = r##"{"type": "Polygon", "data": {"points": [[0.0, 0.0], [20.0, 5.0], [10.0, 10.0]], "color": "#ffffff"}}"##;
+        let mut polygon: Item = serde_json::from_str(json).unwrap();
+        assert_eq!(polygon.size(), Some((20.0, 10.0)));
+        polygon.resize(40.0, 5.0);
+        let Item::Polygon(scaled) = &polygon else { panic!("expected a polygon") };
+        assert_eq!(scaled.points, vec![[0.0, 0.0], [40.0, 2.5], [20.0, 5.0]]);
+
+        let json = r##"{"type": "Line", "data": {"x1": 0.0, "y1": 8.0, "x2": 16.0, "y2": 8.0, "color": "#ffffff", "stroke_width": 2.0}}"##;
+        let mut line: Item = serde_json::from_str(json).unwrap();
+        let Item::Line(parsed) = &line else { panic!("expected a line") };
+        assert_eq!(parsed.cap, LineCap::Butt);
+        line.resize(32.0, 4.0);
+        let Item::Line(scaled) = &line else { panic!("expected a line") };
+        assert_eq!((*scaled.x2, *scaled.y1, *scaled.y2), (32.0, 4.0, 4.0));
+
+        let json = r##"{"type": "Star", "data": {"width": 10.0, "height": 10.0, "points": 4, "color": "#ffffff"}}"##;
+        let star: Item = serde_json::from_str(json).unwrap();
+        let Item::Star(parsed) = &star else { panic!("expected a star") };
+        assert_eq!(parsed.inner_ratio, 0.5.into());
+        let vertices = parsed.vertices();
+        assert_eq!(vertices.len(), 8);
+        assert!((vertices[0][0] - 5.0).abs() < 1e-4 && vertices[0][1].abs() < 1e-4);
+
+        // Bound counts can be anything, so the number of corners drawn is capped
+        let mut huge = parsed.clone();
+        huge.points = u32::MAX.into();
+        assert_eq!(huge.vertices().len(), MAX_CORNERS as usize * 2);
+        let layer = serde_json::from_value(serde_json::json!({ "id": "star", "x": 0, "y": 0, "item": Item::Star(huge) })).unwrap();
+        let sigil = Sigil { version: FORMAT_VERSION, width: 20, height: 20, background: "#000".into(), variables: Vec::new(), layers: vec![layer] };
+        assert_eq!(sigil.validate()[0].to_string(), format!("error at layers[0].item.data.points: A star needs 2 to 1000 points, got {}", u32::MAX));
+        assert!(Item::Text(TextItem::default()).size().is_none());
+    }
+
+    #[test]
+    fn path_items_parse_and_map_their_view_box() {
+        let json = r##"{"type": "Path", "data": {"d": "m2 2 h4 q2 0 2 2 a2 2 0 0 1 -2 2 z", "width": 20.0, "height": 10.0, "view_box": [2.0, 2.0, 10.0, 10.0], "fill": "#ffffff"}}"##;
+        let item: Item = serde_json::from_str(json).unwrap();
+        let Item::Path(path) = &item else { panic!("expected a path") };
+        assert_eq!(path.fill_rule, FillRule::Nonzero);
+        assert_eq!(item.size(), Some((20.0, 10.0)));
+
+        let segments = path.segments().unwrap();
+        assert_eq!(segments[0], PathSegment::MoveTo(0.0, 0.0));
+        assert_eq!(segments[1], PathSegment::LineTo(8.0, 0.0));
+        assert_eq!(segments[2], PathSegment::QuadTo(12.0, 0.0, 12.0, 2.0));
+        assert!(matches!(segments[3], PathSegment::CubicTo(..)));
+        assert_eq!(segments.last(), Some(&PathSegment::Close));
+
+        let round_trip = path::parse_path_data(&path::format_path_data(&segments)).unwrap();
+        assert_eq!(round_trip, segments);
+        assert!(path::parse_path_data("M 0 0 L 10").is_err());
+    }
+
+    #[test]
+    fn groups_resolve_and_flatten_their_children() {
+        let json = r##"{"width": 100, "height": 100, "background": "#000000", "layers": [
+            {"id": "card", "x": 10.0, "y": 10.0, "item": {"type": "Group", "data": {"width": 50.0, "height": 20.0, "children": [
+                {"id": "name", "x": 0.0, "y": 0.0, "item": {"type": "Text", "data": {"text": "{name}", "font_size": 12.0, "color": "#ffffff", "font_family": "Sans Serif"}}},
+                {"id": "inner", "x": 5.0, "y": 5.0, "item": {"type": "Group", "data": {"width": 10.0, "height": 10.0, "clip": true, "children": [
+                    {"id": "dot", "x": 0.0, "y": 0.0, "item": {"type": "Ellipse", "data": {"width": 4.0, "height": 4.0, "color": "{accent}"}}}
+                ]}}}
+            ]}}}
+        ]}"##;
+        let sigil: Sigil = serde_json::from_str(json).unwrap();
+        let ids: Vec<&str> = sigil.all_layers().map(|layer| layer.id.as_str()).collect();
+        assert_eq!(ids, ["card", "name", "inner", "dot"]);
+        assert_eq!(sigil.layers[0].item.size(), Some((50.0, 20.0)));
+
+        let vars = HashMap::from([("name".to_string(), "Ada".to_string()), ("accent".to_string(), "#ff0000".to_string())]);
+        let resolved = sigil.resolve(&vars);
+        let layers: Vec<&Layer> = resolved.all_layers().collect();
+        let Item::Text(text) = &layers[1].item else { panic!("expected text") };
+        assert_eq!(text.text, "Ada");
+        let Item::Group(inner) = &layers[2].item else { panic!("expected a group") };
+        assert!(*inner.clip);
+        let Item::Ellipse(dot) = &layers[3].item else { panic!("expected an ellipse") };
+        assert_eq!(dot.color, Fill::from("#ff0000"));
+    }
+
+    #[test]
+    fn image_fit_matches_css_object_fit() {
+        let json = r##"{"source": "{avatar}", "width": 20.0, "height": 20.0, "border_radius": 0.0}"##;
+        let mut image: ImageItem = serde_json::from_str(json).unwrap();
+        assert_eq!((image.fit, &image.focal_point, &image.crop), (ImageFit::Fill, &[0.5.into(), 0.5.into()], &None));
+
+        // Expected rectangles as laid out by browsers for a 20x20 box with `object-position: 50% 50%`
+        let cases = [
+            (ImageFit::Fill, (40.0, 20.0), [0.0, 0.0, 20.0, 20.0]),
+            (ImageFit::Contain, (40.0, 20.0), [0.0, 5.0, 20.0, 10.0]),
+            (ImageFit::Cover, (40.0, 20.0), [-10.0, 0.0, 40.0, 20.0]),
+            (ImageFit::None, (40.0, 20.0), [-10.0, 0.0, 40.0, 20.0]),
+            (ImageFit::ScaleDown, (40.0, 20.0), [0.0, 5.0, 20.0, 10.0]),
+            (ImageFit::ScaleDown, (10.0, 5.0), [5.0, 7.5, 10.0, 5.0]),
+        ];
+        for (fit, (width, height), expected) in cases {
+            image.fit = fit;
+            assert_eq!(image.fit_rect(width, height), expected, "{}", fit.css_name());
+        }
+
+        image.fit = ImageFit::Cover;
+        image.focal_point = [0.0.into(), 1.0.into()];
+        assert_eq!(image.fit_rect(40.0, 20.0), [0.0, 0.0, 40.0, 20.0]);
+        image.focal_point = [1.0.into(), 1.0.into()];
+        assert_eq!(image.fit_rect(20.0, 40.0), [0.0, -20.0, 20.0, 40.0]);
+
+        image.crop = Some(ImageCrop { x: 30.0.into(), y: (-5.0).into(), width: 20.0.into(), height: 10.0.into() });
+        assert_eq!(image.source_rect(40.0, 20.0), [30.0, 0.0, 10.0, 10.0]);
+        assert_eq!(ImageFit::from_css_name("scale-down"), Some(ImageFit::ScaleDown));
+
+        let value = serde_json::to_value(&image).unwrap();
+        assert_eq!(value["fit"], "cover");
+        assert_eq!(value["crop"]["x"], 30.0);
+    }
+
+    #[test]
+    fn image_filters_follow_css_color_matrices() {
+        let json = r##"{"source": "{avatar}", "width": 20.0, "height": 20.0, "border_radius": 0.0, "filters": [
+            {"type": "grayscale", "amount": 1.0},
+            {"type": "tint", "color": "{accent}", "amount": 0.5},
+            {"type": "blur", "radius": 4.0}
+        ]}"##;
+        let image: ImageItem = serde_json::from_str(json).unwrap();
+        assert_eq!(image.filters[0], ImageFilter::Grayscale { amount: 1.0 });
+
+        let vars = HashMap::from([("accent".to_string(), "#ff0000".to_string())]);
+        let mut item = Item::Image(image);
+        resolve_item(&mut item, &vars);
+        let Item::Image(image) = item else { panic!("expected an image") };
+        assert_eq!(image.filters[1], ImageFilter::Tint { color: "#ff0000".to_string(), amount: 0.5 });
+        assert_eq!(image.filters[2].color_matrix(), None);
+        assert_eq!(image.filters[2].css_function().as_deref(), Some("blur(2px)"));
+
+        let apply = |filter: ImageFilter, pixel: [f32; 4]| {
+            apply_color_matrix(&filter.color_matrix().unwrap(), pixel).map(|c| (c * 1000.0).round() / 1000.0)
+        };
+        let red = [1.0, 0.0, 0.0, 1.0];
+        assert_eq!(apply(ImageFilter::Grayscale { amount: 1.0 }, red), [0.213, 0.213, 0.213, 1.0]);
+        assert_eq!(apply(ImageFilter::Grayscale { amount: 0.0 }, red), red);
+        assert_eq!(apply(ImageFilter::Invert { amount: 1.0 }, [0.2, 0.5, 1.0, 0.5]), [0.8, 0.5, 0.0, 0.5]);
+        assert_eq!(apply(ImageFilter::Brightness { amount: 0.5 }, red), [0.5, 0.0, 0.0, 1.0]);
+        assert_eq!(apply(ImageFilter::Contrast { amount: 0.0 }, red), [0.5, 0.5, 0.5, 1.0]);
+        assert_eq!(apply(ImageFilter::HueRotate { degrees: 0.0 }, red), red);
+        // A full tint maps white to the tint color and keeps black
+        let tint = ImageFilter::Tint { color: "#00ff00".to_string(), amount: 1.0 };
+        assert_eq!(apply(tint.clone(), [1.0, 1.0, 1.0, 1.0]), [0.0, 1.0, 0.0, 1.0]);
+        assert_eq!(apply(tint, [0.0, 0.0, 0.0, 1.0]), [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(ImageFilter::Tint { color: "nope".to_string(), amount: 1.0 }.color_matrix(), None);
+    }
+
+    #[test]
+    fn backgrounds_load_legacy_strings_and_typed_objects() {
+        let background = |json: &str| serde_json::from_str::<Background>(json).unwrap();
+        assert_eq!(background(r##""#101010""##), Background::Solid("#101010".to_string()));
+        assert_eq!(background(r#""banner.png""#), Background::Image(BackgroundImage::new("banner.png")));
+        assert!(matches!(background(r#"{"type": "linear", "stops": []}"#), Background::Gradient(Gradient::Linear(_))));
+
+        let image = background(r##"{"type": "image", "source": "{banner}", "fit": "contain", "overlay": "#00000080",
+            "filters": [{"type": "blur", "radius": 6.0}]}"##);
+        let Background::Image(typed) = &image else { panic!("expected an image") };
+        assert_eq!((typed.fit, &typed.focal_point), (ImageFit::Contain, &[0.5.into(), 0.5.into()]));
+        assert_eq!(typed.overlay, Some(Fill::from("#00000080")));
+        let pattern = background(r#"{"type": "pattern", "source": "dots", "tile_size": [8.0, 8.0]}"#);
+        assert_eq!(pattern, Background::Pattern(BackgroundPattern { source: "dots".to_string(), tile_size: Some([8.0.into(), 8.0.into()]) }));
+
+        // Colors still serialize as plain strings
+        assert_eq!(serde_json::to_value(Background::from("#101010")).unwrap(), "#101010");
+        assert_eq!(serde_json::to_value(&image).unwrap()["type"], "image");
+        assert_eq!(serde_json::from_value::<Background>(serde_json::to_value(&pattern).unwrap()).unwrap(), pattern);
+
+        // A variable decides between a color and an image once it is substituted
+        let sigil = Sigil { version: FORMAT_VERSION, width: 10, height: 10, background: "{bg}".into(), variables: Vec::new(), layers: Vec::new() };
+        assert_eq!(sigil.background, Background::Solid("{bg}".to_string()));
+        let resolve = |value: &str| sigil.resolve(&HashMap::from([("bg".to_string(), value.to_string())])).background;
+        assert_eq!(resolve("#ff0000"), Background::Solid("#ff0000".to_string()));
+        assert_eq!(resolve("banner.png"), Background::Image(BackgroundImage::new("banner.png")));
+    }
+
+    #[test]
+    fn variables_are_declared_validated_and_defaulted() {
+        let json = r##"{"width": 10, "height": 10, "background": "{bg}", "layers": [], "variables": [
+            {"name": "name", "max_length": 5},
+            {"name": "bg", "type": "color", "default": "#000000", "description": "Card color"},
+            {"name": "level", "type": "number", "min": 1.0, "max": 99.0},
+            {"name": "theme", "default": "dark", "options": ["dark", "light"]},
+            {"name": "premium", "type": "boolean", "default": "false"}
+        ]}"##;
+        let sigil: Sigil = serde_json::from_str(json).unwrap();
+        let required: Vec<&str> = sigil.required_variables().map(|def| def.name.as_str()).collect();
+        assert_eq!(required, ["name", "level"]);
+        assert_eq!(sigil.variables[1].kind, VariableType::Color);
+
+        let values = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+        assert_eq!(sigil.validate_variables(&values(&[("name", "Ann"), ("level", "12")])), Ok(()));
+
+        let errors = sigil
+            .validate_variables(&values(&[
+                ("name", "Annabelle"),
+                ("bg", "nope"),
+                ("theme", "blue"),
+                ("premium", "yes"),
+                ("zeta", "1"),
+                ("alpha", "2"),
+            ]))
+            .unwrap_err();
+        assert_eq!(
+            errors,
+            [
+                VariableError::TooLong { name: "name".to_string(), max_length: 5 },
+                VariableError::InvalidType { name: "bg".to_string(), expected: VariableType::Color, value: "nope".to_string() },
+                VariableError::Missing { name: "level".to_string() },
+                VariableError::NotAnOption {
+                    name: "theme".to_string(),
+                    value: "blue".to_string(),
+                    options: vec!["dark".to_string(), "light".to_string()],
+                },
+                VariableError::InvalidType { name: "premium".to_string(), expected: VariableType::Boolean, value: "yes".to_string() },
+                VariableError::Unknown { name: "alpha".to_string() },
+                VariableError::Unknown { name: "zeta".to_string() },
+            ]
+        );
+        let bounds = sigil.validate_variables(&values(&[("name", "A"), ("level", "0")])).unwrap_err();
+        assert_eq!(bounds, [VariableError::TooSmall { name: "level".to_string(), min: 1.0, value: 0.0 }]);
+        assert_eq!(bounds[0].to_string(), "Variable 'level' must be at least 1, got 0");
+
+        // Defaults fill in for missing values, given values win
+        assert_eq!(sigil.resolve(&HashMap::new()).background, Background::Solid("#000000".to_string()));
+        assert_eq!(sigil.resolve(&values(&[("bg", "#ffffff")])).background, Background::Solid("#ffffff".to_string()));
+
+        // Templates without a schema accept anything, and keep serializing without one
+        let plain = Sigil { variables: Vec::new(), ..sigil };
+        assert_eq!(plain.validate_variables(&values(&[("anything", "goes")])), Ok(()));
+        assert!(serde_json::to_value(&plain).unwrap().get("variables").is_none());
+    }
+
+    #[test]
+    fn expressions_compute_and_format_values() {
+        let vars: HashMap<String, String> = [
+            ("username", "meetzli"),
+            ("xp", "1234567"),
+            ("max_xp", "4000000"),
+            ("name", "Alexander the Great"),
+            ("level", "7"),
+            ("user-id", "42"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let render = |template: &str| expr::render(template, &vars);
+
+        assert_eq!(render("{username | upper}").unwrap(), "MEETZLI");
+        assert_eq!(render("{ username|capitalize } lv.{level}").unwrap(), "Meetzli lv.7");
+        assert_eq!(render(r#"{xp | number:"0,0"} XP"#).unwrap(), "1,234,567 XP");
+        assert_eq!(render(r#"{xp | number:"0.0a"}"#).unwrap(), "1.2m");
+        assert_eq!(render(r#"{999950 | number:"0.0a"}"#).unwrap(), "1.0m");
+        assert_eq!(render(r#"{-1234.5 | number:"0,0.00"}"#).unwrap(), "-1,234.50");
+        assert_eq!(render("{name | truncate:10}").unwrap(), "Alexander…");
+        assert_eq!(render(r#"{name | truncate:9,"..."}"#).unwrap(), "Alexan...");
+        assert_eq!(render(r#"Hi {missing | default:"Guest"}"#).unwrap(), "Hi Guest");
+        assert_eq!(render("{xp / max_xp * 100}").unwrap(), "30.864175");
+        assert_eq!(render("{xp / max_xp * 100 | round:1}%").unwrap(), "30.9%");
+        assert_eq!(render("{(level + 3) * -2 % 7}").unwrap(), "-6");
+        assert_eq!(render("{0.1 + 0.2}").unwrap(), "0.3");
+        assert_eq!(render("{xp / max_xp | percent | clamp:0,100 | floor}").unwrap(), "30");
+        assert_eq!(render("{user-id}").unwrap(), "42");
+
+        // Errors point at the offending character of the template
+        let error = |template: &str| render(template).unwrap_err();
+        assert_eq!(error("XP: {xp +}"), ExprError { position: 9, kind: ExprErrorKind::Expected { expected: "a value", found: "end of expression".to_string() } });
+        assert_eq!(error("{name | shout}"), ExprError { position: 8, kind: ExprErrorKind::UnknownFilter("shout".to_string()) });
+        assert_eq!(error("{username * 2}").kind, ExprErrorKind::NotANumber("meetzli".to_string()));
+        assert_eq!(error("{level / (xp - xp)}").position, 7);
+        assert_eq!(error("{missing | upper}"), ExprError { position: 1, kind: ExprErrorKind::MissingVariable("missing".to_string()) });
+        assert_eq!(expr::parse(r#"name | default:"open"#).unwrap_err(), ExprError { position: 15, kind: ExprErrorKind::UnterminatedString });
+        assert_eq!(error("a {b").kind, ExprErrorKind::UnclosedPlaceholder);
+        assert_eq!(error("{level $ 2}").to_string(), "Unexpected character '$' at position 7");
+        assert_eq!(error(&format!("{{{}1{}}}", "(".repeat(40), ")".repeat(40))).kind, ExprErrorKind::TooDeep);
+        assert_eq!(expr::check("{a | nope}").unwrap_err().kind, ExprErrorKind::UnknownFilter("nope".to_string()));
+        assert_eq!(expr::check("{a | upper} and {b + 1}"), Ok(()));
+
+        // Resolving keeps placeholders that cannot be evaluated yet, and text that only looks like one
+        assert_eq!(replace_vars("{username} {missing} {:)} {", &vars), "meetzli {missing} {:)} {");
+    }
+
+    #[test]
+    fn numbers_and_flags_bind_to_variables() {
+        let json = r##"{"id": "bar", "x": "{margin}", "y": "12", "visible": "{level >= 5}", "opacity": "{alpha | default:0.5}",
+            "item": {"type": "Slider", "data": {"width": 200.0, "height": 10.0, "value": "{xp / max_xp * 100}", "max_value": 100.0,
+                "background_color": "#000000", "fill_color": "#ffffff", "border_radius": "{radius}"}}}"##;
+        let layer: Layer = serde_json::from_str(json).unwrap();
+        assert_eq!(layer.x.binding(), Some("{margin}"));
+        assert_eq!(layer.y, Bindable::new(12.0));
+        // Unresolved bindings read as the default value
+        assert_eq!(*layer.x, 0.0);
+
+        // Bindings serialize back as written, literals as themselves
+        let value = serde_json::to_value(&layer).unwrap();
+        assert_eq!(value["x"], "{margin}");
+        assert_eq!(value["y"], 12.0);
+        assert_eq!(value["item"]["data"]["value"], "{xp / max_xp * 100}");
+
+        let sigil = Sigil { version: FORMAT_VERSION, width: 200, height: 20, background: "#000000".into(), variables: Vec::new(), layers: vec![layer] };
+        let vars = HashMap::from([
+            ("margin".to_string(), "8".to_string()),
+            ("xp".to_string(), "150".to_string()),
+            ("max_xp".to_string(), "600".to_string()),
+        ]);
+        let resolved = sigil.resolve(&vars);
+        let layer = &resolved.layers[0];
+        assert_eq!((*layer.x, *layer.opacity), (8.0, 0.5));
+        assert_eq!(layer.x.binding(), None);
+        let Item::Slider(slider) = &layer.item else { panic!() };
+        assert_eq!(*slider.value, 25.0);
+
+        // A binding that cannot be evaluated stays unresolved: `level` and `radius` are missing
+        assert_eq!(layer.visible.binding(), Some("{level >= 5}"));
+        assert!(!*layer.visible);
+        assert_eq!(slider.border_radius.binding(), Some("{radius}"));
+        assert_eq!(sigil.validate(), []);
+        let issues: Vec<String> = resolved.validate_resolved().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            issues,
+            [
+                "error at layers[0].visible: Binding '{level >= 5}' could not be resolved",
+                "error at layers[0].item.data.border_radius: Binding '{radius}' could not be resolved",
+            ]
+        );
+
+        // Strings must be literals or templates, and templates must parse
+        let rect = |width: &str| serde_json::from_value::<Item>(serde_json::json!({"type": "Rect", "data": {"width": width, "height": 4, "color": "#fff", "border_radius": 0}}));
+        let err = rect("abc").unwrap_err().to_string();
+        assert!(err.contains(r#"expected a value or a {...} template, found "abc""#), "{err}");
+        let mut broken = sigil.clone();
+        broken.layers[0].item = rect("{width +}").unwrap();
+        assert_eq!(broken.validate()[0].path, "layers[0].item.data.width");
+
+        // Editors read either form from a text field
+        assert_eq!("4.5".parse::<Bindable<f32>>(), Ok(Bindable::new(4.5)));
+        assert_eq!("{size * 2}".parse::<Bindable<f32>>(), Ok(Bindable::bound("{size * 2}")));
+        assert!("big".parse::<Bindable<f32>>().is_err());
+        assert_eq!(Bindable::<f32>::bound("{size}").to_string(), "{size}");
+    }
+
+    #[test]
+    fn text_image_and_effect_numbers_bind_to_variables() {
+        let json = r##"[
+            {"id": "title", "x": 0.0, "y": 0.0,
+             "effects": [
+                {"type": "drop_shadow", "color": "#000000", "offset_y": "{depth}", "blur": "{depth * 3}"},
+                {"type": "layer_blur", "radius": "{depth}"}
+             ],
+             "item": {"type": "Text", "data": {"text": "Hi", "font_size": 12.0, "color": "#ffffff", "font_family": "Sans Serif",
+                "max_width": "{w}", "max_height": "{w / 2}", "max_lines": "{lines}", "line_height": "{spacing}", "font_weight": "{weight}",
+                "fit": {"min_font_size": 8.0, "max_font_size": "{size}", "box_width": "{w}", "box_height": 40.0},
+                "stroke": {"color": "#000000", "width": "{depth / 2}"},
+                "shadow": {"color": "#000000", "offset_x": "{depth}", "offset_y": 0.0, "blur": 0.0},
+                "spans": [{"text": "Hi", "font_size": "{size}", "font_weight": "{weight}"}]}}},
+            {"id": "photo", "x": 0.0, "y": 0.0,
+             "item": {"type": "Image", "data": {"source": "photo.png", "width": 40.0, "height": 40.0, "border_radius": 0.0,
+                "stroke": {"color": "#ffffff", "width": "{depth}"},
+                "focal_point": ["{focus}", 0.5], "crop": {"x": "{depth}", "y": 0.0, "width": "{w / 10}", "height": 20.0}}}}
+        ]"##;
+        let layers: Vec<Layer> = serde_json::from_str(json).unwrap();
+        let Item::Text(text) = &layers[0].item else { panic!() };
+        assert_eq!(text.max_width.as_ref().and_then(Bindable::binding), Some("{w}"));
+        let sigil = Sigil { version: FORMAT_VERSION, width: 10, height: 10, background: "#000000".into(), variables: Vec::new(), layers };
+        let written = serde_json::to_value(&sigil).unwrap();
+        assert_eq!(written["layers"][0]["item"]["data"]["max_width"], "{w}");
+        assert_eq!(written["layers"][1]["item"]["data"]["focal_point"], serde_json::json!(["{focus}", 0.5]));
+        assert_eq!(serde_json::from_value::<Sigil>(written).unwrap(), sigil);
+        assert_eq!(sigil.validate(), []);
+
+        let vars: HashMap<String, String> = [("w", "200"), ("lines", "2"), ("spacing", "1.5"), ("weight", "700"), ("size", "32"), ("depth", "4"), ("focus", "0.25")]
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let resolved = sigil.resolve(&vars);
+        let title = &resolved.layers[0];
+        let Effect::DropShadow(shadow) = &title.effects[0] else { panic!("expected a drop shadow") };
+        assert_eq!((*shadow.offset_y, *shadow.blur), (4.0, 12.0));
+        assert_eq!(title.effects[1], Effect::LayerBlur { radius: 4.0.into() });
+        let Item::Text(text) = &title.item else { panic!() };
+        let number = |value: &Option<Bindable<f32>>| value.as_deref().copied();
+        assert_eq!((number(&text.max_width), number(&text.max_height), number(&text.line_height)), (Some(200.0), Some(100.0), Some(1.5)));
+        assert_eq!((text.max_lines.as_deref().copied(), text.font_weight.as_deref().copied()), (Some(2), Some(700)));
+        let fit = text.fit.as_ref().unwrap();
+        assert_eq!((*fit.max_font_size, *fit.box_width), (32.0, 200.0));
+        assert_eq!(*text.stroke.as_ref().unwrap().width, 2.0);
+        assert_eq!(*text.shadow.as_ref().unwrap().offset_x, 4.0);
+        assert_eq!((number(&text.spans[0].font_size), text.spans[0].font_weight.as_deref().copied()), (Some(32.0), Some(700)));
+        let Item::Image(image) = &resolved.layers[1].item else { panic!() };
+        assert_eq!(*image.stroke.as_ref().unwrap().width, 4.0);
+        assert_eq!([*image.focal_point[0], *image.focal_point[1]], [0.25, 0.5]);
+        let crop = image.crop.as_ref().unwrap();
+        assert_eq!((*crop.x, *crop.width), (4.0, 20.0));
+
+        // Background images and patterns bind their numbers too
+        let with_background = |background: &str| Sigil { background: serde_json::from_str(background).unwrap(), layers: Vec::new(), ..sigil.clone() };
+        let image = with_background(r#"{"type": "image", "source": "banner.png", "focal_point": [0.5, "{focus}"]}"#).resolve(&vars);
+        assert!(matches!(&image.background, Background::Image(image) if *image.focal_point[1] == 0.25));
+        let pattern = with_background(r#"{"type": "pattern", "source": "dots", "tile_size": ["{w / 10}", 8.0]}"#).resolve(&vars);
+        assert_eq!(pattern.background, Background::Pattern(BackgroundPattern { source: "dots".to_string(), tile_size: Some([20.0.into(), 8.0.into()]) }));
+    }
+
+    #[test]
+    fn conditions_remove_layers_during_resolve() {
+        let vars = HashMap::from([
+            ("is_premium".to_string(), "true".to_string()),
+            ("level".to_string(), "12".to_string()),
+            ("name".to_string(), "meetzli".to_string()),
+        ]);
+        assert_eq!(expr::condition("{is_premium} == true", &vars), Ok(true));
+        assert_eq!(expr::condition("{level} >= 10 && !is_premium", &vars), Ok(false));
+        assert_eq!(expr::condition("level < 5 || name == 'meetzli'", &vars), Ok(true));
+        assert_eq!(expr::condition("level == 12.0 && level % 2 == 0", &vars), Ok(true));
+        assert_eq!(expr::condition("name | upper", &vars), Ok(true));
+        // `||` and `&&` stop once the result is known, so the missing variable is never read
+        assert_eq!(expr::condition("is_premium || missing > 1", &vars), Ok(true));
+        assert_eq!(expr::condition("missing > 1", &vars).unwrap_err().kind, ExprErrorKind::MissingVariable("missing".to_string()));
+        assert_eq!(expr::parse("a < b < c").unwrap_err().to_string(), "Expected an operator, '|' or the end of the expression, found '<' at position 6");
+        assert_eq!(expr::parse("{level} >= 10 && {is_premium}").unwrap().variables(), ["level", "is_premium"]);
+        // Comparisons also work inside placeholders
+        assert_eq!(expr::render("{level >= 10}/{name != 'meetzli'}", &vars).unwrap(), "true/false");
+
+        let json = r##"[
+            {"id": "badge", "x": 0.0, "y": 0.0, "condition": "{is_premium} == true",
+             "item": {"type": "Text", "data": {"text": "{name}", "font_size": 12.0, "color": "#ffffff", "font_family": "Sans Serif"}}},
+            {"id": "veteran", "x": 0.0, "y": 0.0, "condition": "{level} >= 10",
+             "item": {"type": "Group", "data": {"width": 10.0, "height": 10.0, "children": [
+                {"id": "star", "x": 0.0, "y": 0.0, "condition": "{level} >= 50",
+                 "item": {"type": "Rect", "data": {"width": 10.0, "height": 10.0, "color": "#ffffff", "border_radius": 0.0}}},
+                {"id": "stripe", "x": 0.0, "y": 0.0, "condition": "!{is_premium}",
+                 "item": {"type": "Rect", "data": {"width": 10.0, "height": 10.0, "color": "#ffffff", "border_radius": 0.0}}},
+                {"id": "plate", "x": 0.0, "y": 0.0,
+                 "item": {"type": "Rect", "data": {"width": 10.0, "height": 10.0, "color": "#ffffff", "border_radius": 0.0}}}
+             ]}}},
+            {"id": "streak", "x": 0.0, "y": 0.0, "condition": "{streak} > 3",
+             "item": {"type": "Rect", "data": {"width": 10.0, "height": 10.0, "color": "#ffffff", "border_radius": 0.0}}}
+        ]"##;
+        let layers: Vec<Layer> = serde_json::from_str(json).unwrap();
+        let sigil = Sigil { version: FORMAT_VERSION, width: 10, height: 10, background: "#000000".into(), variables: Vec::new(), layers };
+        assert_eq!(serde_json::to_value(&sigil).unwrap()["layers"][0]["condition"], "{is_premium} == true");
+
+        let resolved = sigil.resolve(&vars);
+        let ids: Vec<&str> = resolved.all_layers().map(|layer| layer.id.as_str()).collect();
+        // A condition reading a missing variable does not hold
+        assert_eq!(ids, ["badge", "veteran", "plate"]);
+        assert!(resolved.all_layers().all(|layer| layer.condition.is_none()));
+        let Item::Text(badge) = &resolved.layers[0].item else { panic!() };
+        assert_eq!(badge.text, "meetzli");
+
+        // Editors preview one layer at a time; declared defaults apply as in `resolve`
+        let mut sigil = sigil;
+        sigil.variables = vec![VariableDef { default: Some("5".to_string()), ..VariableDef::new("streak") }];
+        assert!(sigil.resolve_layer(&sigil.layers[2], &HashMap::new()).is_some());
+        assert!(sigil.resolve_layer(&sigil.layers[0], &HashMap::new()).is_none());
+    }
+
+    #[test]
+    fn migration_loads_every_historical_version() {
+        let load = |json: &str| migrate(serde_json::from_str(json).unwrap()).unwrap();
+
+        // Templates from before versioning name an image with a plain string background, written as a typed one
+        let baseline = load(include_str!("../fixtures/unversioned-baseline.json"));
+        let expected: serde_json::Value = serde_json::from_str(include_str!("../fixtures/unversioned-baseline.v1.json")).unwrap();
+        assert_eq!(serde_json::to_value(&baseline).unwrap(), expected);
+        assert_eq!(baseline.background, Background::Image(BackgroundImage::new("backgrounds/forest.png")));
+        // Placeholders are kept as written
+        let Item::Text(text) = &baseline.layers[1].item else { panic!() };
+        assert_eq!(text.text, "Welcome {username}!");
+        // Variables in the background stay colors
+        let variable_background = load(r#"{"width": 10, "height": 10, "background": "{theme}", "layers": []}"#);
+        assert_eq!(variable_background.background, Background::Solid("{theme}".to_string()));
+
+        let shapes = load(include_str!("../fixtures/v1-shapes.json"));
+        let ids: Vec<&str> = shapes.all_layers().map(|layer| layer.id.as_str()).collect();
+        assert_eq!(ids, ["panel", "badge", "hexagon", "portrait", "star", "title"]);
+        let variables = load(include_str!("../fixtures/v1-variables.json"));
+        assert_eq!(variables.variables.len(), 5);
+        assert!(matches!(variables.layers[2].item, Item::Repeat(_)));
+        let current = load(include_str!("../fixtures/v1.json"));
+        assert!(matches!(&current.background, Background::Image(image) if image.fit == ImageFit::Contain && image.overlay.is_some()));
+
+        // Migrating what was migrated, and serialized, changes nothing
+        for sigil in [baseline, shapes, variables, current] {
+            assert_eq!(migrate(serde_json::to_value(&sigil).unwrap()).unwrap(), sigil);
+        }
+
+        let error = |json: serde_json::Value| migrate(json).unwrap_err();
+        assert!(matches!(error(serde_json::json!([])), MigrationError::NotAnObject));
+        assert!(matches!(error(serde_json::json!({"version": "1"})), MigrationError::InvalidVersion(_)));
+        assert_eq!(
+            error(serde_json::json!({"version": 7, "width": 10})).to_string(),
+            "Version 7 is newer than this version of Sigil supports (1)"
+        );
+        assert!(matches!(error(serde_json::json!({"width": 10, "height": 10})), MigrationError::Invalid(_)));
+    }
+
+    #[test]
+    #[cfg(feature = "schema")]
+    fn schema_accepts_the_example_templates() {
+        let schema = serde_json::to_value(json_schema()).unwrap();
+        let published: serde_json::Value = serde_json::from_str(include_str!("../sigil.schema.json")).unwrap();
+        assert!(schema == published, "sigil.schema.json is out of date, regenerate it as described in the schema module");
+        assert_eq!(schema["$schema"], "https://json-schema.org/draft/2020-12/schema");
+
+        let validator = jsonschema::draft202012::new(&schema).unwrap();
+        let assert_valid = |json: &serde_json::Value| {
+            let errors: Vec<String> = validator.iter_errors(json).map(|err| format!("{} at {}", err, err.instance_path())).collect();
+            assert!(errors.is_empty(), "{errors:#?}");
+        };
+
+        for template in [
+            include_str!("../fixtures/unversioned-baseline.json"),
+            include_str!("../fixtures/v1-shapes.json"),
+            include_str!("../fixtures/v1-variables.json"),
+            include_str!("../fixtures/v1.json"),
+        ] {
+            let json: serde_json::Value = serde_json::from_str(template).unwrap();
+            assert_valid(&json);
+            assert_valid(&serde_json::to_value(migrate(json).unwrap()).unwrap());
+        }
+
+        // Items are adjacently tagged, and bindable fields take a number or a template
+        let layer = |item: serde_json::Value| serde_json::json!({ "width": 10, "height": 10, "background": "#000", "layers": [{ "id": "a", "x": "{x}", "y": 0, "item": item }] });
+        assert!(validator.is_valid(&layer(serde_json::json!({ "type": "Ellipse", "data": { "width": 4, "height": "{size}", "color": "#fff" } }))));
+        assert!(!validator.is_valid(&layer(serde_json::json!({ "type": "Ellipse", "data": { "width": 4, "height": true, "color": "#fff" } }))));
+        assert!(!validator.is_valid(&layer(serde_json::json!({ "type": "Circle", "data": { "width": 4, "height": 4, "color": "#fff" } }))));
+        assert!(!validator.is_valid(&layer(serde_json::json!({ "width": 4, "height": 4, "color": "#fff" }))));
+    }
+
+    #[test]
+    fn validation_reports_issues_with_json_paths() {
+        let sigil: Sigil = serde_json::from_value(serde_json::json!({
+            "version": 1,
+            "width": 200,
+            "height": 100,
+            "background": { "type": "image", "source": "backgrounds/forest.png" },
+            "variables": [
+                { "name": "count", "type": "number", "default": "many" },
+                { "name": "count" }
+            ],
+            "layers": [
+                { "id": "card", "x": 0, "y": 0, "item": { "type": "Rect", "data": { "width": 0, "height": 40, "color": "blue-ish", "border_radius": 0 } } },
+                { "id": "card", "x": 10, "y": 10, "condition": "{count} <", "item": { "type": "Ellipse", "data": { "width": 20, "height": 20, "color": "{accent}" } } },
+                { "id": "away", "x": 300, "y": 10, "mask": { "source": { "type": "layer", "id": "nowhere" } },
+                  "item": { "type": "Rect", "data": { "width": 20, "height": 20, "color": "#fff", "border_radius": 0 } } },
+                { "id": "photo", "x": 0, "y": 0, "item": { "type": "Image", "data": { "source": "photo.png", "width": 20, "height": 20, "border_radius": 0 } } },
+                { "id": "rows", "x": 0, "y": 0, "item": { "type": "Repeat", "data": {
+                    "source": "players", "width": 100, "height": 20, "template": [
+                        { "id": "card", "x": 0, "y": 0, "item": { "type": "Text", "data": { "text": "{item.name}", "font_size": 12, "color": "#fff", "font_family": "Comic Neue" } } },
+                        { "id": "avatar", "x": 0, "y": 0, "item": { "type": "Image", "data": { "source": "https://example.com/{item.id}.png", "width": 20, "height": 20, "border_radius": 0 } } }
+                    ]
+                } } }
+            ]
+        }))
+        .unwrap();
+
+        let issues: Vec<String> = sigil.validate().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            issues,
+            [
+                "error at variables[0].default: Variable 'count' must be a number, got 'many'",
+                "error at variables[1].name: Variable 'count' is declared twice",
+                "error at layers[0].item.data.width: Width must be positive, got 0",
+                "error at layers[0].item.data.color: Unknown color name: blue-ish",
+                "error at layers[1].id: Duplicate layer id 'card', also used at layers[0]",
+                "error at layers[1].condition: Expected a value, found end of expression at position 9",
+                "error at layers[2].mask.source.id: No sibling layer with id 'nowhere'",
+                "warning at layers[2]: Layer is entirely outside the canvas",
+            ]
+        );
+
+        // Ids only need to be unique within a repeater's template, and placeholders are valid colors
+        assert!(!issues.iter().any(|issue| issue.contains("template") || issue.contains("layers[1].item")));
+
+        let fonts = ["Arial".to_string()];
+        let resources: Vec<String> = sigil.validate_resources(&fonts, &[]).iter().map(ToString::to_string).collect();
+        assert_eq!(
+            resources,
+            [
+                "warning at background.source: Resource 'backgrounds/forest.png' not found",
+                "warning at layers[3].item.data.source: Resource 'photo.png' not found",
+                "warning at layers[4].item.data.template[0].item.data.font_family: Unknown font 'Comic Neue', drawn with the default font",
+            ]
+        );
+        assert_eq!(sigil.validate_resources(&fonts, &["photo.png".to_string(), "backgrounds/forest.png".to_string()]).len(), 1);
+
+        let fixture = migrate(serde_json::from_str(include_str!("../fixtures/v1.json")).unwrap()).unwrap();
+        assert_eq!(fixture.validate(), []);
+
+        let mut text = fixture.clone();
+        let Item::Text(item) = &mut text.layers[0].item else { panic!() };
+        item.line_height = Some(0.0.into());
+        item.spans = vec![TextSpan { text: "hi".to_string(), font_size: Some(0.0.into()), ..Default::default() }];
+        let issues: Vec<String> = text.validate().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            issues,
+            [
+                "error at layers[0].item.data.line_height: Line height must be positive, got 0",
+                "error at layers[0].item.data.spans[0].font_size: Font size must be positive, got 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn repeaters_lay_out_a_cell_per_list_entry() {
+        let data = serde_json::json!({
+            "title": "Top players",
+            "season": {"number": 3, "ended": false},
+            "players": [
+                {"name": "meetzli", "score": 1200, "premium": true},
+                {"name": "ferris", "score": 950},
+                {"name": "corro", "score": null, "premium": true}
+            ]
+        });
+        let vars = variables_from_json(&data);
+        assert_eq!(vars["season.number"], "3");
+        assert_eq!(vars["players.1.name"], "ferris");
+        assert!(!vars.contains_key("players.2.score"));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&vars["players"]).unwrap(), data["players"]);
+
+        let json = r##"{"width": 200, "height": 200, "background": "#000000",
+            "variables": [{"name": "title"}, {"name": "season.number", "type": "number"}, {"name": "season.ended", "type": "boolean"},
+                          {"name": "players", "type": "list"}],
+            "layers": [
+                {"id": "board", "x": 10.0, "y": 20.0, "item": {"type": "Repeat", "data": {
+                    "source": "players", "width": 180.0, "height": 30.0, "gap": 5.0, "max_items": 2, "template": [
+                        {"id": "name", "x": 4.0, "y": 0.0, "item": {"type": "Text", "data":
+                            {"text": "{index + 1}. {item.name} ({item.score | number})", "font_size": 12.0, "color": "#ffffff", "font_family": "Sans Serif"}}},
+                        {"id": "crown", "x": "{item.score / 10}", "y": 0.0, "condition": "{item.premium}", "mask": {"source": {"type": "layer", "id": "name"}},
+                         "item": {"type": "Rect", "data": {"width": 10.0, "height": 10.0, "color": "#ffd700", "border_radius": 0.0}}}
+                    ]}}},
+                {"id": "tags", "x": 0.0, "y": 0.0, "item": {"type": "Repeat", "data": {
+                    "source": "tags", "direction": "horizontal", "width": 20.0, "height": 10.0, "template": [
+                        {"id": "tag", "x": 0.0, "y": 0.0, "item": {"type": "Text", "data": {"text": "#{item}", "font_size": 8.0, "color": "#ffffff", "font_family": "Sans Serif"}}}
+                    ]}}}
+            ]}"##;
+        let sigil: Sigil = serde_json::from_str(json).unwrap();
+        assert_eq!(sigil.validate_variables(&vars), Ok(()));
+        assert_eq!(
+            sigil.validate_variables(&HashMap::from([("title".to_string(), "x".to_string()), ("players".to_string(), "{}".to_string())])),
+            Err(vec![
+                VariableError::Missing { name: "season.number".to_string() },
+                VariableError::Missing { name: "season.ended".to_string() },
+                VariableError::InvalidType { name: "players".to_string(), expected: VariableType::List, value: "{}".to_string() },
+            ])
+        );
+
+        let resolved = sigil.resolve_json(&data);
+        let Item::Group(board) = &resolved.layers[0].item else { panic!("repeaters resolve to groups") };
+        assert_eq!((*board.width, *board.height), (180.0, 65.0));
+        let ids: Vec<&str> = resolved.layers[0].flatten().iter().map(|layer| layer.id.as_str()).collect();
+        // Only the first entry is premium, and `max_items` leaves out the third
+        assert_eq!(ids, ["board", "board-0", "name-0", "crown-0", "board-1", "name-1"]);
+
+        let second = &board.children[1];
+        assert_eq!((*second.x, *second.y), (0.0, 35.0));
+        let Item::Group(cell) = &second.item else { panic!() };
+        let Item::Text(name) = &cell.children[0].item else { panic!() };
+        assert_eq!(name.text, "2. ferris (950)");
+        let Item::Group(cell) = &board.children[0].item else { panic!() };
+        assert_eq!(*cell.children[1].x, 120.0);
+        // Masks follow the renamed layers they refer to
+        assert_eq!(cell.children[1].mask.as_ref().unwrap().layer_id(), Some("name-0"));
+
+        // Lists of plain values, given as JSON text like any variable
+        let resolved = sigil.resolve(&HashMap::from([("tags".to_string(), r#"["new", "hot"]"#.to_string())]));
+        let Item::Group(tags) = &resolved.layers[1].item else { panic!() };
+        assert_eq!((*tags.width, *tags.height), (40.0, 10.0));
+        let texts: Vec<&str> = resolved.layers[1]
+            .flatten()
+            .into_iter()
+            .filter_map(|layer| match &layer.item {
+                Item::Text(text) => Some(text.text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts, ["#new", "#hot"]);
+        // Without its list, a repeater is an empty group
+        let Item::Group(board) = &resolved.layers[0].item else { panic!() };
+        assert!(board.children.is_empty());
+    }
+
+    #[test]
+    fn layer_masks_reference_layers_or_inline_shapes() {
+        let json = r##"[
+            {"id": "avatar", "x": 0.0, "y": 0.0, "mask": {"source": {"type": "layer", "id": "hexagon"}, "mode": "clip"},
+             "item": {"type": "Image", "data": {"source": "{avatar}", "width": 10.0, "height": 10.0, "border_radius": 0.0}}},
+            {"id": "photo", "x": 0.0, "y": 0.0, "mask": {"source": {"type": "shape", "x": 0.0, "y": 0.0,
+                "item": {"type": "Rect", "data": {"width": 10.0, "height": 10.0, "color": "{fade}", "border_radius": 0.0}}}},
+             "item": {"type": "Image", "data": {"source": "photo.png", "width": 10.0, "height": 10.0, "border_radius": 0.0}}}
+        ]"##;
+        let layers: Vec<Layer> = serde_json::from_str(json).unwrap();
+        let avatar_mask = layers[0].mask.as_ref().unwrap();
+        assert_eq!(avatar_mask.layer_id(), Some("hexagon"));
+        assert_eq!(avatar_mask.mode, MaskMode::Clip);
+        assert!(!layers[0].is_opaque_normal());
+
+        let photo_mask = layers[1].mask.as_ref().unwrap();
+        assert_eq!(photo_mask.mode, MaskMode::Alpha);
+        assert_eq!(photo_mask.layer_id(), None);
+
+        let sigil = Sigil { version: FORMAT_VERSION, width: 10, height: 10, background: "#000000".into(), variables: Vec::new(), layers };
+        let resolved = sigil.resolve(&HashMap::from([("fade".to_string(), "#ffffff80".to_string())]));
+        let Some(LayerMask { source: MaskSource::Shape { item, .. }, .. }) = &resolved.layers[1].mask else { panic!("expected a shape mask") };
+        let Item::Rect(rect) = item.as_ref() else { panic!("expected a rect") };
+        assert_eq!(rect.color, Fill::from("#ffffff80"));
+    }
+
+    #[test]
+    fn layer_effects_serialize_tagged() {
+        let json = r##"{"id": "card", "x": 0.0, "y": 0.0,
+            "effects": [
+                {"type": "drop_shadow", "color": "{shadow}", "offset_y": 4.0, "blur": 12.0},
+                {"type": "background_blur", "radius": 8.0}
+            ],
+            "item": {"type": "Rect", "data": {"width": 10.0, "height": 10.0, "color": "#ffffff", "border_radius": 0.0}}}"##;
+        let layer: Layer = serde_json::from_str(json).unwrap();
+        let Effect::DropShadow(shadow) = &layer.effects[0] else { panic!("expected a drop shadow") };
+        assert_eq!((*shadow.offset_x, *shadow.offset_y, *shadow.spread), (0.0, 4.0, 0.0));
+        assert_eq!(layer.effects[1], Effect::BackgroundBlur { radius: 8.0.into() });
+        assert!(!layer.is_opaque_normal());
+
+        let sigil = Sigil { version: FORMAT_VERSION, width: 10, height: 10, background: "#000000".into(), variables: Vec::new(), layers: vec![layer] };
+        let resolved = sigil.resolve(&HashMap::from([("shadow".to_string(), "#00000080".to_string())]));
+        let Effect::DropShadow(shadow) = &resolved.layers[0].effects[0] else { panic!("expected a drop shadow") };
+        assert_eq!(shadow.color, "#00000080");
+
+        let plain = Layer { effects: Vec::new(), ..resolved.layers[0].clone() };
+        assert!(serde_json::to_value(&plain).unwrap().get("effects").is_none());
+    }
 }
@@ -0,0 +1,66 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! Layer masks: another layer or an inline shape limiting where a layer shows, like CSS `mask`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::Item;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LayerMask {
+    pub source: MaskSource,
+    #[serde(default)]
+    pub mode: MaskMode,
+}
+
+/// What a mask is drawn from. Mask sources are positioned like the masked layer's siblings,
+/// i.e. on the canvas or inside the same group.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MaskSource {
+    /// A sibling layer, which is then only used as a mask and not drawn itself.
+    /// Only its item and position are used; its opacity, effects and own mask are ignored.
+    Layer { id: String },
+    /// A shape item used only as this mask.
+    Shape {
+        x: f32,
+        y: f32,
+        #[serde(default)]
+        rotation: f32,
+        item: Box<Item>,
+    },
+}
+
+/// How the mask source turns into coverage.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum MaskMode {
+    /// The source's alpha, so transparent gradient stops fade the layer out.
+    #[default]
+    Alpha,
+    /// The source's luminance times its alpha, like CSS `mask-mode: luminance`: white shows, black hides.
+    Luminance,
+    /// The source's shape at full strength, whatever its colors or transparency.
+    Clip,
+}
+
+impl LayerMask {
+    /// Id of the sibling layer used as the mask, if any.
+    pub fn layer_id(&self) -> Option<&str> {
+        match &self.source {
+            MaskSource::Layer { id } => Some(id),
+            MaskSource::Shape { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,77 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! Upgrades stored sigil JSON from older versions of the format.
+//!
+//! Each format change that old templates cannot simply be read with gets a new [`FORMAT_VERSION`] and a step in
+//! [`MIGRATIONS`] that rewrites the JSON of the version before it. [`migrate`] runs the steps a template needs
+//! in order, so a step only ever has to know the version right before its own.
+
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::{Rgba, Sigil};
+
+/// The version of the JSON format written by this crate.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Upgrade steps, each from the version at its index to the next one.
+const MIGRATIONS: [fn(&mut Map<String, Value>); FORMAT_VERSION as usize] = [unversioned_to_v1];
+
+/// Why stored JSON could not be loaded as a sigil.
+#[derive(Error, Debug)]
+pub enum MigrationError {
+    #[error("A sigil must be a JSON object")]
+    NotAnObject,
+
+    #[error("Invalid version {0}, expected a whole number")]
+    InvalidVersion(Value),
+
+    #[error("Version {version} is newer than this version of Sigil supports ({FORMAT_VERSION})")]
+    UnsupportedVersion { version: u64 },
+
+    #[error("Invalid sigil: {0}")]
+    Invalid(#[from] serde_json::Error),
+}
+
+/// Loads sigil JSON of any version, upgrading it to [`FORMAT_VERSION`]. JSON without a version is from before
+/// versioning, version 0.
+pub fn migrate(mut json: Value) -> Result<Sigil, MigrationError> {
+    let Value::Object(sigil) = &mut json else {
+        return Err(MigrationError::NotAnObject);
+    };
+
+    let version = match sigil.get("version") {
+        None => 0,
+        Some(version) => version.as_u64().ok_or_else(|| MigrationError::InvalidVersion(version.clone()))?,
+    };
+    if version > u64::from(FORMAT_VERSION) {
+        return Err(MigrationError::UnsupportedVersion { version });
+    }
+
+    for step in &MIGRATIONS[version as usize..] {
+        step(sigil);
+    }
+    sigil.insert("version".to_string(), FORMAT_VERSION.into());
+
+    Ok(serde_json::from_value(json)?)
+}
+
+/// Templates from before versioning named a background image with a plain string, which version 1 writes as a
+/// typed image background. Everything else those templates use is still read as written.
+fn unversioned_to_v1(sigil: &mut Map<String, Value>) {
+    if let Some(background) = sigil.get_mut("background")
+        && let Value::String(source) = background
+        && !source.contains('{')
+        && Rgba::parse(source).is_err()
+    {
+        *background = serde_json::json!({ "type": "image", "source": source });
+    }
+}
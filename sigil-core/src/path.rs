@@ -0,0 +1,88 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! SVG path data, parsed into absolute move, line, curve and close segments.
+
+use svgtypes::{SimplePathSegment, SimplifyingPathParser};
+use thiserror::Error;
+
+/// A path segment with absolute coordinates. Arcs and shorthand curves are converted to curves when parsing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathSegment {
+    MoveTo(f32, f32),
+    LineTo(f32, f32),
+    /// Control point, then end point.
+    QuadTo(f32, f32, f32, f32),
+    /// Two control points, then end point.
+    CubicTo(f32, f32, f32, f32, f32, f32),
+    Close,
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("Invalid path data: {0}")]
+pub struct PathDataError(pub String);
+
+/// Parses the `d` attribute of an SVG `<path>`.
+pub fn parse_path_data(d: &str) -> Result<Vec<PathSegment>, PathDataError> {
+    SimplifyingPathParser::from(d)
+        .map(|segment| {
+            let segment = segment.map_err(|err| PathDataError(err.to_string()))?;
+            Ok(match segment {
+                SimplePathSegment::MoveTo { x, y } => PathSegment::MoveTo(x as f32, y as f32),
+                SimplePathSegment::LineTo { x, y } => PathSegment::LineTo(x as f32, y as f32),
+                SimplePathSegment::Quadratic { x1, y1, x, y } => PathSegment::QuadTo(x1 as f32, y1 as f32, x as f32, y as f32),
+                SimplePathSegment::CurveTo { x1, y1, x2, y2, x, y } => {
+                    PathSegment::CubicTo(x1 as f32, y1 as f32, x2 as f32, y2 as f32, x as f32, y as f32)
+                }
+                SimplePathSegment::ClosePath => PathSegment::Close,
+            })
+        })
+        .collect()
+}
+
+/// Writes segments back out as SVG path data.
+pub fn format_path_data(segments: &[PathSegment]) -> String {
+    segments
+        .iter()
+        .map(|segment| match *segment {
+            PathSegment::MoveTo(x, y) => format!("M {} {}", x, y),
+            PathSegment::LineTo(x, y) => format!("L {} {}", x, y),
+            PathSegment::QuadTo(x1, y1, x, y) => format!("Q {} {} {} {}", x1, y1, x, y),
+            PathSegment::CubicTo(x1, y1, x2, y2, x, y) => format!("C {} {} {} {} {} {}", x1, y1, x2, y2, x, y),
+            PathSegment::Close => "Z".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl PathSegment {
+    /// The segment with every point mapped through `f`.
+    pub fn map_points(self, f: impl Fn(f32, f32) -> (f32, f32)) -> Self {
+        match self {
+            PathSegment::MoveTo(x, y) => {
+                let (x, y) = f(x, y);
+                PathSegment::MoveTo(x, y)
+            }
+            PathSegment::LineTo(x, y) => {
+                let (x, y) = f(x, y);
+                PathSegment::LineTo(x, y)
+            }
+            PathSegment::QuadTo(x1, y1, x, y) => {
+                let ((x1, y1), (x, y)) = (f(x1, y1), f(x, y));
+                PathSegment::QuadTo(x1, y1, x, y)
+            }
+            PathSegment::CubicTo(x1, y1, x2, y2, x, y) => {
+                let ((x1, y1), (x2, y2), (x, y)) = (f(x1, y1), f(x2, y2), f(x, y));
+                PathSegment::CubicTo(x1, y1, x2, y2, x, y)
+            }
+            PathSegment::Close => PathSegment::Close,
+        }
+    }
+}
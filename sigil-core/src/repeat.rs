@@ -0,0 +1,111 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! Repeaters: layers drawn once for every entry of a list variable, such as the rows of a leaderboard.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::variable::flatten_json;
+use crate::{Bindable, Layer};
+
+/// Draws `template` once for each entry of the list variable `source`, in cells laid out in a row or column.
+///
+/// Within the template, `{item}` is the entry, or `{item.name}` one of its fields for entries that are objects,
+/// and `{index}` counts entries from 0. [`crate::Sigil::resolve`] replaces the repeater with a group of cells,
+/// so renderers only ever see resolved repeaters as groups, and draw nothing for unresolved ones.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RepeatItem {
+    /// Name of the variable holding the entries, a JSON array such as `[{"name": "meetzli", "score": 1200}]`.
+    pub source: String,
+    /// Width of one entry's cell.
+    pub width: Bindable<f32>,
+    /// Height of one entry's cell.
+    pub height: Bindable<f32>,
+    #[serde(default)]
+    pub direction: RepeatDirection,
+    /// Space between cells.
+    #[serde(default)]
+    pub gap: Bindable<f32>,
+    /// Entries past this many are left out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<usize>,
+    /// Drawn for every entry, positioned within its cell. Layer ids get the entry's index appended, as `row-0`.
+    pub template: Vec<Layer>,
+}
+
+/// Which way a repeater's cells follow each other.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum RepeatDirection {
+    /// Top to bottom.
+    #[default]
+    Vertical,
+    /// Left to right.
+    Horizontal,
+}
+
+impl RepeatDirection {
+    pub const ALL: [RepeatDirection; 2] = [RepeatDirection::Vertical, RepeatDirection::Horizontal];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RepeatDirection::Vertical => "vertical",
+            RepeatDirection::Horizontal => "horizontal",
+        }
+    }
+}
+
+impl RepeatItem {
+    /// The variables for each entry to draw: the given ones plus `item`, its fields and `index`. A source that is
+    /// missing or not a JSON array has no entries.
+    pub(crate) fn entries(&self, variables: &HashMap<String, String>) -> Vec<HashMap<String, String>> {
+        let items = variables.get(&self.source).and_then(|list| serde_json::from_str::<Vec<Value>>(list).ok()).unwrap_or_default();
+
+        let mut outer = variables.clone();
+        // An outer repeater's entry must not show through where this entry lacks a field
+        outer.retain(|name, _| name != "item" && !name.starts_with("item."));
+
+        items
+            .iter()
+            .take(self.max_items.unwrap_or(usize::MAX))
+            .enumerate()
+            .map(|(index, item)| {
+                let mut entry = outer.clone();
+                flatten_json("item".to_string(), item, &mut entry);
+                entry.insert("index".to_string(), index.to_string());
+                entry
+            })
+            .collect()
+    }
+
+    /// Top left corner of the cell for entry `index`, relative to the repeater.
+    pub fn cell_position(&self, index: usize) -> (f32, f32) {
+        let offset = index as f32;
+        match self.direction {
+            RepeatDirection::Vertical => (0.0, offset * (*self.height + *self.gap)),
+            RepeatDirection::Horizontal => (offset * (*self.width + *self.gap), 0.0),
+        }
+    }
+
+    /// Size of the box holding `count` cells and the gaps between them.
+    pub fn size_for(&self, count: usize) -> (f32, f32) {
+        let gaps = count.saturating_sub(1) as f32 * *self.gap;
+        let count = count as f32;
+        match self.direction {
+            RepeatDirection::Vertical => (*self.width, count * *self.height + gaps),
+            RepeatDirection::Horizontal => (count * *self.width + gaps, *self.height),
+        }
+    }
+}
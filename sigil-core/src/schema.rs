@@ -0,0 +1,25 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! JSON Schema for the template format, so editors can complete and check templates as they are written.
+//!
+//! The schema is generated from the types themselves, with their doc comments as descriptions and their serde
+//! defaults. `sigil.schema.json` in this crate is a copy for editors to point at; regenerate it with
+//! `cargo run -p sigil-core --example schema --features schema > sigil-core/sigil.schema.json`, and check it with
+//! `cargo test -p sigil-core --features schema`.
+
+use schemars::Schema;
+
+use crate::Sigil;
+
+/// The JSON Schema (draft 2020-12) of [`Sigil`] JSON at [`crate::FORMAT_VERSION`].
+pub fn json_schema() -> Schema {
+    schemars::schema_for!(Sigil)
+}
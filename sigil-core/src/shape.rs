@@ -0,0 +1,229 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! Vector shape items and the geometry shared by the raster and HTML renderers.
+//!
+//! Coordinates are relative to the layer position; points are expected to lie at or right/below it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::path::{parse_path_data, PathDataError, PathSegment};
+use crate::{Bindable, Fill, ShapeStroke};
+use std::f32::consts::{FRAC_PI_2, PI};
+
+/// An ellipse filling its `width` x `height` box.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct EllipseItem {
+    pub width: Bindable<f32>,
+    pub height: Bindable<f32>,
+    pub color: Fill,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stroke: Option<ShapeStroke>,
+}
+
+/// A straight line from (`x1`, `y1`) to (`x2`, `y2`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LineItem {
+    pub x1: Bindable<f32>,
+    pub y1: Bindable<f32>,
+    pub x2: Bindable<f32>,
+    pub y2: Bindable<f32>,
+    pub color: String,
+    pub stroke_width: Bindable<f32>,
+    #[serde(default)]
+    pub cap: LineCap,
+    /// Alternating dash and gap lengths in pixels, like SVG `stroke-dasharray`. Solid when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dash: Vec<f32>,
+}
+
+/// How the ends of a line are drawn, like SVG `stroke-linecap`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+/// A closed polygon through `points`, filled with the nonzero rule like SVG `<polygon>`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PolygonItem {
+    pub points: Vec<[f32; 2]>,
+    pub color: Fill,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stroke: Option<ShapeStroke>,
+}
+
+/// Most tips a star, or corners a regular polygon, is drawn with. Counts bound to variables can be anything, and
+/// past this many the shape looks the same as an ellipse anyway.
+pub const MAX_CORNERS: u32 = 1000;
+
+/// A star with `points` tips touching its `width` x `height` box, the first pointing up. Drawn with 2 to
+/// [`MAX_CORNERS`] tips.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct StarItem {
+    pub width: Bindable<f32>,
+    pub height: Bindable<f32>,
+    pub points: Bindable<u32>,
+    /// Distance of the inner corners from the center, as a fraction of the outer radius. Defaults to 0.5.
+    #[serde(default = "default_inner_ratio")]
+    pub inner_ratio: Bindable<f32>,
+    pub color: Fill,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stroke: Option<ShapeStroke>,
+}
+
+/// A regular polygon with `sides` corners on the ellipse inscribed in its box, the first at the top. Drawn with
+/// 3 to [`MAX_CORNERS`] corners.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RegularPolygonItem {
+    pub width: Bindable<f32>,
+    pub height: Bindable<f32>,
+    pub sides: Bindable<u32>,
+    pub color: Fill,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stroke: Option<ShapeStroke>,
+}
+
+/// An SVG path, such as an icon exported from a design tool.
+///
+/// Coordinates in `d` are mapped from `view_box` onto the `width` x `height` box, stretching if the aspect
+/// ratios differ; without a view box they are in pixels. Stroke widths are always in pixels.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PathItem {
+    pub d: String,
+    pub width: Bindable<f32>,
+    pub height: Bindable<f32>,
+    /// `[min_x, min_y, width, height]` of the coordinate system `d` is written in, like SVG `viewBox`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view_box: Option<[f32; 4]>,
+    /// The path is not filled when unset, like `fill="none"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fill: Option<Fill>,
+    #[serde(default)]
+    pub fill_rule: FillRule,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stroke: Option<ShapeStroke>,
+}
+
+/// Which areas of a self-intersecting path are inside, named like SVG `fill-rule` values.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum FillRule {
+    #[default]
+    Nonzero,
+    EvenOdd,
+}
+
+fn default_inner_ratio() -> Bindable<f32> {
+    Bindable::new(0.5)
+}
+
+/// Corners evenly spaced around the ellipse inscribed in a `width` x `height` box, starting at the top
+/// and going clockwise, with every other corner pulled in to `inner_ratio` when it is set.
+fn radial_vertices(width: f32, height: f32, corners: u32, inner_ratio: Option<f32>) -> Vec<[f32; 2]> {
+    let (rx, ry) = (width / 2.0, height / 2.0);
+    let count = match inner_ratio {
+        Some(_) => corners * 2,
+        None => corners,
+    };
+
+    (0..count)
+        .map(|i| {
+            let angle = -FRAC_PI_2 + 2.0 * PI * i as f32 / count as f32;
+            let scale = match inner_ratio {
+                Some(ratio) if i % 2 == 1 => ratio,
+                _ => 1.0,
+            };
+            [rx + rx * scale * angle.cos(), ry + ry * scale * angle.sin()]
+        })
+        .collect()
+}
+
+/// Size of the box from the layer position to the farthest point.
+fn points_size(points: &[[f32; 2]]) -> (f32, f32) {
+    points
+        .iter()
+        .fold((0.0, 0.0), |(w, h), [x, y]| (f32::max(w, *x), f32::max(h, *y)))
+}
+
+/// Scales every point so that the points fill a `width` x `height` box.
+fn scale_points<'a>(points: impl IntoIterator<Item = &'a mut [f32; 2]>, from: (f32, f32), width: f32, height: f32) {
+    let sx = if from.0 > 0.0 { width / from.0 } else { 1.0 };
+    let sy = if from.1 > 0.0 { height / from.1 } else { 1.0 };
+    for point in points {
+        point[0] *= sx;
+        point[1] *= sy;
+    }
+}
+
+impl StarItem {
+    /// Alternating outer and inner corners, starting with the top tip.
+    pub fn vertices(&self) -> Vec<[f32; 2]> {
+        radial_vertices(*self.width, *self.height, self.points.clamp(2, MAX_CORNERS), Some(*self.inner_ratio))
+    }
+}
+
+impl RegularPolygonItem {
+    pub fn vertices(&self) -> Vec<[f32; 2]> {
+        radial_vertices(*self.width, *self.height, self.sides.clamp(3, MAX_CORNERS), None)
+    }
+}
+
+impl PathItem {
+    /// The parsed path in box coordinates, i.e. with the view box applied.
+    pub fn segments(&self) -> Result<Vec<PathSegment>, PathDataError> {
+        let segments = parse_path_data(&self.d)?;
+        let Some([min_x, min_y, vb_width, vb_height]) = self.view_box.filter(|vb| vb[2] > 0.0 && vb[3] > 0.0) else {
+            return Ok(segments);
+        };
+
+        let (sx, sy) = (*self.width / vb_width, *self.height / vb_height);
+        Ok(segments
+            .into_iter()
+            .map(|segment| segment.map_points(|x, y| ((x - min_x) * sx, (y - min_y) * sy)))
+            .collect())
+    }
+}
+
+impl PolygonItem {
+    pub fn size(&self) -> (f32, f32) {
+        points_size(&self.points)
+    }
+
+    pub fn resize(&mut self, width: f32, height: f32) {
+        let from = self.size();
+        scale_points(&mut self.points, from, width, height);
+    }
+}
+
+impl LineItem {
+    pub fn size(&self) -> (f32, f32) {
+        points_size(&[[*self.x1, *self.y1], [*self.x2, *self.y2]])
+    }
+
+    pub fn resize(&mut self, width: f32, height: f32) {
+        let from = self.size();
+        let mut points = [[*self.x1, *self.y1], [*self.x2, *self.y2]];
+        scale_points(&mut points, from, width, height);
+        let [[x1, y1], [x2, y2]] = points;
+        [self.x1, self.y1, self.x2, self.y2] = [x1.into(), y1.into(), x2.into(), y2.into()];
+    }
+}
@@ -0,0 +1,526 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! Checks of a whole template before rendering, reported with the JSON path of the offending value.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{
+    expr, Background, Bindable, Effect, Fill, ImageFilter, Item, Layer, LayerMask, MaskSource, Rgba, ShapeStroke, Sigil, TextItem,
+    MAX_CORNERS,
+};
+
+/// Font families every renderer can draw, matched case-insensitively.
+const GENERIC_FONT_FAMILIES: [&str; 7] = ["sans-serif", "sans serif", "serif", "monospace", "mono", "system-ui", "-apple-system"];
+
+/// A problem found by [`Sigil::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    /// Where the problem is in the template's JSON, such as `layers[3].item.data.color`.
+    pub path: String,
+    pub message: String,
+}
+
+/// Whether a renderer fails or draws something other than intended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Rendering fails, or the value is ignored.
+    Error,
+    /// The template renders, though likely not as meant.
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}: {}", self.severity, self.path, self.message)
+    }
+}
+
+/// Collects issues while walking the template. Values with `{...}` placeholders are only checked for syntax, as
+/// what they stand for is not known until [`Sigil::resolve`].
+struct Validator {
+    issues: Vec<ValidationIssue>,
+    /// Whether the sigil was resolved, so any binding left is one that could not be.
+    resolved: bool,
+}
+
+impl Validator {
+    fn error(&mut self, path: &str, message: impl Into<String>) {
+        self.issues.push(ValidationIssue { severity: Severity::Error, path: path.to_string(), message: message.into() });
+    }
+
+    fn warning(&mut self, path: &str, message: impl Into<String>) {
+        self.issues.push(ValidationIssue { severity: Severity::Warning, path: path.to_string(), message: message.into() });
+    }
+
+    fn template(&mut self, path: &str, text: &str) {
+        if let Err(err) = expr::check(text) {
+            self.error(path, err.to_string());
+        }
+    }
+
+    fn color(&mut self, path: &str, color: &str) {
+        if color.contains('{') {
+            self.template(path, color);
+        } else if let Err(err) = Rgba::parse(color) {
+            self.error(path, err.to_string());
+        }
+    }
+
+    fn fill(&mut self, path: &str, fill: &Fill) {
+        match fill {
+            Fill::Solid(color) => self.color(path, color),
+            Fill::Gradient(gradient) => {
+                if gradient.stops().is_empty() {
+                    self.error(&format!("{path}.stops"), "A gradient needs at least one stop");
+                }
+                for (index, stop) in gradient.stops().iter().enumerate() {
+                    self.color(&format!("{path}.stops[{index}].color"), &stop.color);
+                }
+            }
+        }
+    }
+
+    fn stroke(&mut self, path: &str, stroke: Option<&ShapeStroke>) {
+        if let Some(stroke) = stroke {
+            self.color(&format!("{path}.color"), &stroke.color);
+            self.bindings(path, &[("width", stroke.width.binding())]);
+        }
+    }
+
+    fn filters(&mut self, path: &str, filters: &[ImageFilter]) {
+        for (index, filter) in filters.iter().enumerate() {
+            if let ImageFilter::Tint { color, .. } = filter {
+                self.color(&format!("{path}[{index}].color"), color);
+            }
+        }
+    }
+
+    /// A width, height or similar that must be above zero, unless it is bound to a variable.
+    fn positive(&mut self, path: &str, name: &str, value: &Bindable<f32>) {
+        if value.binding().is_none() && **value <= 0.0 {
+            self.error(&format!("{path}.{name}"), format!("{} must be positive, got {}", capitalize(name), **value));
+        }
+    }
+
+    /// Bound fields must be valid templates, and in a resolved sigil there must be none left: a variable was
+    /// missing or its value did not fit the field, and the field reads as zero or `false`.
+    fn bindings(&mut self, path: &str, fields: &[(&str, Option<&str>)]) {
+        for (name, binding) in fields {
+            let Some(binding) = binding else { continue };
+            let path = format!("{path}.{name}");
+            if self.resolved {
+                self.error(&path, format!("Binding '{binding}' could not be resolved"));
+            } else {
+                self.template(&path, binding);
+            }
+        }
+    }
+
+    fn size(&mut self, path: &str, width: &Bindable<f32>, height: &Bindable<f32>) {
+        self.positive(path, "width", width);
+        self.positive(path, "height", height);
+    }
+
+    fn background(&mut self, background: &Background) {
+        match background {
+            Background::Solid(color) => self.color("background", color),
+            Background::Gradient(gradient) => self.fill("background", &Fill::Gradient(gradient.clone())),
+            Background::Image(image) => {
+                if image.source.trim().is_empty() {
+                    self.error("background.source", "The background image has no source");
+                }
+                let [focal_x, focal_y] = &image.focal_point;
+                self.bindings("background", &[("focal_point[0]", focal_x.binding()), ("focal_point[1]", focal_y.binding())]);
+                self.filters("background.filters", &image.filters);
+                if let Some(overlay) = &image.overlay {
+                    self.fill("background.overlay", overlay);
+                }
+            }
+            Background::Pattern(pattern) => {
+                if pattern.source.trim().is_empty() {
+                    self.error("background.source", "The background pattern has no source");
+                }
+                if let Some([width, height]) = &pattern.tile_size {
+                    self.bindings("background", &[("tile_size[0]", width.binding()), ("tile_size[1]", height.binding())]);
+                    let not_positive = |side: &Bindable<f32>| side.binding().is_none() && **side <= 0.0;
+                    if not_positive(width) || not_positive(height) {
+                        self.error("background.tile_size", format!("Tile size must be positive, got {} x {}", **width, **height));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Checks sibling layers. `ids` maps each layer id seen so far in the template to its path.
+    fn layers(&mut self, path: &str, layers: &[Layer], ids: &mut HashMap<String, String>, canvas: Option<(f32, f32)>) {
+        for (index, layer) in layers.iter().enumerate() {
+            let path = format!("{path}[{index}]");
+
+            if layer.id.is_empty() {
+                self.warning(&format!("{path}.id"), "Layer has no id");
+            } else if let Some(first) = ids.get(&layer.id) {
+                self.error(&format!("{path}.id"), format!("Duplicate layer id '{}', also used at {first}", layer.id));
+            } else {
+                ids.insert(layer.id.clone(), path.clone());
+            }
+
+            let fields = [
+                ("x", layer.x.binding()),
+                ("y", layer.y.binding()),
+                ("rotation", layer.rotation.binding()),
+                ("visible", layer.visible.binding()),
+                ("opacity", layer.opacity.binding()),
+            ];
+            self.bindings(&path, &fields);
+            if let Some(condition) = &layer.condition
+                && let Err(err) = expr::parse(condition)
+            {
+                self.error(&format!("{path}.condition"), err.to_string());
+            }
+            for (effect_index, effect) in layer.effects.iter().enumerate() {
+                let effect_path = format!("{path}.effects[{effect_index}]");
+                match effect {
+                    Effect::DropShadow(shadow) | Effect::InnerShadow(shadow) => {
+                        self.color(&format!("{effect_path}.color"), &shadow.color);
+                        let fields = [
+                            ("offset_x", shadow.offset_x.binding()),
+                            ("offset_y", shadow.offset_y.binding()),
+                            ("blur", shadow.blur.binding()),
+                            ("spread", shadow.spread.binding()),
+                        ];
+                        self.bindings(&effect_path, &fields);
+                    }
+                    Effect::LayerBlur { radius } | Effect::BackgroundBlur { radius } => {
+                        self.bindings(&effect_path, &[("radius", radius.binding())]);
+                    }
+                }
+            }
+            match &layer.mask {
+                Some(LayerMask { source: MaskSource::Layer { id }, .. }) if !layers.iter().any(|sibling| &sibling.id == id) => {
+                    self.error(&format!("{path}.mask.source.id"), format!("No sibling layer with id '{id}'"));
+                }
+                Some(LayerMask { source: MaskSource::Shape { item, .. }, .. }) => self.item(&format!("{path}.mask.source.item"), item, ids),
+                _ => {}
+            }
+
+            if let Some((width, height)) = canvas
+                && is_off_canvas(layer, width, height)
+            {
+                self.warning(&path, "Layer is entirely outside the canvas");
+            }
+
+            self.item(&format!("{path}.item"), &layer.item, ids);
+        }
+    }
+
+    fn item(&mut self, path: &str, item: &Item, ids: &mut HashMap<String, String>) {
+        let path = format!("{path}.data");
+        let path = path.as_str();
+        self.bindings(path, &bound_fields(item));
+        match item {
+            Item::Text(text) => self.text(path, text),
+            Item::Image(image) => {
+                self.size(path, &image.width, &image.height);
+                self.stroke(&format!("{path}.stroke"), image.stroke.as_ref());
+                self.filters(&format!("{path}.filters"), &image.filters);
+                if image.source.contains('{') {
+                    self.template(&format!("{path}.source"), &image.source);
+                }
+            }
+            Item::Rect(rect) => {
+                self.size(path, &rect.width, &rect.height);
+                self.fill(&format!("{path}.color"), &rect.color);
+                self.stroke(&format!("{path}.stroke"), rect.stroke.as_ref());
+            }
+            Item::Slider(slider) => {
+                self.size(path, &slider.width, &slider.height);
+                self.positive(path, "max_value", &slider.max_value);
+                self.color(&format!("{path}.background_color"), &slider.background_color);
+                self.fill(&format!("{path}.fill_color"), &slider.fill_color);
+                self.stroke(&format!("{path}.stroke"), slider.stroke.as_ref());
+            }
+            Item::Ellipse(ellipse) => {
+                self.size(path, &ellipse.width, &ellipse.height);
+                self.fill(&format!("{path}.color"), &ellipse.color);
+                self.stroke(&format!("{path}.stroke"), ellipse.stroke.as_ref());
+            }
+            Item::Line(line) => self.color(&format!("{path}.color"), &line.color),
+            Item::Polygon(polygon) => {
+                if polygon.points.len() < 3 {
+                    self.error(&format!("{path}.points"), format!("A polygon needs at least 3 points, got {}", polygon.points.len()));
+                }
+                self.fill(&format!("{path}.color"), &polygon.color);
+                self.stroke(&format!("{path}.stroke"), polygon.stroke.as_ref());
+            }
+            Item::Star(star) => {
+                self.size(path, &star.width, &star.height);
+                if star.points.binding().is_none() && !(2..=MAX_CORNERS).contains(&*star.points) {
+                    self.error(&format!("{path}.points"), format!("A star needs 2 to {MAX_CORNERS} points, got {}", *star.points));
+                }
+                self.fill(&format!("{path}.color"), &star.color);
+                self.stroke(&format!("{path}.stroke"), star.stroke.as_ref());
+            }
+            Item::RegularPolygon(polygon) => {
+                self.size(path, &polygon.width, &polygon.height);
+                if polygon.sides.binding().is_none() && !(3..=MAX_CORNERS).contains(&*polygon.sides) {
+                    self.error(&format!("{path}.sides"), format!("A polygon needs 3 to {MAX_CORNERS} sides, got {}", *polygon.sides));
+                }
+                self.fill(&format!("{path}.color"), &polygon.color);
+                self.stroke(&format!("{path}.stroke"), polygon.stroke.as_ref());
+            }
+            Item::Path(shape) => {
+                self.size(path, &shape.width, &shape.height);
+                if let Err(err) = shape.segments() {
+                    self.error(&format!("{path}.d"), err.to_string());
+                }
+                if let Some(fill) = &shape.fill {
+                    self.fill(&format!("{path}.fill"), fill);
+                }
+                self.stroke(&format!("{path}.stroke"), shape.stroke.as_ref());
+            }
+            Item::Group(group) => {
+                self.size(path, &group.width, &group.height);
+                self.layers(&format!("{path}.children"), &group.children, ids, None);
+            }
+            Item::Repeat(repeat) => {
+                self.size(path, &repeat.width, &repeat.height);
+                if repeat.source.trim().is_empty() {
+                    self.error(&format!("{path}.source"), "A repeater needs a list variable");
+                }
+                // Template ids get each entry's index appended, so they only need to be unique within the template
+                self.layers(&format!("{path}.template"), &repeat.template, &mut HashMap::new(), None);
+            }
+        }
+    }
+
+    fn text(&mut self, path: &str, text: &TextItem) {
+        self.template(&format!("{path}.text"), &text.text);
+        self.positive(path, "font_size", &text.font_size);
+        self.fill(&format!("{path}.color"), &text.color);
+        if let Some(max_width) = &text.max_width {
+            self.positive(path, "max_width", max_width);
+        }
+        if let Some(line_height) = &text.line_height {
+            self.positive(path, "line_height", line_height);
+        }
+        if let Some(stroke) = &text.stroke {
+            self.color(&format!("{path}.stroke.color"), &stroke.color);
+        }
+        if let Some(shadow) = &text.shadow {
+            self.color(&format!("{path}.shadow.color"), &shadow.color);
+        }
+        for (index, span) in text.spans.iter().enumerate() {
+            let span_path = format!("{path}.spans[{index}]");
+            self.template(&format!("{span_path}.text"), &span.text);
+            let fields = [
+                ("font_size", span.font_size.as_ref().and_then(Bindable::binding)),
+                ("font_weight", span.font_weight.as_ref().and_then(Bindable::binding)),
+            ];
+            self.bindings(&span_path, &fields);
+            if let Some(font_size) = &span.font_size {
+                self.positive(&span_path, "font_size", font_size);
+            }
+            if let Some(color) = &span.color {
+                self.color(&format!("{path}.spans[{index}].color"), color);
+            }
+        }
+    }
+}
+
+/// The item's bindable fields, with their bindings.
+fn bound_fields(item: &Item) -> Vec<(&'static str, Option<&str>)> {
+    match item {
+        Item::Text(text) => {
+            let fit = text.fit.as_ref();
+            let shadow = text.shadow.as_ref();
+            vec![
+                ("font_size", text.font_size.binding()),
+                ("max_width", text.max_width.as_ref().and_then(Bindable::binding)),
+                ("max_height", text.max_height.as_ref().and_then(Bindable::binding)),
+                ("max_lines", text.max_lines.as_ref().and_then(Bindable::binding)),
+                ("line_height", text.line_height.as_ref().and_then(Bindable::binding)),
+                ("fit.min_font_size", fit.and_then(|fit| fit.min_font_size.binding())),
+                ("fit.max_font_size", fit.and_then(|fit| fit.max_font_size.binding())),
+                ("fit.box_width", fit.and_then(|fit| fit.box_width.binding())),
+                ("fit.box_height", fit.and_then(|fit| fit.box_height.binding())),
+                ("font_weight", text.font_weight.as_ref().and_then(Bindable::binding)),
+                ("letter_spacing", text.letter_spacing.binding()),
+                ("underline", text.underline.binding()),
+                ("strikethrough", text.strikethrough.binding()),
+                ("stroke.width", text.stroke.as_ref().and_then(|stroke| stroke.width.binding())),
+                ("shadow.offset_x", shadow.and_then(|shadow| shadow.offset_x.binding())),
+                ("shadow.offset_y", shadow.and_then(|shadow| shadow.offset_y.binding())),
+                ("shadow.blur", shadow.and_then(|shadow| shadow.blur.binding())),
+            ]
+        }
+        Item::Image(image) => {
+            let crop = image.crop.as_ref();
+            vec![
+                ("width", image.width.binding()),
+                ("height", image.height.binding()),
+                ("border_radius", image.border_radius.binding()),
+                ("focal_point[0]", image.focal_point[0].binding()),
+                ("focal_point[1]", image.focal_point[1].binding()),
+                ("crop.x", crop.and_then(|crop| crop.x.binding())),
+                ("crop.y", crop.and_then(|crop| crop.y.binding())),
+                ("crop.width", crop.and_then(|crop| crop.width.binding())),
+                ("crop.height", crop.and_then(|crop| crop.height.binding())),
+            ]
+        }
+        Item::Rect(rect) => {
+            vec![("width", rect.width.binding()), ("height", rect.height.binding()), ("border_radius", rect.border_radius.binding())]
+        }
+        Item::Slider(slider) => vec![
+            ("width", slider.width.binding()),
+            ("height", slider.height.binding()),
+            ("value", slider.value.binding()),
+            ("max_value", slider.max_value.binding()),
+            ("border_radius", slider.border_radius.binding()),
+        ],
+        Item::Ellipse(ellipse) => vec![("width", ellipse.width.binding()), ("height", ellipse.height.binding())],
+        Item::Line(line) => vec![
+            ("x1", line.x1.binding()),
+            ("y1", line.y1.binding()),
+            ("x2", line.x2.binding()),
+            ("y2", line.y2.binding()),
+            ("stroke_width", line.stroke_width.binding()),
+        ],
+        Item::Polygon(_) => Vec::new(),
+        Item::Star(star) => vec![
+            ("width", star.width.binding()),
+            ("height", star.height.binding()),
+            ("points", star.points.binding()),
+            ("inner_ratio", star.inner_ratio.binding()),
+        ],
+        Item::RegularPolygon(polygon) => {
+            vec![("width", polygon.width.binding()), ("height", polygon.height.binding()), ("sides", polygon.sides.binding())]
+        }
+        Item::Path(shape) => vec![("width", shape.width.binding()), ("height", shape.height.binding())],
+        Item::Group(group) => vec![("width", group.width.binding()), ("height", group.height.binding()), ("clip", group.clip.binding())],
+        Item::Repeat(repeat) => vec![("width", repeat.width.binding()), ("height", repeat.height.binding()), ("gap", repeat.gap.binding())],
+    }
+}
+
+/// Whether the layer's box lies entirely outside the canvas, ignoring rotation. Text, whose size is not known
+/// without laying it out, only counts when it starts past the right or bottom edge.
+fn is_off_canvas(layer: &Layer, width: f32, height: f32) -> bool {
+    if layer.x.binding().is_some() || layer.y.binding().is_some() {
+        return false;
+    }
+    let (x, y) = (*layer.x, *layer.y);
+    let (w, h) = layer.item.size().unwrap_or((f32::INFINITY, f32::INFINITY));
+    if w <= 0.0 || h <= 0.0 {
+        // Reported as a size error instead
+        return false;
+    }
+    x >= width || y >= height || x + w <= 0.0 || y + h <= 0.0
+}
+
+fn capitalize(name: &str) -> String {
+    let name = name.replace('_', " ");
+    let mut chars = name.chars();
+    chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+pub(crate) fn validate(sigil: &Sigil, resolved: bool) -> Vec<ValidationIssue> {
+    let mut validator = Validator { issues: Vec::new(), resolved };
+
+    if sigil.width == 0 {
+        validator.error("width", "Canvas width must be positive");
+    }
+    if sigil.height == 0 {
+        validator.error("height", "Canvas height must be positive");
+    }
+    validator.background(&sigil.background);
+
+    for (index, def) in sigil.variables.iter().enumerate() {
+        if sigil.variables[..index].iter().any(|other| other.name == def.name) {
+            validator.error(&format!("variables[{index}].name"), format!("Variable '{}' is declared twice", def.name));
+        }
+        if let Some(default) = &def.default
+            && let Err(err) = def.check(default)
+        {
+            validator.error(&format!("variables[{index}].default"), err.to_string());
+        }
+    }
+
+    let canvas = (sigil.width as f32, sigil.height as f32);
+    validator.layers("layers", &sigil.layers, &mut HashMap::new(), Some(canvas));
+    validator.issues
+}
+
+/// Checks that fonts and image resources are available, for [`Sigil::validate_resources`].
+pub(crate) fn validate_resources(sigil: &Sigil, fonts: &[String], resources: &[String]) -> Vec<ValidationIssue> {
+    let mut validator = Validator { issues: Vec::new(), resolved: false };
+
+    let normalize = |name: &str| name.trim().to_lowercase().replace(' ', "");
+    let fonts: Vec<String> = fonts.iter().map(|font| normalize(font)).collect();
+    let known_font = |family: &str| {
+        family.split(',').any(|name| GENERIC_FONT_FAMILIES.contains(&name.trim().to_lowercase().as_str()) || fonts.contains(&normalize(name)))
+    };
+    // URLs are loaded by the HTML renderers, and placeholders are not known yet
+    let known_resource =
+        |source: &str| source.contains('{') || source.contains("://") || source.starts_with("data:") || resources.iter().any(|name| name == source);
+
+    match &sigil.background {
+        Background::Image(image) if !known_resource(&image.source) => {
+            validator.warning("background.source", format!("Resource '{}' not found", image.source));
+        }
+        Background::Pattern(pattern) if !known_resource(&pattern.source) => {
+            validator.warning("background.source", format!("Resource '{}' not found", pattern.source));
+        }
+        _ => {}
+    }
+
+    let mut check = |path: String, layer: &Layer| match &layer.item {
+        Item::Text(text) => {
+            if !known_font(&text.font_family) {
+                validator.warning(&format!("{path}.item.data.font_family"), format!("Unknown font '{}', drawn with the default font", text.font_family));
+            }
+            for (index, span) in text.spans.iter().enumerate() {
+                if let Some(family) = span.font_family.as_deref().filter(|family| !known_font(family)) {
+                    validator.warning(&format!("{path}.item.data.spans[{index}].font_family"), format!("Unknown font '{family}', drawn with the default font"));
+                }
+            }
+        }
+        Item::Image(image) if !known_resource(&image.source) => {
+            validator.warning(&format!("{path}.item.data.source"), format!("Resource '{}' not found", image.source));
+        }
+        _ => {}
+    };
+    for_each_layer_path("layers", &sigil.layers, &mut check);
+
+    validator.issues
+}
+
+/// Calls `f` with every layer and its path, including the layers nested in groups and repeaters.
+fn for_each_layer_path(path: &str, layers: &[Layer], f: &mut impl FnMut(String, &Layer)) {
+    for (index, layer) in layers.iter().enumerate() {
+        let path = format!("{path}[{index}]");
+        f(path.clone(), layer);
+        match &layer.item {
+            Item::Group(group) => for_each_layer_path(&format!("{path}.item.data.children"), &group.children, f),
+            Item::Repeat(repeat) => for_each_layer_path(&format!("{path}.item.data.template"), &repeat.template, f),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,260 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! Declared template variables, so callers can tell which values a template needs and check them before rendering.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::Rgba;
+
+/// A variable a template uses as `{name}`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct VariableDef {
+    pub name: String,
+    #[serde(rename = "type", default)]
+    pub kind: VariableType,
+    /// Used when the caller gives no value. Variables without a default are required.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// Smallest allowed number.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f32>,
+    /// Largest allowed number.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f32>,
+    /// Longest allowed string, in characters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    /// The only allowed values, if not empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<String>,
+}
+
+/// What a variable's value must look like.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum VariableType {
+    /// Any text.
+    #[default]
+    String,
+    /// A decimal number.
+    Number,
+    /// Any CSS color supported by [`Rgba::parse`].
+    Color,
+    /// The name of an image resource, or a URL for the HTML renderers.
+    Image,
+    /// `true` or `false`.
+    Boolean,
+    /// A JSON array, such as the entries of a [`crate::RepeatItem`].
+    List,
+}
+
+/// Why a variable's value was rejected.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum VariableError {
+    #[error("Missing required variable '{name}'")]
+    Missing { name: String },
+
+    #[error("Unknown variable '{name}'")]
+    Unknown { name: String },
+
+    #[error("Variable '{name}' must be a {expected}, got '{value}'")]
+    InvalidType { name: String, expected: VariableType, value: String },
+
+    #[error("Variable '{name}' must be at least {min}, got {value}")]
+    TooSmall { name: String, min: f32, value: f32 },
+
+    #[error("Variable '{name}' must be at most {max}, got {value}")]
+    TooLarge { name: String, max: f32, value: f32 },
+
+    #[error("Variable '{name}' must be at most {max_length} characters long")]
+    TooLong { name: String, max_length: usize },
+
+    #[error("Variable '{name}' must be one of {options:?}, got '{value}'")]
+    NotAnOption { name: String, value: String, options: Vec<String> },
+}
+
+impl VariableType {
+    pub const ALL: [VariableType; 6] = [
+        VariableType::String,
+        VariableType::Number,
+        VariableType::Color,
+        VariableType::Image,
+        VariableType::Boolean,
+        VariableType::List,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            VariableType::String => "string",
+            VariableType::Number => "number",
+            VariableType::Color => "color",
+            VariableType::Image => "image",
+            VariableType::Boolean => "boolean",
+            VariableType::List => "list",
+        }
+    }
+}
+
+impl fmt::Display for VariableType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl VariableDef {
+    /// A required string variable without constraints.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            kind: VariableType::String,
+            default: None,
+            description: String::new(),
+            min: None,
+            max: None,
+            max_length: None,
+            options: Vec::new(),
+        }
+    }
+
+    pub fn is_required(&self) -> bool {
+        self.default.is_none()
+    }
+
+    /// Checks a value against the type and constraints.
+    pub fn check(&self, value: &str) -> Result<(), VariableError> {
+        let invalid_type = || VariableError::InvalidType {
+            name: self.name.clone(),
+            expected: self.kind,
+            value: value.to_string(),
+        };
+
+        match self.kind {
+            VariableType::String => {}
+            VariableType::Number => {
+                let number = value.trim().parse::<f32>().ok().filter(|n| n.is_finite()).ok_or_else(invalid_type)?;
+                if let Some(min) = self.min.filter(|min| number < *min) {
+                    return Err(VariableError::TooSmall { name: self.name.clone(), min, value: number });
+                }
+                if let Some(max) = self.max.filter(|max| number > *max) {
+                    return Err(VariableError::TooLarge { name: self.name.clone(), max, value: number });
+                }
+            }
+            VariableType::Color => {
+                Rgba::parse(value).map_err(|_| invalid_type())?;
+            }
+            VariableType::Image => {
+                if value.trim().is_empty() {
+                    return Err(invalid_type());
+                }
+            }
+            VariableType::Boolean => {
+                if value != "true" && value != "false" {
+                    return Err(invalid_type());
+                }
+            }
+            VariableType::List => {
+                serde_json::from_str::<Vec<Value>>(value).map_err(|_| invalid_type())?;
+            }
+        }
+
+        if let Some(max_length) = self.max_length
+            && value.chars().count() > max_length
+        {
+            return Err(VariableError::TooLong { name: self.name.clone(), max_length });
+        }
+        if !self.options.is_empty() && !self.options.iter().any(|option| option == value) {
+            return Err(VariableError::NotAnOption {
+                name: self.name.clone(),
+                value: value.to_string(),
+                options: self.options.clone(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks `values` against `defs`: every required variable must be given, every given one declared and valid.
+pub(crate) fn validate_variables(defs: &[VariableDef], values: &HashMap<String, String>) -> Result<(), Vec<VariableError>> {
+    let mut errors = Vec::new();
+
+    for def in defs {
+        match values.get(&def.name) {
+            Some(value) => errors.extend(def.check(value).err()),
+            None if def.is_required() => errors.push(VariableError::Missing { name: def.name.clone() }),
+            None => {}
+        }
+    }
+
+    // The fields of list entries, as given by `variables_from_json`, belong to the list
+    let is_declared = |name: &str| {
+        defs.iter().any(|def| {
+            def.name == name
+                || (def.kind == VariableType::List && name.strip_prefix(def.name.as_str()).is_some_and(|field| field.starts_with('.')))
+        })
+    };
+    let mut unknown: Vec<&String> = values.keys().filter(|name| !is_declared(name)).collect();
+    unknown.sort();
+    errors.extend(unknown.into_iter().map(|name| VariableError::Unknown { name: name.clone() }));
+
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+/// Reads the fields of a JSON object as variables. Nested objects give one variable per field, so that
+/// `{"user": {"name": "meetzli"}}` gives `user.name`. Arrays give a variable holding their JSON, as list variables
+/// and [`crate::RepeatItem`] expect, plus one per entry: `{"tags": ["new"]}` gives `tags` and `tags.0`.
+/// Nulls are left out, like missing values.
+pub fn variables_from_json(data: &Value) -> HashMap<String, String> {
+    let mut variables = HashMap::new();
+    if let Value::Object(fields) = data {
+        for (name, value) in fields {
+            flatten_json(name.clone(), value, &mut variables);
+        }
+    }
+    variables
+}
+
+/// Adds `value` as the variable `name`, or its fields as `name.field`. Arrays are added both ways, as `name` and
+/// `name.0` and so on.
+pub(crate) fn flatten_json(name: String, value: &Value, variables: &mut HashMap<String, String>) {
+    match value {
+        Value::Null => {}
+        Value::Bool(value) => {
+            variables.insert(name, value.to_string());
+        }
+        Value::Number(number) => {
+            variables.insert(name, number.to_string());
+        }
+        Value::String(text) => {
+            variables.insert(name, text.clone());
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                flatten_json(format!("{name}.{index}"), item, variables);
+            }
+            variables.insert(name, value.to_string());
+        }
+        Value::Object(fields) => {
+            for (field, item) in fields {
+                flatten_json(format!("{name}.{field}"), item, variables);
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! Image fitting, cropping and filters, shared by `render_to_rsx` and the editor.
+//!
+//! Uncropped images use CSS `object-fit` and `object-position`, which `ImageItem::fit_rect` follows. Cropped
+//! images are laid out from `fit_rect` directly, since CSS cannot crop an `<img>` to a source rectangle.
+//! Filters go on an element covering the item's box, as `sigil-render` filters the image laid out in its box.
+
+use dioxus::prelude::*;
+use sigil_core::{Background, ImageFilter, ImageItem};
+
+use crate::style::css_fill;
+
+/// The image laid out and filtered in its box, for an element covering the item's box with `overflow: hidden`.
+/// `id` must be unique in the document; it names the SVG filters of tints.
+pub fn image_content(id: &str, img: &ImageItem) -> Element {
+    let filter = filter_css(id, &img.filters);
+    rsx! {
+        div {
+            style: "position: absolute; left: 0; top: 0; width: 100%; height: 100%; {filter}",
+            {filter_defs(id, &img.filters)}
+            match cropped_image(img) {
+                Some(content) => content,
+                None => rsx! {
+                    img {
+                        src: "{img.source}",
+                        style: "position: absolute; left: 0; top: 0; width: 100%; height: 100%; user-select: none; {image_fit_css(img)}",
+                        draggable: "false",
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// An image background with its filters and overlay, for the start of a `width` x `height` container; nothing
+/// for other backgrounds, which `style::background_css` puts on the container itself.
+///
+/// The image is an `<img>` laid out like an image item, rather than a CSS background, as `background-size`
+/// has no `scale-down` and the filters would otherwise apply to the layers too.
+pub fn background_image(id: &str, background: &Background, width: f32, height: f32) -> Element {
+    let Background::Image(image) = background else {
+        return VNode::empty();
+    };
+    let item = ImageItem {
+        source: image.source.clone(),
+        width: width.into(),
+        height: height.into(),
+        border_radius: 0.0.into(),
+        stroke: None,
+        fit: image.fit,
+        focal_point: image.focal_point.clone(),
+        crop: None,
+        filters: image.filters.clone(),
+    };
+    let overlay = image.overlay.as_ref().map(css_fill);
+
+    rsx! {
+        {image_content(id, &item)}
+        if let Some(overlay) = overlay {
+            div { style: "position: absolute; left: 0; top: 0; width: 100%; height: 100%; background: {overlay};" }
+        }
+    }
+}
+
+/// `object-fit` and `object-position` for an uncropped `<img>` filling the item's box.
+pub fn image_fit_css(img: &ImageItem) -> String {
+    format!(
+        "object-fit: {}; object-position: {}% {}%;",
+        img.fit.css_name(),
+        *img.focal_point[0] * 100.0,
+        *img.focal_point[1] * 100.0
+    )
+}
+
+/// The crop of the image at its fitted place, or `None` when the image is not cropped.
+pub fn cropped_image(img: &ImageItem) -> Option<Element> {
+    let (viewport_style, image_style) = cropped_image_css(img)?;
+
+    Some(rsx! {
+        div {
+            style: "{viewport_style}",
+            img { src: "{img.source}", style: "{image_style}", draggable: "false" }
+        }
+    })
+}
+
+/// The styles of a cropped image: a viewport at the fitted rectangle, and the `<img>` inside it. The `<img>`
+/// keeps its natural size and is scaled from its corner, so that the crop fills the fitted rectangle.
+pub fn cropped_image_css(img: &ImageItem) -> Option<(String, String)> {
+    let crop = img.crop.as_ref().filter(|crop| *crop.width > 0.0 && *crop.height > 0.0)?;
+    let [x, y, width, height] = img.fit_rect(*crop.width, *crop.height);
+    let (scale_x, scale_y) = (width / *crop.width, height / *crop.height);
+
+    let viewport_style = format!(
+        "position: absolute; left: {x}px; top: {y}px; width: {width}px; height: {height}px; overflow: hidden;"
+    );
+    let image_style = format!(
+        "position: absolute; left: {}px; top: {}px; width: auto; height: auto; max-width: none; max-height: none; \
+         transform: scale({scale_x}, {scale_y}); transform-origin: 0 0; user-select: none;",
+        -*crop.x * scale_x,
+        -*crop.y * scale_y
+    );
+    Some((viewport_style, image_style))
+}
+
+/// A CSS `filter` declaration for `filters`, or nothing when there are none. Tints refer to the SVG
+/// filters from [`filter_defs`] with the same `id`.
+pub fn filter_css(id: &str, filters: &[ImageFilter]) -> String {
+    let functions: Vec<String> = filters
+        .iter()
+        .enumerate()
+        .filter_map(|(i, filter)| match filter {
+            ImageFilter::Tint { .. } => filter.color_matrix().map(|_| format!("url(#{id}-filter-{i})")),
+            filter => filter.css_function(),
+        })
+        .collect();
+
+    if functions.is_empty() {
+        String::new()
+    } else {
+        format!("filter: {};", functions.join(" "))
+    }
+}
+
+/// A hidden SVG defining a `<feColorMatrix>` filter for every tint, as CSS has no tint function.
+pub fn filter_defs(id: &str, filters: &[ImageFilter]) -> Element {
+    let tints: Vec<(String, String)> = filters
+        .iter()
+        .enumerate()
+        .filter(|(_, filter)| matches!(filter, ImageFilter::Tint { .. }))
+        .filter_map(|(i, filter)| {
+            let matrix = filter.color_matrix()?;
+            let values = matrix.map(|value| value.to_string()).join(" ");
+            Some((format!("{id}-filter-{i}"), values))
+        })
+        .collect();
+    if tints.is_empty() {
+        return VNode::empty();
+    }
+
+    rsx! {
+        svg {
+            width: "0",
+            height: "0",
+            style: "position: absolute;",
+            for (filter_id, values) in tints {
+                filter {
+                    id: "{filter_id}",
+                    color_interpolation_filters: "sRGB",
+                    feColorMatrix { "type": "matrix", "values": "{values}" }
+                }
+            }
+        }
+    }
+}
@@ -8,103 +8,319 @@
     (at your option) any later version.
 */
 
-use sigil_core::{Sigil, Item};
+pub mod image;
+pub mod mask;
+pub mod shape;
+pub mod style;
+
+use sigil_core::{Sigil, Item, Layer};
 use std::collections::HashMap;
 use dioxus::prelude::*;
+use image::{background_image, image_content};
+use mask::mask_css;
+use shape::shape_svg;
+use style::{background_css, css_fill, layer_css, stroke_css, text_box_css, text_content_css, text_span_css};
 
+/// Renders a Sigil as absolutely positioned HTML elements. Lists for repeaters and other structured data can be
+/// given with `sigil_core::variables_from_json`.
+///
+/// Auto-fit text is approximated by the browser; resolve it first with
+/// `Sigil::with_fitted_text` and the sizes reported by `sigil-render` for an exact match.
 pub fn render_to_rsx(sigil: &Sigil, variables: &HashMap<String, String>) -> Element {
     let resolved = sigil.resolve(variables);
     
-    let background_style = if resolved.background.starts_with('#') {
-        format!("background-color: {}", resolved.background)
-    } else if resolved.background.starts_with("http") || resolved.background.starts_with('/') {
-        format!("background-image: url('{}'); background-size: cover; background-position: center", resolved.background)
-    } else {
-        format!("background: {}", resolved.background)
-    };
-    
     let container_style = format!(
-        "position: relative; width: {}px; height: {}px; {}; overflow: hidden;",
-        resolved.width, resolved.height, background_style
+        "position: relative; width: {}px; height: {}px; {} overflow: hidden;",
+        resolved.width, resolved.height, background_css(&resolved.background)
     );
     
     rsx! {
         div {
             class: "sigil-container",
             style: "{container_style}",
-            for layer in resolved.layers.iter() {
-                if layer.visible {
-                    {
-                        let transform = if layer.rotation != 0.0 {
-                            format!("rotate({}deg)", layer.rotation)
+            {background_image("sigil-background", &resolved.background, resolved.width as f32, resolved.height as f32)}
+            {render_layers(&resolved.layers)}
+        }
+    }
+}
+
+/// Renders sibling layers in order. Masked layers are wrapped in an element carrying their mask, and layers used
+/// as another layer's mask are left out, as in `sigil-render`.
+pub fn render_layers(layers: &[Layer]) -> Element {
+    let mask_layers: Vec<&str> = layers.iter().filter_map(|layer| layer.mask.as_ref()?.layer_id()).collect();
+
+    rsx! {
+        for layer in layers.iter().filter(|layer| !mask_layers.contains(&layer.id.as_str())) {
+            if let Some(mask) = layer.mask.as_ref().and_then(|mask| mask_css(mask, layers)) {
+                div {
+                    style: "position: absolute; left: 0; top: 0; width: 100%; height: 100%; {mask}",
+                    {render_layer(layer)}
+                }
+            } else {
+                {render_layer(layer)}
+            }
+        }
+    }
+}
+
+/// Renders one layer as an absolutely positioned element, and the children of groups inside it.
+/// Hidden layers render nothing.
+fn render_layer(layer: &Layer) -> Element {
+    if !*layer.visible {
+        return VNode::empty();
+    }
+
+    let transform = if *layer.rotation != 0.0 {
+        format!("rotate({}deg)", *layer.rotation)
+    } else {
+        String::new()
+    };
+    let compositing = layer_css(layer);
+    
+    match &layer.item {
+        Item::Text(text) => {
+            let style = format!(
+                "position: absolute; left: {}px; top: {}px; transform: {}; {}{}",
+                *layer.x, *layer.y, transform, text_box_css(text), compositing
+            );
+            let content_style = text_content_css(text);
+            rsx! {
+                div { style: "{style}",
+                    div { style: "{content_style}",
+                        if text.spans.is_empty() {
+                            "{text.text}"
                         } else {
-                            String::new()
-                        };
-                        
-                        rsx! {
-                            {match &layer.item {
-                                Item::Text(text) => {
-                                    let style = format!(
-                                        "position: absolute; left: {}px; top: {}px; font-size: {}px; color: {}; font-family: {}; transform: {}; white-space: nowrap;",
-                                        layer.x, layer.y, text.font_size, text.color, text.font_family, transform
-                                    );
-                                    rsx! {
-                                        div { style: "{style}", "{text.text}" }
-                                    }
-                                }
-                                Item::Image(img) => {
-                                    let border_radius = if img.border_radius > 0.0 {
-                                        format!("border-radius: {}px;", img.border_radius)
-                                    } else {
-                                        String::new()
-                                    };
-                                    let style = format!(
-                                        "position: absolute; left: {}px; top: {}px; width: {}px; height: {}px; {} transform: {}; object-fit: cover;",
-                                        layer.x, layer.y, img.width, img.height, border_radius, transform
-                                    );
-                                    rsx! {
-                                        img { src: "{img.source}", style: "{style}" }
-                                    }
-                                }
-                                Item::Rect(rect) => {
-                                    let border_radius = if rect.border_radius > 0.0 {
-                                        format!("border-radius: {}px;", rect.border_radius)
-                                    } else {
-                                        String::new()
-                                    };
-                                    let style = format!(
-                                        "position: absolute; left: {}px; top: {}px; width: {}px; height: {}px; background-color: {}; {} transform: {};",
-                                        layer.x, layer.y, rect.width, rect.height, rect.color, border_radius, transform
-                                    );
-                                    rsx! {
-                                        div { style: "{style}" }
-                                    }
-                                }
-                                Item::Slider(slider) => {
-                                    let border_radius = if slider.border_radius > 0.0 {
-                                        format!("border-radius: {}px;", slider.border_radius)
-                                    } else {
-                                        String::new()
-                                    };
-                                    let bg_style = format!(
-                                        "position: absolute; left: {}px; top: {}px; width: {}px; height: {}px; background-color: {}; {} transform: {};",
-                                        layer.x, layer.y, slider.width, slider.height, slider.background_color, border_radius, transform
-                                    );
-                                    let fill_width = (slider.value / slider.max_value.max(1.0)) * slider.width;
-                                    let fill_style = format!(
-                                        "position: absolute; left: {}px; top: {}px; width: {}px; height: {}px; background-color: {}; {} transform: {};",
-                                        layer.x, layer.y, fill_width, slider.height, slider.fill_color, border_radius, transform
-                                    );
-                                    rsx! {
-                                        div { style: "{bg_style}" }
-                                        div { style: "{fill_style}" }
-                                    }
-                                }
-                            }}
+                            for span in text.spans.iter() {
+                                span { style: "{text_span_css(span)}", "{span.text}" }
+                            }
                         }
                     }
                 }
             }
         }
+        Item::Image(img) => {
+            let border_radius = if *img.border_radius > 0.0 {
+                format!("border-radius: {}px;", *img.border_radius)
+            } else {
+                String::new()
+            };
+            let style = format!(
+                "position: absolute; left: {}px; top: {}px; width: {}px; height: {}px; {} transform: {}; {}{}",
+                *layer.x, *layer.y, *img.width, *img.height, border_radius, transform, compositing,
+                img.stroke.as_ref().map(stroke_css).unwrap_or_default()
+            );
+            rsx! {
+                div { style: "{style} overflow: hidden;", {image_content(&format!("sigil-{}", layer.id), img)} }
+            }
+        }
+        Item::Rect(rect) => {
+            let border_radius = if *rect.border_radius > 0.0 {
+                format!("border-radius: {}px;", *rect.border_radius)
+            } else {
+                String::new()
+            };
+            let style = format!(
+                "position: absolute; left: {}px; top: {}px; width: {}px; height: {}px; background: {}; {} transform: {}; {}{}",
+                *layer.x, *layer.y, *rect.width, *rect.height, css_fill(&rect.color), border_radius, transform, compositing,
+                rect.stroke.as_ref().map(stroke_css).unwrap_or_default()
+            );
+            rsx! {
+                div { style: "{style}" }
+            }
+        }
+        Item::Slider(slider) => {
+            let border_radius = if *slider.border_radius > 0.0 {
+                format!("border-radius: {}px;", *slider.border_radius)
+            } else {
+                String::new()
+            };
+            // The wrapper is the track, so shadows and blurs take its shape; the bars fill it
+            let track_style = format!(
+                "position: absolute; left: {}px; top: {}px; width: {}px; height: {}px; {} transform: {}; {}",
+                *layer.x, *layer.y, *slider.width, *slider.height, border_radius, transform, compositing
+            );
+            let bg_style = format!(
+                "position: absolute; left: 0; top: 0; width: 100%; height: 100%; background-color: {}; {}",
+                slider.background_color, border_radius
+            );
+            let fill_width = (*slider.value / slider.max_value.max(1.0)) * *slider.width;
+            let fill_style = format!(
+                "position: absolute; left: 0; top: 0; width: {}px; height: 100%; background: {}; {}",
+                fill_width, css_fill(&slider.fill_color), border_radius
+            );
+            // The stroke goes around the whole track, above the fill
+            let stroke_style = slider.stroke.as_ref().map(|stroke| format!(
+                "position: absolute; left: 0; top: 0; width: 100%; height: 100%; {} {}",
+                border_radius, stroke_css(stroke)
+            ));
+            // Both bars are composited as one group, like the single layer sigil-render draws
+            rsx! {
+                div { style: "{track_style}",
+                    div { style: "{bg_style}" }
+                    div { style: "{fill_style}" }
+                    if let Some(stroke_style) = stroke_style {
+                        div { style: "{stroke_style}" }
+                    }
+                }
+            }
+        }
+        Item::Ellipse(_) | Item::Line(_) | Item::Polygon(_) | Item::Star(_) | Item::RegularPolygon(_) | Item::Path(_) => {
+            let (width, height) = layer.item.size().unwrap_or_default();
+            let style = format!(
+                "position: absolute; left: {}px; top: {}px; width: {}px; height: {}px; transform: {}; {}",
+                *layer.x, *layer.y, width, height, transform, compositing
+            );
+            rsx! {
+                div { style: "{style}", {shape_svg(&format!("sigil-{}", layer.id), &layer.item)} }
+            }
+        }
+        Item::Group(group) => {
+            let clip = if *group.clip { "overflow: hidden;" } else { "" };
+            let style = format!(
+                "position: absolute; left: {}px; top: {}px; width: {}px; height: {}px; transform: {}; {} {}",
+                *layer.x, *layer.y, *group.width, *group.height, transform, clip, compositing
+            );
+            // Children are positioned inside the group's box, so they move and rotate with it
+            rsx! {
+                div { style: "{style}", {render_layers(&group.children)} }
+            }
+        }
+        // Resolving turns repeaters into groups; without entries there is nothing to draw
+        Item::Repeat(_) => VNode::empty(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::image::{cropped_image_css, image_fit_css};
+    use sigil_core::{BlendMode, FORMAT_VERSION, ImageCrop, ImageFit, ImageItem, Item, Layer, Sigil};
+    use sigil_render::Renderer;
+    use std::collections::HashMap;
+
+    /// The value of `property` in an inline style.
+    fn css_value<'a>(style: &'a str, property: &str) -> &'a str {
+        style
+            .split(';')
+            .filter_map(|declaration| declaration.trim().split_once(": "))
+            .find(|(name, _)| *name == property)
+            .map(|(_, value)| value)
+            .unwrap_or_else(|| panic!("no {property} in {style:?}"))
+    }
+
+    fn px(value: &str) -> f32 {
+        value.trim_end_matches("px").parse().unwrap()
+    }
+
+    /// Where a browser puts an `<img>` of `natural` size filling a `size` box, following the CSS `object-fit`
+    /// and `object-position` in `style`, as `[x, y, width, height]`.
+    fn object_fit_rect(style: &str, size: [f32; 2], natural: [f32; 2]) -> [f32; 4] {
+        let contain = f32::min(size[0] / natural[0], size[1] / natural[1]);
+        let cover = f32::max(size[0] / natural[0], size[1] / natural[1]);
+        let [width, height] = match css_value(style, "object-fit") {
+            "fill" => size,
+            "contain" => natural.map(|side| side * contain),
+            "cover" => natural.map(|side| side * cover),
+            "none" => natural,
+            "scale-down" => natural.map(|side| side * contain.min(1.0)),
+            fit => panic!("unknown object-fit {fit}"),
+        };
+        let position: Vec<f32> = css_value(style, "object-position")
+            .split(' ')
+            .map(|percent| percent.trim_end_matches('%').parse::<f32>().unwrap() / 100.0)
+            .collect();
+        [(size[0] - width) * position[0], (size[1] - height) * position[1], width, height]
+    }
+
+    #[test]
+    fn test_image_css_matches_rendered_placement() {
+        // A 20x10 PNG, red on the left half and blue on the right
+        let mut png = Vec::new();
+        ::image::RgbaImage::from_fn(20, 10, |x, _| if x < 10 { ::image::Rgba([255, 0, 0, 255]) } else { ::image::Rgba([0, 0, 255, 255]) })
+            .write_to(&mut std::io::Cursor::new(&mut png), ::image::ImageFormat::Png)
+            .unwrap();
+        let resources = HashMap::from([("photo".to_string(), png)]);
+        let mut renderer = Renderer::new();
+        let (box_width, box_height) = (40usize, 30usize);
+        let crops = [None, Some(ImageCrop { x: 5.0.into(), y: 2.0.into(), width: 10.0.into(), height: 6.0.into() })];
+
+        for fit in ImageFit::ALL {
+            for crop in &crops {
+                let image = ImageItem {
+                    source: "photo".to_string(),
+                    width: (box_width as f32).into(),
+                    height: (box_height as f32).into(),
+                    border_radius: 0.0.into(),
+                    stroke: None,
+                    fit,
+                    focal_point: [0.2.into(), 0.8.into()],
+                    crop: crop.clone(),
+                    filters: Vec::new(),
+                };
+
+                // The shown rectangle in the item's box, and where the red half of the image meets the blue
+                let (shown, boundary) = match cropped_image_css(&image) {
+                    Some((viewport, img)) => {
+                        let shown = ["left", "top", "width", "height"].map(|property| px(css_value(&viewport, property)));
+                        let scale: f32 = css_value(&img, "transform")
+                            .trim_start_matches("scale(")
+                            .split(',')
+                            .next()
+                            .unwrap()
+                            .parse()
+                            .unwrap();
+                        (shown, shown[0] + px(css_value(&img, "left")) + 10.0 * scale)
+                    }
+                    None => {
+                        let shown = object_fit_rect(&image_fit_css(&image), [box_width as f32, box_height as f32], [20.0, 10.0]);
+                        (shown, shown[0] + shown[2] / 2.0)
+                    }
+                };
+                let left = shown[0].max(0.0);
+                let top = shown[1].max(0.0);
+                let right = (shown[0] + shown[2]).min(box_width as f32);
+                let bottom = (shown[1] + shown[3]).min(box_height as f32);
+
+                let layer = Layer {
+                    id: "photo".to_string(),
+                    x: 0.0.into(),
+                    y: 0.0.into(),
+                    rotation: 0.0.into(),
+                    visible: true.into(),
+                    opacity: 1.0.into(),
+                    blend_mode: BlendMode::Normal,
+                    effects: Vec::new(),
+                    mask: None,
+                    condition: None,
+                    item: Item::Image(image),
+                };
+                let sigil = Sigil {
+                    version: FORMAT_VERSION,
+                    width: box_width as u32,
+                    height: box_height as u32,
+                    background: "#000000".into(),
+                    variables: Vec::new(),
+                    layers: vec![layer],
+                };
+                let data = renderer.render_raw(&sigil, &resources).unwrap();
+                let pixel = |x: usize, y: usize| {
+                    let i = (y * box_width + x) * 4;
+                    (data[i], data[i + 2])
+                };
+                let shows_image = |(red, blue): (u8, u8)| red > 127 || blue > 127;
+
+                // Scan across the middle of the shown rectangle, and down through it
+                let row = ((top + bottom) / 2.0) as usize;
+                let column = ((left + right) / 2.0) as usize;
+                let columns: Vec<usize> = (0..box_width).filter(|&x| shows_image(pixel(x, row))).collect();
+                let rows: Vec<usize> = (0..box_height).filter(|&y| shows_image(pixel(column, y))).collect();
+                let blue = (0..box_width).find(|&x| shows_image(pixel(x, row)) && pixel(x, row).1 > pixel(x, row).0);
+
+                let case = format!("{} {crop:?}", fit.css_name());
+                let near = |rendered: usize, css: f32| (rendered as f32 - css).abs() <= 1.0;
+                assert!(near(columns[0], left) && near(columns[columns.len() - 1] + 1, right), "{case}: columns {columns:?}, CSS {shown:?}");
+                assert!(near(rows[0], top) && near(rows[rows.len() - 1] + 1, bottom), "{case}: rows {rows:?}, CSS {shown:?}");
+                assert!(near(blue.unwrap(), boundary), "{case}: blue from {blue:?}, CSS {boundary}");
+            }
+        }
     }
 }
@@ -0,0 +1,159 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! Layer masks as CSS `mask-image`, shared by `render_to_rsx` and the editor.
+//!
+//! The mask goes on a wrapper covering the masked layer's parent, so that, as in `sigil-render`, it is placed in
+//! the parent's coordinates and does not turn with the layer. Shapes are drawn into an SVG data URL; images are
+//! used as they are.
+
+use sigil_core::{Fill, Gradient, Item, Layer, LayerMask, LineCap, MaskMode, MaskSource};
+
+use crate::shape::shape_path;
+
+/// CSS for the wrapper of a masked layer, or `None` if the mask source cannot be shown as a CSS mask
+/// (text, sliders and groups) or does not exist, in which case the layer is shown unmasked.
+///
+/// Only fills are drawn into the mask, and lines; shape strokes are left out.
+pub fn mask_css(mask: &LayerMask, siblings: &[Layer]) -> Option<String> {
+    let (x, y, rotation, item) = match &mask.source {
+        MaskSource::Layer { id } => {
+            let layer = siblings.iter().find(|layer| &layer.id == id)?;
+            (*layer.x, *layer.y, *layer.rotation, &layer.item)
+        }
+        MaskSource::Shape { x, y, rotation, item } => (*x, *y, *rotation, item.as_ref()),
+    };
+    let mode = match mask.mode {
+        MaskMode::Luminance => "luminance",
+        MaskMode::Alpha | MaskMode::Clip => "alpha",
+    };
+
+    let (image, left, top, width, height) = match item {
+        Item::Image(img) => (format!("url('{}')", img.source), x, y, *img.width, *img.height),
+        item => {
+            let (w, h) = item.size()?;
+            let shape = mask_shape(item, mask.mode == MaskMode::Clip)?;
+
+            // The SVG covers the shape's box turned by the rotation, plus room for a line's stroke
+            let pad = match item {
+                Item::Line(line) => *line.stroke_width,
+                _ => 0.0,
+            };
+            let (sin, cos) = rotation.to_radians().sin_cos();
+            let half_w = (w / 2.0 * cos).abs() + (h / 2.0 * sin).abs() + pad;
+            let half_h = (w / 2.0 * sin).abs() + (h / 2.0 * cos).abs() + pad;
+            let (min_x, min_y) = (w / 2.0 - half_w, h / 2.0 - half_h);
+
+            let svg = format!(
+                "<svg xmlns='http://www.w3.org/2000/svg' width='{bw}' height='{bh}' viewBox='{min_x} {min_y} {bw} {bh}'><g transform='rotate({rotation} {cx} {cy})'>{shape}</g></svg>",
+                bw = half_w * 2.0,
+                bh = half_h * 2.0,
+                cx = w / 2.0,
+                cy = h / 2.0,
+            );
+            (format!("url(\"{}\")", svg_data_url(&svg)), x + min_x, y + min_y, half_w * 2.0, half_h * 2.0)
+        }
+    };
+
+    Some(format!(
+        "mask-image: {image}; -webkit-mask-image: {image}; mask-mode: {mode}; \
+         mask-position: {left}px {top}px; -webkit-mask-position: {left}px {top}px; \
+         mask-size: {width}px {height}px; -webkit-mask-size: {width}px {height}px; \
+         mask-repeat: no-repeat; -webkit-mask-repeat: no-repeat;"
+    ))
+}
+
+/// SVG markup for the item in its own box; opaque white when `solid`.
+fn mask_shape(item: &Item, solid: bool) -> Option<String> {
+    let fill = match item {
+        Item::Rect(rect) => Some(&rect.color),
+        Item::Ellipse(ellipse) => Some(&ellipse.color),
+        Item::Polygon(polygon) => Some(&polygon.color),
+        Item::Star(star) => Some(&star.color),
+        Item::RegularPolygon(polygon) => Some(&polygon.color),
+        Item::Path(path) => path.fill.as_ref(),
+        _ => None,
+    };
+    let (w, h) = item.size()?;
+    let (defs, paint) = match fill {
+        _ if solid => (String::new(), "white".to_string()),
+        Some(Fill::Gradient(gradient)) => (svg_gradient(gradient, w, h), "url(#fill)".to_string()),
+        Some(Fill::Solid(color)) => (String::new(), color.clone()),
+        None => (String::new(), "none".to_string()),
+    };
+
+    let shape = match item {
+        Item::Rect(rect) => format!("<rect width='{w}' height='{h}' rx='{}' fill='{paint}'/>", *rect.border_radius),
+        Item::Line(line) => {
+            let cap = match line.cap {
+                LineCap::Butt => "butt",
+                LineCap::Round => "round",
+                LineCap::Square => "square",
+            };
+            let stroke = if solid { "white" } else { &line.color };
+            format!(
+                "<path d='{}' fill='none' stroke='{stroke}' stroke-width='{}' stroke-linecap='{cap}'/>",
+                shape_path(item)?,
+                *line.stroke_width
+            )
+        }
+        Item::Path(path) => {
+            let rule = match path.fill_rule {
+                sigil_core::FillRule::Nonzero => "nonzero",
+                sigil_core::FillRule::EvenOdd => "evenodd",
+            };
+            format!("<path d='{}' fill='{paint}' fill-rule='{rule}'/>", shape_path(item)?)
+        }
+        _ => format!("<path d='{}' fill='{paint}'/>", shape_path(item)?),
+    };
+
+    Some(format!("{defs}{shape}"))
+}
+
+/// A gradient with the id `fill`, laid out over the box like `shape::shape_svg` does.
+fn svg_gradient(gradient: &Gradient, width: f32, height: f32) -> String {
+    let stops: String = gradient
+        .stops()
+        .iter()
+        .map(|stop| format!("<stop offset='{}' stop-color='{}'/>", stop.offset, stop.color))
+        .collect();
+
+    match gradient {
+        Gradient::Linear(linear) => {
+            let ([x1, y1], [x2, y2]) = linear.endpoints(width, height);
+            format!(
+                "<defs><linearGradient id='fill' gradientUnits='userSpaceOnUse' x1='{x1}' y1='{y1}' x2='{x2}' y2='{y2}'>{stops}</linearGradient></defs>"
+            )
+        }
+        Gradient::Radial(radial) => {
+            let ([cx, cy], r) = radial.geometry(width, height);
+            format!(
+                "<defs><radialGradient id='fill' gradientUnits='userSpaceOnUse' cx='{cx}' cy='{cy}' r='{r}'>{stops}</radialGradient></defs>"
+            )
+        }
+    }
+}
+
+/// Percent-encodes the characters that would end or corrupt a `data:image/svg+xml` URL inside CSS.
+fn svg_data_url(svg: &str) -> String {
+    let mut url = String::from("data:image/svg+xml,");
+    for c in svg.chars() {
+        match c {
+            '%' => url.push_str("%25"),
+            '#' => url.push_str("%23"),
+            '<' => url.push_str("%3C"),
+            '>' => url.push_str("%3E"),
+            '"' => url.push_str("%22"),
+            '\n' | '\r' => url.push(' '),
+            c => url.push(c),
+        }
+    }
+    url
+}
@@ -0,0 +1,225 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! Inline SVG for vector shape items, shared by `render_to_rsx` and the editor.
+
+use dioxus::prelude::*;
+use sigil_core::path::format_path_data;
+use sigil_core::{Fill, FillRule, Gradient, Item, LineCap, ShapeStroke, StrokeAlign};
+
+/// SVG path data for the outline of a vector shape item, or `None` for other items.
+pub fn shape_path(item: &Item) -> Option<String> {
+    match item {
+        Item::Ellipse(ellipse) => {
+            let (rx, ry) = (*ellipse.width / 2.0, *ellipse.height / 2.0);
+            Some(format!(
+                "M 0 {ry} A {rx} {ry} 0 1 0 {w} {ry} A {rx} {ry} 0 1 0 0 {ry} Z",
+                w = *ellipse.width
+            ))
+        }
+        Item::Line(line) => Some(format!("M {} {} L {} {}", *line.x1, *line.y1, *line.x2, *line.y2)),
+        Item::Polygon(polygon) => Some(points_path(&polygon.points)),
+        Item::Star(star) => Some(points_path(&star.vertices())),
+        Item::RegularPolygon(polygon) => Some(points_path(&polygon.vertices())),
+        Item::Path(path) => path.segments().ok().map(|segments| format_path_data(&segments)),
+        Item::Text(_) | Item::Image(_) | Item::Rect(_) | Item::Slider(_) | Item::Group(_) | Item::Repeat(_) => None,
+    }
+}
+
+fn points_path(points: &[[f32; 2]]) -> String {
+    let segments: Vec<String> = points
+        .iter()
+        .enumerate()
+        .map(|(i, [x, y])| format!("{} {} {}", if i == 0 { "M" } else { "L" }, x, y))
+        .collect();
+    format!("{} Z", segments.join(" "))
+}
+
+/// An `<svg>` drawing a vector shape item over its box, at the top left of the containing element.
+///
+/// Gradient, clip and mask definitions get ids derived from `id`, which must be unique in the document.
+/// Inside and outside strokes are drawn twice as wide and clipped or masked to one side of the edge,
+/// as `sigil-render` does.
+pub fn shape_svg(id: &str, item: &Item) -> Element {
+    let Some(d) = shape_path(item) else {
+        return VNode::empty();
+    };
+    let (width, height) = item.size().unwrap_or_default();
+
+    if let Item::Line(line) = item {
+        let cap = match line.cap {
+            LineCap::Butt => "butt",
+            LineCap::Round => "round",
+            LineCap::Square => "square",
+        };
+        let dash = dasharray(&line.dash);
+        let stroke_width = *line.stroke_width;
+        return rsx! {
+            svg {
+                width: "{width}",
+                height: "{height}",
+                style: "position: absolute; left: 0; top: 0; overflow: visible;",
+                path {
+                    d: "{d}",
+                    fill: "none",
+                    stroke: "{line.color}",
+                    stroke_width: "{stroke_width}",
+                    stroke_linecap: "{cap}",
+                    stroke_dasharray: "{dash}",
+                }
+            }
+        };
+    }
+
+    let (fill, stroke, fill_rule) = match item {
+        Item::Ellipse(ellipse) => (Some(&ellipse.color), ellipse.stroke.as_ref(), FillRule::Nonzero),
+        Item::Polygon(polygon) => (Some(&polygon.color), polygon.stroke.as_ref(), FillRule::Nonzero),
+        Item::Star(star) => (Some(&star.color), star.stroke.as_ref(), FillRule::Nonzero),
+        Item::RegularPolygon(polygon) => (Some(&polygon.color), polygon.stroke.as_ref(), FillRule::Nonzero),
+        Item::Path(path) => (path.fill.as_ref(), path.stroke.as_ref(), path.fill_rule),
+        _ => return VNode::empty(),
+    };
+
+    let fill_id = format!("{id}-fill");
+    let fill_value = match fill {
+        Some(Fill::Solid(color)) => color.clone(),
+        Some(Fill::Gradient(_)) => format!("url(#{fill_id})"),
+        None => "none".to_string(),
+    };
+    let rule = match fill_rule {
+        FillRule::Nonzero => "nonzero",
+        FillRule::EvenOdd => "evenodd",
+    };
+    let stroke = stroke.filter(|stroke| *stroke.width > 0.0);
+
+    rsx! {
+        svg {
+            width: "{width}",
+            height: "{height}",
+            view_box: "0 0 {width} {height}",
+            style: "position: absolute; left: 0; top: 0; overflow: visible;",
+            if let Some(Fill::Gradient(gradient)) = fill {
+                defs { {gradient_def(&fill_id, gradient, width, height)} }
+            }
+            path { d: "{d}", fill: "{fill_value}", fill_rule: "{rule}" }
+            if let Some(stroke) = stroke {
+                {stroke_path(id, &d, rule, stroke, width, height)}
+            }
+        }
+    }
+}
+
+/// A `<linearGradient>` or `<radialGradient>` laid out over the box like the CSS gradients in `style::css_fill`.
+fn gradient_def(id: &str, gradient: &Gradient, width: f32, height: f32) -> Element {
+    let stops = gradient.stops().to_vec();
+    match gradient {
+        Gradient::Linear(linear) => {
+            let ([x1, y1], [x2, y2]) = linear.endpoints(width, height);
+            rsx! {
+                linearGradient {
+                    id: "{id}",
+                    gradient_units: "userSpaceOnUse",
+                    x1: "{x1}",
+                    y1: "{y1}",
+                    x2: "{x2}",
+                    y2: "{y2}",
+                    for stop in stops {
+                        stop { offset: "{stop.offset}", stop_color: "{stop.color}" }
+                    }
+                }
+            }
+        }
+        Gradient::Radial(radial) => {
+            let ([cx, cy], r) = radial.geometry(width, height);
+            rsx! {
+                radialGradient {
+                    id: "{id}",
+                    gradient_units: "userSpaceOnUse",
+                    cx: "{cx}",
+                    cy: "{cy}",
+                    r: "{r}",
+                    for stop in stops {
+                        stop { offset: "{stop.offset}", stop_color: "{stop.color}" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The stroke of a shape whose inside is given by `d` and the SVG `fill_rule`.
+fn stroke_path(id: &str, d: &str, fill_rule: &str, stroke: &ShapeStroke, width: f32, height: f32) -> Element {
+    let dash = dasharray(&stroke.dash);
+    let doubled = *stroke.width * 2.0;
+
+    match stroke.align {
+        StrokeAlign::Center => rsx! {
+            path {
+                d: "{d}",
+                fill: "none",
+                stroke: "{stroke.color}",
+                stroke_width: "{stroke.width}",
+                stroke_dasharray: "{dash}",
+            }
+        },
+        StrokeAlign::Inside => {
+            let clip_id = format!("{id}-inside");
+            rsx! {
+                defs {
+                    clipPath { id: "{clip_id}", path { d: "{d}", clip_rule: "{fill_rule}" } }
+                }
+                path {
+                    d: "{d}",
+                    fill: "none",
+                    stroke: "{stroke.color}",
+                    stroke_width: "{doubled}",
+                    stroke_dasharray: "{dash}",
+                    clip_path: "url(#{clip_id})",
+                }
+            }
+        }
+        StrokeAlign::Outside => {
+            let mask_id = format!("{id}-outside");
+            // The mask region has to cover the stroke, which reaches past the box
+            let (x, y) = (-doubled, -doubled);
+            let (mask_width, mask_height) = (width + 2.0 * doubled, height + 2.0 * doubled);
+            rsx! {
+                defs {
+                    mask {
+                        id: "{mask_id}",
+                        mask_units: "userSpaceOnUse",
+                        x: "{x}",
+                        y: "{y}",
+                        width: "{mask_width}",
+                        height: "{mask_height}",
+                        rect { x: "{x}", y: "{y}", width: "{mask_width}", height: "{mask_height}", fill: "white" }
+                        path { d: "{d}", fill: "black", fill_rule: "{fill_rule}" }
+                    }
+                }
+                path {
+                    d: "{d}",
+                    fill: "none",
+                    stroke: "{stroke.color}",
+                    stroke_width: "{doubled}",
+                    stroke_dasharray: "{dash}",
+                    mask: "url(#{mask_id})",
+                }
+            }
+        }
+    }
+}
+
+/// `stroke-dasharray` value; `none` for a solid stroke.
+fn dasharray(dash: &[f32]) -> String {
+    if dash.is_empty() {
+        return "none".to_string();
+    }
+    dash.iter().map(|length| length.to_string()).collect::<Vec<_>>().join(" ")
+}
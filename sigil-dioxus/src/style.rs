@@ -0,0 +1,298 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! CSS fragments shared by `render_to_rsx` and the editor so both HTML previews lay items out like `sigil-render`.
+
+use sigil_core::{
+    Background, BlendMode, Effect, Fill, FontStyle, Gradient, GradientStop, Item, Layer, ShapeStroke, TextAlign, TextItem, TextSpan, TextTransform, VerticalAlign, DEFAULT_FONT_WEIGHT, DEFAULT_LINE_HEIGHT,
+};
+
+/// Maps the editor's font family names onto CSS generic families, passing custom names through.
+pub fn css_font_family(font_family: &str) -> String {
+    match font_family {
+        "Sans Serif" => "sans-serif".to_string(),
+        "Serif" => "serif".to_string(),
+        "Monospace" => "monospace".to_string(),
+        "Cursive" => "cursive".to_string(),
+        "Fantasy" => "fantasy".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// CSS value for a fill: the color itself, or a `linear-gradient()` / `radial-gradient()` image.
+pub fn css_fill(fill: &Fill) -> String {
+    let stops = |stops: &[GradientStop]| {
+        stops
+            .iter()
+            .map(|stop| format!("{} {}%", stop.color, stop.offset * 100.0))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    match fill {
+        Fill::Solid(color) => color.clone(),
+        Fill::Gradient(Gradient::Linear(linear)) => {
+            format!("linear-gradient({}deg, {})", linear.angle, stops(&linear.stops))
+        }
+        Fill::Gradient(Gradient::Radial(radial)) => format!(
+            "radial-gradient(circle farthest-corner at {}% {}%, {})",
+            radial.center_x * 100.0,
+            radial.center_y * 100.0,
+            stops(&radial.stops)
+        ),
+    }
+}
+
+/// The container's CSS for a color, gradient or pattern background. Image backgrounds are drawn by
+/// `image::background_image` instead.
+pub fn background_css(background: &Background) -> String {
+    match background {
+        Background::Solid(color) => format!("background-color: {};", color),
+        Background::Gradient(gradient) => format!("background: {};", css_fill(&Fill::Gradient(gradient.clone()))),
+        Background::Image(_) => String::new(),
+        Background::Pattern(pattern) => {
+            let size = match &pattern.tile_size {
+                Some([width, height]) => format!("{}px {}px", **width, **height),
+                None => "auto".to_string(),
+            };
+            format!(
+                "background-image: url('{}'); background-size: {size}; background-position: 0 0; background-repeat: repeat;",
+                pattern.source
+            )
+        }
+    }
+}
+
+/// A shape stroke as an `outline`, which follows `border-radius` and, unlike `border`, leaves the box size alone.
+/// `outline-offset` places it inside, centered on or outside the edge. Browsers cannot draw custom dash
+/// lengths, so any dash pattern shows as `dashed`.
+pub fn stroke_css(stroke: &ShapeStroke) -> String {
+    if *stroke.width <= 0.0 {
+        return String::new();
+    }
+
+    let style = if stroke.dash.is_empty() { "solid" } else { "dashed" };
+    format!(
+        "outline: {}px {} {}; outline-offset: {}px;",
+        *stroke.width,
+        style,
+        stroke.color,
+        stroke.center_offset() - *stroke.width / 2.0
+    )
+}
+
+/// Layer-wide compositing: `opacity`, `mix-blend-mode` and effects, omitted when they have no effect.
+pub fn layer_css(layer: &Layer) -> String {
+    let mut css = String::new();
+
+    if *layer.opacity < 1.0 {
+        css.push_str(&format!("opacity: {}; ", layer.opacity.max(0.0)));
+    }
+    if layer.blend_mode != BlendMode::Normal {
+        css.push_str(&format!("mix-blend-mode: {}; ", layer.blend_mode.css_name()));
+    }
+    css.push_str(&effects_css(layer));
+
+    css
+}
+
+/// Layer effects as `box-shadow`, `filter` and `backdrop-filter`.
+///
+/// Shadows of text and vector shapes use `filter: drop-shadow()` so they follow the glyphs or outline
+/// rather than the box; it has no spread or inset form, so those ignore spread and inner shadows. `blur()` takes a standard
+/// deviation rather than a shadow blur radius, hence the halved radii.
+fn effects_css(layer: &Layer) -> String {
+    let is_box = matches!(layer.item, Item::Rect(_) | Item::Image(_) | Item::Slider(_));
+    let mut box_shadows = Vec::new();
+    let mut filters = Vec::new();
+    let mut backdrop_filters = Vec::new();
+
+    for effect in &layer.effects {
+        match effect {
+            Effect::DropShadow(shadow) if !is_box => filters.push(format!(
+                "drop-shadow({}px {}px {}px {})",
+                *shadow.offset_x, *shadow.offset_y, *shadow.blur, shadow.color
+            )),
+            Effect::InnerShadow(_) if !is_box => {}
+            Effect::DropShadow(shadow) | Effect::InnerShadow(shadow) => box_shadows.push(format!(
+                "{}{}px {}px {}px {}px {}",
+                if matches!(effect, Effect::InnerShadow(_)) { "inset " } else { "" },
+                *shadow.offset_x,
+                *shadow.offset_y,
+                *shadow.blur,
+                *shadow.spread,
+                shadow.color
+            )),
+            Effect::LayerBlur { radius } => filters.push(format!("blur({}px)", **radius / 2.0)),
+            Effect::BackgroundBlur { radius } => backdrop_filters.push(format!("blur({}px)", **radius / 2.0)),
+        }
+    }
+
+    let mut css = String::new();
+    if !box_shadows.is_empty() {
+        css.push_str(&format!("box-shadow: {}; ", box_shadows.join(", ")));
+    }
+    if !filters.is_empty() {
+        css.push_str(&format!("filter: {}; ", filters.join(" ")));
+    }
+    if !backdrop_filters.is_empty() {
+        let backdrop = backdrop_filters.join(" ");
+        css.push_str(&format!("backdrop-filter: {}; -webkit-backdrop-filter: {}; ", backdrop, backdrop));
+    }
+
+    css
+}
+
+/// Width and height of the text box, taking an unresolved auto-fit box into account.
+fn text_box_size(text: &TextItem) -> (Option<f32>, Option<f32>) {
+    match &text.fit {
+        Some(fit) => (Some(*fit.box_width), Some(*fit.box_height)),
+        None => (text.max_width.as_deref().copied(), text.max_height.as_deref().copied()),
+    }
+}
+
+/// Size of the text box and vertical placement of the text inside it.
+pub fn text_box_css(text: &TextItem) -> String {
+    let mut css = String::new();
+    let (box_width, box_height) = text_box_size(text);
+
+    if let Some(max_width) = box_width {
+        css.push_str(&format!("width: {}px; ", max_width));
+    }
+
+    if let Some(max_height) = box_height {
+        let justify = match text.vertical_align {
+            VerticalAlign::Top => "flex-start",
+            VerticalAlign::Middle => "center",
+            VerticalAlign::Bottom => "flex-end",
+        };
+        css.push_str(&format!(
+            "height: {}px; overflow: hidden; display: flex; flex-direction: column; justify-content: {}; ",
+            max_height, justify
+        ));
+    }
+
+    css
+}
+
+/// Font, wrapping, alignment and line clamping of the text itself.
+///
+/// Auto-fit text that has not been measured by `sigil-render` (see `Sigil::with_fitted_text`)
+/// falls back to its maximum font size and lets the browser clip or ellipsize it.
+pub fn text_content_css(text: &TextItem) -> String {
+    let (box_width, _) = text_box_size(text);
+    let font_size = text.fit.as_ref().map_or(*text.font_size, |fit| *fit.max_font_size);
+
+    let white_space = if box_width.is_some() {
+        "white-space: pre-wrap; overflow-wrap: anywhere;"
+    } else {
+        "white-space: pre;"
+    };
+
+    let align = match text.align {
+        TextAlign::Left => "left",
+        TextAlign::Center => "center",
+        TextAlign::Right => "right",
+        TextAlign::Justify => "justify",
+    };
+
+    let font_style = match text.font_style {
+        FontStyle::Normal => "normal",
+        FontStyle::Italic => "italic",
+        FontStyle::Oblique => "oblique",
+    };
+
+    let text_transform = match text.text_transform {
+        TextTransform::None => "none",
+        TextTransform::Uppercase => "uppercase",
+        TextTransform::Lowercase => "lowercase",
+        TextTransform::Capitalize => "capitalize",
+    };
+
+    // Gradient text paints the background through the glyphs; decorations take the first stop color,
+    // as they do in sigil-render.
+    let color = match &text.color {
+        Fill::Solid(color) => format!("color: {};", color),
+        fill => format!(
+            "color: transparent; background: {}; -webkit-background-clip: text; background-clip: text; text-decoration-color: {};",
+            css_fill(fill),
+            fill.primary_color()
+        ),
+    };
+
+    let mut css = format!(
+        "font-size: {}px; {} font-family: {}; font-weight: {}; font-style: {}; letter-spacing: {}px; text-transform: {}; line-height: {}; text-align: {}; {}",
+        font_size,
+        color,
+        css_font_family(&text.font_family),
+        text.font_weight.as_deref().copied().unwrap_or(DEFAULT_FONT_WEIGHT),
+        font_style,
+        *text.letter_spacing,
+        text_transform,
+        text.line_height.as_deref().copied().unwrap_or(DEFAULT_LINE_HEIGHT),
+        align,
+        white_space
+    );
+
+    let decorations: Vec<&str> = [(*text.underline, "underline"), (*text.strikethrough, "line-through")]
+        .into_iter()
+        .filter_map(|(enabled, line)| enabled.then_some(line))
+        .collect();
+    if !decorations.is_empty() {
+        css.push_str(&format!(" text-decoration-line: {};", decorations.join(" ")));
+    }
+
+    if let Some(stroke) = &text.stroke {
+        css.push_str(&format!(
+            " -webkit-text-stroke: {}px {};",
+            *stroke.width, stroke.color
+        ));
+    }
+
+    if let Some(shadow) = &text.shadow {
+        css.push_str(&format!(
+            " text-shadow: {}px {}px {}px {};",
+            *shadow.offset_x, *shadow.offset_y, *shadow.blur, shadow.color
+        ));
+    }
+
+    if let Some(max_lines) = text.max_lines.as_deref() {
+        css.push_str(&format!(
+            " display: -webkit-box; -webkit-box-orient: vertical; -webkit-line-clamp: {}; overflow: hidden;",
+            max_lines
+        ));
+    }
+
+    if text.fit.as_ref().is_some_and(|fit| fit.ellipsis) {
+        css.push_str(" overflow: hidden; text-overflow: ellipsis;");
+    }
+
+    css
+}
+
+/// Overrides of a rich text span, for a `<span>` nested in the element styled by `text_content_css`.
+pub fn text_span_css(span: &TextSpan) -> String {
+    let mut css = String::new();
+
+    if let Some(color) = &span.color {
+        css.push_str(&format!("color: {}; ", color));
+    }
+    if let Some(font_weight) = span.font_weight.as_deref() {
+        css.push_str(&format!("font-weight: {}; ", font_weight));
+    }
+    if let Some(font_size) = span.font_size.as_deref() {
+        css.push_str(&format!("font-size: {}px; ", font_size));
+    }
+    if let Some(font_family) = &span.font_family {
+        css.push_str(&format!("font-family: {}; ", css_font_family(font_family)));
+    }
+
+    css
+}
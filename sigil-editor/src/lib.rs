@@ -13,7 +13,12 @@
 
 use dioxus::prelude::*;
 use std::collections::{HashSet, HashMap};
-use sigil_core::{Sigil, Layer, Item, RectItem, TextItem, ImageItem};
+use sigil_core::{Sigil, FORMAT_VERSION, Background, Bindable, BackgroundImage, BackgroundPattern, Layer, Item, BlendMode, RepeatDirection, RepeatItem, Effect, Shadow, Fill, EllipseItem, LineCap, LineItem, PolygonItem, StarItem, RegularPolygonItem, PathItem, FillRule, GroupItem, LayerMask, MaskMode, MaskSource, Rgba, Gradient, GradientStop, LinearGradient, RadialGradient, RectItem, TextItem, ImageItem, ImageFit, ImageCrop, ImageFilter, VariableDef, VariableType, ShapeStroke, Severity, StrokeAlign, TextAlign, VerticalAlign, FontStyle, TextTransform, TextStroke, TextShadow, TextSpan, ValidationIssue, DEFAULT_FONT_WEIGHT};
+use sigil_dioxus::image::{background_image, image_content};
+use sigil_dioxus::mask::mask_css;
+use sigil_dioxus::render_layers;
+use sigil_dioxus::shape::shape_svg;
+use sigil_dioxus::style::{background_css, css_fill, layer_css, stroke_css, text_box_css, text_content_css, text_span_css};
 
 const MAIN_CSS: Asset = asset!("/assets/editor.css");
 
@@ -67,37 +72,59 @@ pub fn snap_to_grid(val: f32) -> f32 {
     (val / GRID_SIZE).round() * GRID_SIZE
 }
 
+/// Sets a field moved on the canvas, unless a variable binds it: the template decides that value, so
+/// dragging or pasting must not replace the binding with a literal.
+fn set_unbound<T>(field: &mut Bindable<T>, value: T) {
+    if field.binding().is_none() {
+        field.set(value);
+    }
+}
+
 #[component]
 pub fn SigilEditor() -> Element {
     let mut sigil = use_signal(|| Sigil {
+        version: FORMAT_VERSION,
         width: 400,
         height: 200,
-        background: "#222222".to_string(),
+        background: "#222222".into(),
+        variables: Vec::new(),
         layers: vec![
             Layer {
                 id: "bg".to_string(),
-                x: 0.0,
-                y: 0.0,
-                rotation: 0.0,
-                visible: true,
+                x: 0.0.into(),
+                y: 0.0.into(),
+                rotation: 0.0.into(),
+                visible: true.into(),
+                opacity: 1.0.into(),
+                blend_mode: BlendMode::Normal,
+                effects: Vec::new(),
+                mask: None,
+                condition: None,
                 item: Item::Rect(RectItem {
-                    width: 400.0,
-                    height: 200.0,
-                    color: "#333333".to_string(),
-                    border_radius: 0.0,
+                    width: 400.0.into(),
+                    height: 200.0.into(),
+                    color: "#333333".into(),
+                    border_radius: 0.0.into(),
+                    stroke: None,
                 }),
             },
             Layer {
                 id: "text".to_string(),
-                x: 20.0,
-                y: 50.0,
-                rotation: 0.0,
-                visible: true,
+                x: 20.0.into(),
+                y: 50.0.into(),
+                rotation: 0.0.into(),
+                visible: true.into(),
+                opacity: 1.0.into(),
+                blend_mode: BlendMode::Normal,
+                effects: Vec::new(),
+                mask: None,
+                condition: None,
                 item: Item::Text(TextItem {
                     text: "Hello Dioxus!".to_string(),
-                    font_size: 32.0,
-                    color: "#ffffff".to_string(),
+                    font_size: 32.0.into(),
+                    color: "#ffffff".into(),
                     font_family: "Sans Serif".to_string(),
+                    ..Default::default()
                 }),
             }
         ],
@@ -106,16 +133,27 @@ pub fn SigilEditor() -> Element {
     let mut dragging = use_signal(|| None::<(usize, DragMode)>);
     let mut dragging_layer_index = use_signal(|| None::<usize>);
     let mut drag_over_state = use_signal(|| None::<(usize, bool)>);
-    let mut selected_layers = use_signal(|| HashSet::<usize>::new());
-    let mut locked_layers = use_signal(|| HashSet::<usize>::new());
-    let mut clipboard = use_signal(|| Vec::<Layer>::new());
-    let mut guides = use_signal(|| Vec::<Guide>::new());
-    let mut text_dimensions = use_signal(|| HashMap::<String, (f32, f32)>::new());
+    let mut selected_layers = use_signal(HashSet::<usize>::new);
+    let mut locked_layers = use_signal(HashSet::<usize>::new);
+    let expanded_groups = use_signal(HashSet::<String>::new);
+    let mut clipboard = use_signal(Vec::<Layer>::new);
+    let mut guides = use_signal(Vec::<Guide>::new);
+    let mut text_dimensions = use_signal(HashMap::<String, (f32, f32)>::new);
     let mut add_layer_type = use_signal(|| "Rectangle".to_string());
     let mut layer_id_counter = use_signal(|| 2);
     let mut show_load_modal = use_signal(|| false);
-    let mut load_json_text = use_signal(|| String::new());
+    let mut load_json_text = use_signal(String::new);
     let mut load_error = use_signal(|| None::<String>);
+    let mut preview_values = use_signal(HashMap::<String, String>::new);
+    // Validation reruns only when the template or the JSON being loaded changes, not on every render
+    let issues = use_memo(move || sigil.read().validate());
+    let load_issues = use_memo(move || {
+        serde_json::from_str(&load_json_text.read())
+            .ok()
+            .and_then(|json| sigil_core::migrate(json).ok())
+            .map(|loaded| loaded.validate())
+            .unwrap_or_default()
+    });
 
     let cursor_style = if dragging.read().is_some() { "grabbing" } else { "default" };
 
@@ -161,8 +199,9 @@ pub fn SigilEditor() -> Element {
                         for mut layer in to_paste {
                             current_id += 1;
                             layer.id = format!("{}_{}", layer.id, current_id);
-                            layer.x += 20.0;
-                            layer.y += 20.0;
+                            let (x, y) = (*layer.x + 20.0, *layer.y + 20.0);
+                            set_unbound(&mut layer.x, x);
+                            set_unbound(&mut layer.y, y);
                             
                             sigil.write().layers.push(layer);
                             new_selection.insert(sigil.read().layers.len() - 1);
@@ -175,6 +214,30 @@ pub fn SigilEditor() -> Element {
                     evt.prevent_default();
                 }
 
+                if evt.key() == Key::Character("g".to_string()) && is_ctrl {
+                    if evt.modifiers().contains(Modifiers::SHIFT) {
+                        let idx_opt = selected_layers.read().iter().next().cloned();
+                        if let Some(idx) = idx_opt
+                            && selected_layers.read().len() == 1
+                        {
+                            let children = ungroup_layer(&mut sigil.write().layers, idx, &text_dimensions.read());
+                            if !children.is_empty() {
+                                selected_layers.set(children.into_iter().collect());
+                            }
+                        }
+                    } else {
+                        let current_id = *layer_id_counter.read() + 1;
+                        let indices = selected_layers.read().clone();
+                        if let Some(group_idx) = group_layers(&mut sigil.write().layers, &indices, format!("group_{}", current_id), &text_dimensions.read()) {
+                            layer_id_counter.set(current_id);
+                            selected_layers.write().clear();
+                            selected_layers.write().insert(group_idx);
+                        }
+                    }
+                    evt.stop_propagation();
+                    evt.prevent_default();
+                }
+
                 if evt.key() == Key::Delete {
                     let to_remove: Vec<usize> = selected_layers.read().iter().cloned().collect();
                     if !to_remove.is_empty() {
@@ -195,13 +258,12 @@ pub fn SigilEditor() -> Element {
                     evt.prevent_default();
                 }
 
-                if evt.key() == Key::Escape {
-                    if *show_load_modal.read() {
+                if evt.key() == Key::Escape
+                    && *show_load_modal.read() {
                         show_load_modal.set(false);
                         evt.stop_propagation();
                         evt.prevent_default();
                     }
-                }
             },
             onmousemove: move |evt| {
                 let mut dragging_write = dragging.write();
@@ -234,52 +296,22 @@ pub fn SigilEditor() -> Element {
                                 let canvas_h = sigil_read.height as f32;
                                 
                                 if let Some(layer) = sigil_read.layers.get(drag_idx) {
-                                    let (w, h) = match &layer.item {
-                                        Item::Rect(r) => (r.width, r.height),
-                                        Item::Image(i) => (i.width, i.height),
-                                        Item::Text(t) => {
-                                            if let Some(&(tw, th)) = text_dimensions.read().get(&layer.id) {
-                                                (tw, th)
-                                            } else {
-                                                (t.text.len() as f32 * t.font_size * 0.6, t.font_size)
-                                            }
-                                        },
-                                        Item::Slider(s) => (s.width, s.height),
-                                    };
+                                    let (w, h) = layer_size(layer, &text_dimensions.read());
 
-                                    let mut proposed_x = *orig_x + delta_x as f32;
-                                    let mut proposed_y = *orig_y + delta_y as f32;
+                                    let proposed_x = *orig_x + delta_x as f32;
+                                    let proposed_y = *orig_y + delta_y as f32;
                                     
                                     let threshold = 5.0;
                                     let mut snap_x_delta: Option<f32> = None;
                                     let mut snap_y_delta: Option<f32> = None;
 
-                                    let mut check_snap = |val: f32, target: f32, is_vertical: bool, start: f32, end: f32| {
-                                        let diff = target - val;
-                                        if diff.abs() < threshold {
-                                            if is_vertical {
-                                                if snap_x_delta.is_none() || diff.abs() < snap_x_delta.unwrap().abs() {
-                                                    snap_x_delta = Some(diff);
-                                            
-                                                }
-                                            } else {
-                                                if snap_y_delta.is_none() || diff.abs() < snap_y_delta.unwrap().abs() {
-                                                    snap_y_delta = Some(diff);
-                                                }
-                                            }
-                                            return true;
-                                        }
-                                        false
-                                    };
-
-
-                                    let v_targets = vec![
+                                    let v_targets = [
                                         (0.0, 0.0, canvas_h), 
                                         (canvas_w / 2.0, 0.0, canvas_h), 
                                         (canvas_w, 0.0, canvas_h), 
                                     ];
                                     
-                                    let h_targets = vec![
+                                    let h_targets = [
                                         (0.0, 0.0, canvas_w), 
                                         (canvas_h / 2.0, 0.0, canvas_w), 
                                         (canvas_h, 0.0, canvas_w),
@@ -289,27 +321,16 @@ pub fn SigilEditor() -> Element {
                                     let mut other_h_targets = Vec::new();
                                     
                                     for (i, l) in sigil_read.layers.iter().enumerate() {
-                                        if i != drag_idx && !selected_layers.read().contains(&i) && l.visible {
-                                            let (lw, lh) = match &l.item {
-                                                Item::Rect(r) => (r.width, r.height),
-                                                Item::Image(img) => (img.width, img.height),
-                                                Item::Text(t) => {
-                                                    if let Some(&(tw, th)) = text_dimensions.read().get(&l.id) {
-                                                        (tw, th)
-                                                    } else {
-                                                        (t.text.len() as f32 * t.font_size * 0.6, t.font_size)
-                                                    }
-                                                },
-                                                Item::Slider(s) => (s.width, s.height),
-                                            };
+                                        if i != drag_idx && !selected_layers.read().contains(&i) && *l.visible {
+                                            let (lw, lh) = layer_size(l, &text_dimensions.read());
                                             
-                                            other_v_targets.push((l.x, l.y, l.y + lh)); 
-                                            other_v_targets.push((l.x + lw / 2.0, l.y, l.y + lh)); 
-                                            other_v_targets.push((l.x + lw, l.y, l.y + lh)); 
+                                            other_v_targets.push((*l.x, *l.y, *l.y + lh)); 
+                                            other_v_targets.push((*l.x + lw / 2.0, *l.y, *l.y + lh)); 
+                                            other_v_targets.push((*l.x + lw, *l.y, *l.y + lh)); 
                                             
-                                            other_h_targets.push((l.y, l.x, l.x + lw)); 
-                                            other_h_targets.push((l.y + lh / 2.0, l.x, l.x + lw)); 
-                                            other_h_targets.push((l.y + lh, l.x, l.x + lw)); 
+                                            other_h_targets.push((*l.y, *l.x, *l.x + lw)); 
+                                            other_h_targets.push((*l.y + lh / 2.0, *l.x, *l.x + lw)); 
+                                            other_h_targets.push((*l.y + lh, *l.x, *l.x + lw)); 
                                         }
                                     }
 
@@ -322,16 +343,15 @@ pub fn SigilEditor() -> Element {
                                             (proposed_x + w, w), 
                                         ];
 
-                                        for (pt_x, offset) in x_points {
+                                        for (pt_x, _) in x_points {
                                             for &(target, t_start, t_end) in v_targets.iter().chain(other_v_targets.iter()) {
-                                                if (pt_x - target).abs() < threshold {
-                                                    if snap_x_delta.is_none() || (target - pt_x).abs() < snap_x_delta.unwrap().abs() {
+                                                if (pt_x - target).abs() < threshold
+                                                    && (snap_x_delta.is_none() || (target - pt_x).abs() < snap_x_delta.unwrap().abs()) {
                                                         snap_x_delta = Some(target - pt_x);
                                                         let min_y = proposed_y.min(t_start);
                                                         let max_y = (proposed_y + h).max(t_end);
                                                         best_v_guide = Some(Guide { is_vertical: true, pos: target, start: min_y, end: max_y });
                                                     }
-                                                }
                                             }
                                         }
                                     }
@@ -344,16 +364,15 @@ pub fn SigilEditor() -> Element {
                                             (proposed_y + h, h), 
                                         ];
 
-                                        for (pt_y, offset) in y_points {
+                                        for (pt_y, _) in y_points {
                                             for &(target, t_start, t_end) in h_targets.iter().chain(other_h_targets.iter()) {
-                                                if (pt_y - target).abs() < threshold {
-                                                    if snap_y_delta.is_none() || (target - pt_y).abs() < snap_y_delta.unwrap().abs() {
+                                                if (pt_y - target).abs() < threshold
+                                                    && (snap_y_delta.is_none() || (target - pt_y).abs() < snap_y_delta.unwrap().abs()) {
                                                         snap_y_delta = Some(target - pt_y);
                                                         let min_x = proposed_x.min(t_start);
                                                         let max_x = (proposed_x + w).max(t_end);
                                                         best_h_guide = Some(Guide { is_vertical: false, pos: target, start: min_x, end: max_x });
                                                     }
-                                                }
                                             }
                                         }
                                     }
@@ -370,13 +389,13 @@ pub fn SigilEditor() -> Element {
                             }
 
                             for (idx, orig_x, orig_y) in original_positions {
-                                let mut new_x = *orig_x + delta_x as f32;
-                                let mut new_y = *orig_y + delta_y as f32;
+                                let new_x = *orig_x + delta_x as f32;
+                                let new_y = *orig_y + delta_y as f32;
 
                                 
                                 if let Some(layer) = sigil.write().layers.get_mut(*idx) {
-                                    layer.x = new_x;
-                                    layer.y = new_y;
+                                    set_unbound(&mut layer.x, new_x);
+                                    set_unbound(&mut layer.y, new_y);
                                 }
                             }
                         },
@@ -436,15 +455,10 @@ pub fn SigilEditor() -> Element {
                                 if new_h < GRID_SIZE { new_h = GRID_SIZE; }
                                 
                                 if let Some(layer) = sigil.write().layers.get_mut(idx) {
-                                    layer.x = new_x;
-                                    layer.y = new_y;
+                                    set_unbound(&mut layer.x, new_x);
+                                    set_unbound(&mut layer.y, new_y);
                                     
-                                    match &mut layer.item {
-                                        Item::Rect(r) => { r.width = new_w; r.height = new_h; },
-                                        Item::Image(i) => { i.width = new_w; i.height = new_h; },
-                                        Item::Slider(s) => { s.width = new_w; s.height = new_h; },
-                                        _ => {}
-                                    }
+                                    layer.item.resize(new_w, new_h);
                                 }
                             }
                         },
@@ -457,7 +471,7 @@ pub fn SigilEditor() -> Element {
                                 let new_rotation = *orig_rotation + delta_angle.to_degrees() as f32;
                                 
                                 if let Some(layer) = sigil.write().layers.get_mut(idx) {
-                                    layer.rotation = new_rotation;
+                                    set_unbound(&mut layer.rotation, new_rotation);
                                 }
                             }
                         }
@@ -525,6 +539,26 @@ pub fn SigilEditor() -> Element {
                         }
                     }
                 }
+                BackgroundEditor {
+                    background: sigil.read().background.clone(),
+                    on_change: move |background| sigil.write().background = background,
+                }
+                VariablesEditor {
+                    variables: sigil.read().variables.clone(),
+                    on_change: move |variables| sigil.write().variables = variables,
+                }
+                PreviewValuesEditor {
+                    variables: preview_variables(&sigil.read()),
+                    values: preview_values.read().clone(),
+                    on_change: move |values| preview_values.set(values),
+                }
+                IssueList {
+                    issues: issues(),
+                    on_select: move |idx| {
+                        selected_layers.write().clear();
+                        selected_layers.write().insert(idx);
+                    },
+                }
 
                     div {
                         class: "inspector-panel",
@@ -544,14 +578,13 @@ pub fn SigilEditor() -> Element {
                                                     class: "control-group",
                                                     label { "Width: " }
                                                     input {
-                                                        r#type: "number",
+                                                        r#type: "text",
                                                         value: "{r.width}",
                                                         oninput: move |evt| {
-                                                            if let Ok(val) = evt.value().parse::<f32>() {
-                                                                if let Item::Rect(ref mut rect) = sigil.write().layers[idx].item {
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Rect(ref mut rect) = sigil.write().layers[idx].item {
                                                                     rect.width = val;
                                                                 }
-                                                            }
                                                         }
                                                     }
                                                 }
@@ -559,27 +592,22 @@ pub fn SigilEditor() -> Element {
                                                     class: "control-group",
                                                     label { "Height: " }
                                                     input {
-                                                        r#type: "number",
+                                                        r#type: "text",
                                                         value: "{r.height}",
                                                         oninput: move |evt| {
-                                                            if let Ok(val) = evt.value().parse::<f32>() {
-                                                                if let Item::Rect(ref mut rect) = sigil.write().layers[idx].item {
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Rect(ref mut rect) = sigil.write().layers[idx].item {
                                                                     rect.height = val;
                                                                 }
-                                                            }
                                                         }
                                                     }
                                                 }
-                                                div {
-                                                    class: "control-group",
-                                                    label { "Color: " }
-                                                    input {
-                                                        r#type: "color",
-                                                        value: "{r.color}",
-                                                        oninput: move |evt| {
-                                                            if let Item::Rect(ref mut rect) = sigil.write().layers[idx].item {
-                                                                rect.color = evt.value();
-                                                            }
+                                                FillEditor {
+                                                    label: "Color",
+                                                    fill: r.color.clone(),
+                                                    on_change: move |fill| {
+                                                        if let Item::Rect(ref mut rect) = sigil.write().layers[idx].item {
+                                                            rect.color = fill;
                                                         }
                                                     }
                                                 }
@@ -587,31 +615,174 @@ pub fn SigilEditor() -> Element {
                                                     class: "control-group",
                                                     label { "Radius: " }
                                                     input {
-                                                        r#type: "number",
+                                                        r#type: "text",
                                                         value: "{r.border_radius}",
                                                         oninput: move |evt| {
-                                                            if let Ok(val) = evt.value().parse::<f32>() {
-                                                                if let Item::Rect(ref mut rect) = sigil.write().layers[idx].item {
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Rect(ref mut rect) = sigil.write().layers[idx].item {
                                                                     rect.border_radius = val;
                                                                 }
+                                                        }
+                                                    }
+                                                }
+                                                StrokeEditor {
+                                                    stroke: r.stroke.clone(),
+                                                    on_change: move |stroke| {
+                                                        if let Item::Rect(ref mut rect) = sigil.write().layers[idx].item {
+                                                            rect.stroke = stroke;
+                                                        }
+                                                    }
+                                                }
+                                            },
+                                            Item::Image(i) => {
+                                                let crop = i.crop.as_ref().map(|c| format_bindables(&[c.x.clone(), c.y.clone(), c.width.clone(), c.height.clone()])).unwrap_or_default();
+                                                rsx! {
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "Width: " }
+                                                        input {
+                                                            r#type: "text",
+                                                            value: "{i.width}",
+                                                            oninput: move |evt| {
+                                                                if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                    && let Item::Image(ref mut img) = sigil.write().layers[idx].item {
+                                                                        img.width = val;
+                                                                    }
+                                                            }
+                                                        }
+                                                    }
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "Height: " }
+                                                        input {
+                                                            r#type: "text",
+                                                            value: "{i.height}",
+                                                            oninput: move |evt| {
+                                                                if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                    && let Item::Image(ref mut img) = sigil.write().layers[idx].item {
+                                                                        img.height = val;
+                                                                    }
+                                                            }
+                                                        }
+                                                    }
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "Source: " }
+                                                        input {
+                                                            r#type: "text",
+                                                            value: "{i.source}",
+                                                            oninput: move |evt| {
+                                                                if let Item::Image(ref mut img) = sigil.write().layers[idx].item {
+                                                                    img.source = evt.value();
+                                                                }
+                                                            }
+                                                        }
+                                                    }
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "Radius: " }
+                                                        input {
+                                                            r#type: "text",
+                                                            value: "{i.border_radius}",
+                                                            oninput: move |evt| {
+                                                                if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                    && let Item::Image(ref mut img) = sigil.write().layers[idx].item {
+                                                                        img.border_radius = val;
+                                                                    }
+                                                            }
+                                                        }
+                                                    }
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "Fit: " }
+                                                        select {
+                                                            value: "{i.fit.css_name()}",
+                                                            oninput: move |evt| {
+                                                                if let Some(fit) = ImageFit::from_css_name(&evt.value())
+                                                                    && let Item::Image(ref mut img) = sigil.write().layers[idx].item {
+                                                                        img.fit = fit;
+                                                                    }
+                                                            },
+                                                            for fit in ImageFit::ALL {
+                                                                option { value: "{fit.css_name()}", "{fit.css_name()}" }
+                                                            }
+                                                        }
+                                                    }
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "Focal X: " }
+                                                        input {
+                                                            r#type: "text",
+                                                            value: "{i.focal_point[0]}",
+                                                            oninput: move |evt| {
+                                                                if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                    && let Item::Image(ref mut img) = sigil.write().layers[idx].item {
+                                                                        img.focal_point[0] = val;
+                                                                    }
+                                                            }
+                                                        }
+                                                    }
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "Focal Y: " }
+                                                        input {
+                                                            r#type: "text",
+                                                            value: "{i.focal_point[1]}",
+                                                            oninput: move |evt| {
+                                                                if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                    && let Item::Image(ref mut img) = sigil.write().layers[idx].item {
+                                                                        img.focal_point[1] = val;
+                                                                    }
+                                                            }
+                                                        }
+                                                    }
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "Crop: " }
+                                                        input {
+                                                            r#type: "text",
+                                                            placeholder: "x y width height",
+                                                            value: "{crop}",
+                                                            oninput: move |evt| {
+                                                                if let Item::Image(ref mut img) = sigil.write().layers[idx].item {
+                                                                    img.crop = <[Bindable<f32>; 4]>::try_from(parse_bindables(&evt.value()))
+                                                                        .ok()
+                                                                        .map(|[x, y, width, height]| ImageCrop { x, y, width, height });
+                                                                }
+                                                            }
+                                                        }
+                                                    }
+                                                    FiltersEditor {
+                                                        label: "Filters",
+                                                        filters: i.filters.clone(),
+                                                        on_change: move |filters| {
+                                                            if let Item::Image(ref mut img) = sigil.write().layers[idx].item {
+                                                                img.filters = filters;
+                                                            }
+                                                        }
+                                                    }
+                                                    StrokeEditor {
+                                                        stroke: i.stroke.clone(),
+                                                        on_change: move |stroke| {
+                                                            if let Item::Image(ref mut img) = sigil.write().layers[idx].item {
+                                                                img.stroke = stroke;
                                                             }
                                                         }
                                                     }
                                                 }
                                             },
-                                            Item::Image(i) => rsx! {
+                                            Item::Slider(s) => rsx! {
                                                 div {
                                                     class: "control-group",
                                                     label { "Width: " }
                                                     input {
-                                                        r#type: "number",
-                                                        value: "{i.width}",
+                                                        r#type: "text",
+                                                        value: "{s.width}",
                                                         oninput: move |evt| {
-                                                            if let Ok(val) = evt.value().parse::<f32>() {
-                                                                if let Item::Image(ref mut img) = sigil.write().layers[idx].item {
-                                                                    img.width = val;
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Slider(ref mut slider) = sigil.write().layers[idx].item {
+                                                                    slider.width = val;
                                                                 }
-                                                            }
                                                         }
                                                     }
                                                 }
@@ -619,59 +790,73 @@ pub fn SigilEditor() -> Element {
                                                     class: "control-group",
                                                     label { "Height: " }
                                                     input {
-                                                        r#type: "number",
-                                                        value: "{i.height}",
+                                                        r#type: "text",
+                                                        value: "{s.height}",
                                                         oninput: move |evt| {
-                                                            if let Ok(val) = evt.value().parse::<f32>() {
-                                                                if let Item::Image(ref mut img) = sigil.write().layers[idx].item {
-                                                                    img.height = val;
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Slider(ref mut slider) = sigil.write().layers[idx].item {
+                                                                    slider.height = val;
                                                                 }
-                                                            }
                                                         }
                                                     }
                                                 }
                                                 div {
                                                     class: "control-group",
-                                                    label { "Source: " }
+                                                    label { "Background Color: " }
                                                     input {
-                                                        r#type: "text",
-                                                        value: "{i.source}",
+                                                        r#type: "color",
+                                                        value: "{color_input_value(&s.background_color)}",
                                                         oninput: move |evt| {
-                                                            if let Item::Image(ref mut img) = sigil.write().layers[idx].item {
-                                                                img.source = evt.value();
+                                                            if let Item::Slider(ref mut slider) = sigil.write().layers[idx].item {
+                                                                slider.background_color = evt.value();
                                                             }
                                                         }
                                                     }
                                                 }
+                                                FillEditor {
+                                                    label: "Fill Color",
+                                                    fill: s.fill_color.clone(),
+                                                    on_change: move |fill| {
+                                                        if let Item::Slider(ref mut slider) = sigil.write().layers[idx].item {
+                                                            slider.fill_color = fill;
+                                                        }
+                                                    }
+                                                }
                                                 div {
                                                     class: "control-group",
                                                     label { "Radius: " }
                                                     input {
-                                                        r#type: "number",
-                                                        value: "{i.border_radius}",
+                                                        r#type: "text",
+                                                        value: "{s.border_radius}",
                                                         oninput: move |evt| {
-                                                            if let Ok(val) = evt.value().parse::<f32>() {
-                                                                if let Item::Image(ref mut img) = sigil.write().layers[idx].item {
-                                                                    img.border_radius = val;
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Slider(ref mut slider) = sigil.write().layers[idx].item {
+                                                                    slider.border_radius = val;
                                                                 }
-                                                            }
+                                                        }
+                                                    }
+                                                }
+                                                StrokeEditor {
+                                                    stroke: s.stroke.clone(),
+                                                    on_change: move |stroke| {
+                                                        if let Item::Slider(ref mut slider) = sigil.write().layers[idx].item {
+                                                            slider.stroke = stroke;
                                                         }
                                                     }
                                                 }
                                             },
-                                            Item::Slider(s) => rsx! {
+                                            Item::Ellipse(e) => rsx! {
                                                 div {
                                                     class: "control-group",
                                                     label { "Width: " }
                                                     input {
-                                                        r#type: "number",
-                                                        value: "{s.width}",
+                                                        r#type: "text",
+                                                        value: "{e.width}",
                                                         oninput: move |evt| {
-                                                            if let Ok(val) = evt.value().parse::<f32>() {
-                                                                if let Item::Slider(ref mut slider) = sigil.write().layers[idx].item {
-                                                                    slider.width = val;
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Ellipse(ref mut ellipse) = sigil.write().layers[idx].item {
+                                                                    ellipse.width = val;
                                                                 }
-                                                            }
                                                         }
                                                     }
                                                 }
@@ -679,606 +864,2719 @@ pub fn SigilEditor() -> Element {
                                                     class: "control-group",
                                                     label { "Height: " }
                                                     input {
-                                                        r#type: "number",
-                                                        value: "{s.height}",
+                                                        r#type: "text",
+                                                        value: "{e.height}",
                                                         oninput: move |evt| {
-                                                            if let Ok(val) = evt.value().parse::<f32>() {
-                                                                if let Item::Slider(ref mut slider) = sigil.write().layers[idx].item {
-                                                                    slider.height = val;
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Ellipse(ref mut ellipse) = sigil.write().layers[idx].item {
+                                                                    ellipse.height = val;
                                                                 }
-                                                            }
                                                         }
                                                     }
                                                 }
+                                                FillEditor {
+                                                    label: "Color",
+                                                    fill: e.color.clone(),
+                                                    on_change: move |fill| {
+                                                        if let Item::Ellipse(ref mut ellipse) = sigil.write().layers[idx].item {
+                                                            ellipse.color = fill;
+                                                        }
+                                                    }
+                                                }
+                                                StrokeEditor {
+                                                    stroke: e.stroke.clone(),
+                                                    on_change: move |stroke| {
+                                                        if let Item::Ellipse(ref mut ellipse) = sigil.write().layers[idx].item {
+                                                            ellipse.stroke = stroke;
+                                                        }
+                                                    }
+                                                }
+                                            },
+                                            Item::Line(l) => rsx! {
                                                 div {
                                                     class: "control-group",
-                                                    label { "Background Color: " }
+                                                    label { "X1: " }
                                                     input {
-                                                        r#type: "color",
-                                                        value: "{s.background_color}",
+                                                        r#type: "text",
+                                                        value: "{l.x1}",
                                                         oninput: move |evt| {
-                                                            if let Item::Slider(ref mut slider) = sigil.write().layers[idx].item {
-                                                                slider.background_color = evt.value();
-                                                            }
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Line(ref mut line) = sigil.write().layers[idx].item {
+                                                                    line.x1 = val;
+                                                                }
                                                         }
                                                     }
                                                 }
                                                 div {
                                                     class: "control-group",
-                                                    label { "Fill Color: " }
+                                                    label { "Y1: " }
                                                     input {
-                                                        r#type: "color",
-                                                        value: "{s.fill_color}",
+                                                        r#type: "text",
+                                                        value: "{l.y1}",
                                                         oninput: move |evt| {
-                                                            if let Item::Slider(ref mut slider) = sigil.write().layers[idx].item {
-                                                                slider.fill_color = evt.value();
-                                                            }
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Line(ref mut line) = sigil.write().layers[idx].item {
+                                                                    line.y1 = val;
+                                                                }
                                                         }
                                                     }
                                                 }
                                                 div {
                                                     class: "control-group",
-                                                    label { "Radius: " }
+                                                    label { "X2: " }
                                                     input {
-                                                        r#type: "number",
-                                                        value: "{s.border_radius}",
+                                                        r#type: "text",
+                                                        value: "{l.x2}",
                                                         oninput: move |evt| {
-                                                            if let Ok(val) = evt.value().parse::<f32>() {
-                                                                if let Item::Slider(ref mut slider) = sigil.write().layers[idx].item {
-                                                                    slider.border_radius = val;
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Line(ref mut line) = sigil.write().layers[idx].item {
+                                                                    line.x2 = val;
                                                                 }
-                                                            }
                                                         }
                                                     }
                                                 }
-                                            },
-                                            Item::Text(t) => rsx! {
                                                 div {
                                                     class: "control-group",
-                                                    label { "Text: " }
+                                                    label { "Y2: " }
                                                     input {
                                                         r#type: "text",
-                                                        value: "{t.text}",
+                                                        value: "{l.y2}",
                                                         oninput: move |evt| {
-                                                            if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
-                                                                text.text = evt.value();
-                                                            }
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Line(ref mut line) = sigil.write().layers[idx].item {
+                                                                    line.y2 = val;
+                                                                }
                                                         }
                                                     }
                                                 }
                                                 div {
                                                     class: "control-group",
-                                                    label { "Font Size: " }
+                                                    label { "Color: " }
                                                     input {
-                                                        r#type: "number",
-                                                        value: "{t.font_size}",
+                                                        r#type: "color",
+                                                        value: "{color_input_value(&l.color)}",
                                                         oninput: move |evt| {
-                                                            if let Ok(val) = evt.value().parse::<f32>() {
-                                                                if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
-                                                                    text.font_size = val;
-                                                                }
+                                                            if let Item::Line(ref mut line) = sigil.write().layers[idx].item {
+                                                                line.color = evt.value();
                                                             }
                                                         }
                                                     }
                                                 }
                                                 div {
                                                     class: "control-group",
-                                                    label { "Color: " }
+                                                    label { "Stroke Width: " }
                                                     input {
-                                                        r#type: "color",
-                                                        value: "{t.color}",
+                                                        r#type: "text",
+                                                        value: "{l.stroke_width}",
                                                         oninput: move |evt| {
-                                                            if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
-                                                                text.color = evt.value();
-                                                            }
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Line(ref mut line) = sigil.write().layers[idx].item {
+                                                                    line.stroke_width = val;
+                                                                }
                                                         }
                                                     }
                                                 }
                                                 div {
                                                     class: "control-group",
-                                                    label { "Font Family: " }
+                                                    label { "Cap: " }
                                                     select {
-                                                        value: "{t.font_family}",
+                                                        value: "{line_cap_name(l.cap)}",
                                                         oninput: move |evt| {
-                                                            if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
-                                                                text.font_family = evt.value();
+                                                            if let Item::Line(ref mut line) = sigil.write().layers[idx].item {
+                                                                line.cap = match evt.value().as_str() {
+                                                                    "round" => LineCap::Round,
+                                                                    "square" => LineCap::Square,
+                                                                    _ => LineCap::Butt,
+                                                                };
                                                             }
                                                         },
-                                                        option { value: "Sans Serif", style: "font-family: sans-serif;", "Sans Serif" }
-                                                        option { value: "Serif", style: "font-family: serif;", "Serif" }
-                                                        option { value: "Monospace", style: "font-family: monospace;", "Monospace" }
-                                                        option { value: "Cursive", style: "font-family: cursive;", "Cursive" }
-                                                        option { value: "Fantasy", style: "font-family: fantasy;", "Fantasy" }
+                                                        option { value: "butt", "Butt" }
+                                                        option { value: "round", "Round" }
+                                                        option { value: "square", "Square" }
                                                     }
                                                 }
-                                            }
-                                        };
-
-                                        rsx! {
-                                            div {
-                                                class: "control-group",
-                                                label { "X: " }
-                                                input {
-                                                    r#type: "number",
-                                                    value: "{layer.x}",
-                                                    oninput: move |evt| {
-                                                        if let Ok(val) = evt.value().parse::<f32>() {
-                                                            sigil.write().layers[idx].x = val;
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Dash: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        placeholder: "e.g. 4 2",
+                                                        value: "{format_numbers(&l.dash)}",
+                                                        oninput: move |evt| {
+                                                            if let Item::Line(ref mut line) = sigil.write().layers[idx].item {
+                                                                line.dash = parse_numbers(&evt.value()).into_iter().filter(|d| *d > 0.0).collect();
+                                                            }
                                                         }
                                                     }
                                                 }
-                                            }
-                                            div {
-                                                class: "control-group",
-                                                label { "Y: " }
-                                                input {
-                                                    r#type: "number",
-                                                    value: "{layer.y}",
-                                                    oninput: move |evt| {
-                                                        if let Ok(val) = evt.value().parse::<f32>() {
-                                                            sigil.write().layers[idx].y = val;
+                                            },
+                                            Item::Polygon(p) => rsx! {
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Points: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        placeholder: "x,y x,y x,y",
+                                                        value: "{format_points(&p.points)}",
+                                                        oninput: move |evt| {
+                                                            if let Item::Polygon(ref mut polygon) = sigil.write().layers[idx].item {
+                                                                polygon.points = parse_points(&evt.value());
+                                                            }
                                                         }
                                                     }
                                                 }
-                                            }
-                                            div {
-                                                class: "control-group",
-                                                label { "Rotation: " }
-                                                input {
-                                                    r#type: "number",
-                                                    value: "{layer.rotation}",
-                                                    oninput: move |evt| {
-                                                        if let Ok(val) = evt.value().parse::<f32>() {
-                                                            sigil.write().layers[idx].rotation = val;
+                                                FillEditor {
+                                                    label: "Color",
+                                                    fill: p.color.clone(),
+                                                    on_change: move |fill| {
+                                                        if let Item::Polygon(ref mut polygon) = sigil.write().layers[idx].item {
+                                                            polygon.color = fill;
                                                         }
                                                     }
                                                 }
-                                            }
-                                            
-                                            {properties}
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                h3 { "Layers" }
-
-                div {
-                    class: "add-layer-controls",
-                    select {
-                        value: "{add_layer_type}",
-                        oninput: move |evt| add_layer_type.set(evt.value()),
-                        option { value: "Rectangle", "Rectangle" }
-                        option { value: "Text", "Text" }
-                        option { value: "Image", "Image" }
-                    }
-                    button {
-                        class: "primary-btn",
-                        onclick: move |_| {
-                            let current_id = *layer_id_counter.read();
-                            layer_id_counter.set(current_id + 1);
+                                                StrokeEditor {
+                                                    stroke: p.stroke.clone(),
+                                                    on_change: move |stroke| {
+                                                        if let Item::Polygon(ref mut polygon) = sigil.write().layers[idx].item {
+                                                            polygon.stroke = stroke;
+                                                        }
+                                                    }
+                                                }
+                                            },
+                                            Item::Star(st) => rsx! {
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Width: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        value: "{st.width}",
+                                                        oninput: move |evt| {
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Star(ref mut star) = sigil.write().layers[idx].item {
+                                                                    star.width = val;
+                                                                }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Height: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        value: "{st.height}",
+                                                        oninput: move |evt| {
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Star(ref mut star) = sigil.write().layers[idx].item {
+                                                                    star.height = val;
+                                                                }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Points: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        value: "{st.points}",
+                                                        oninput: move |evt| {
+                                                            if let Ok(val) = evt.value().parse::<Bindable<u32>>()
+                                                                && let Item::Star(ref mut star) = sigil.write().layers[idx].item {
+                                                                    star.points = val;
+                                                                }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Inner Ratio: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        value: "{st.inner_ratio}",
+                                                        oninput: move |evt| {
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Star(ref mut star) = sigil.write().layers[idx].item {
+                                                                    star.inner_ratio = val;
+                                                                }
+                                                        }
+                                                    }
+                                                }
+                                                FillEditor {
+                                                    label: "Color",
+                                                    fill: st.color.clone(),
+                                                    on_change: move |fill| {
+                                                        if let Item::Star(ref mut star) = sigil.write().layers[idx].item {
+                                                            star.color = fill;
+                                                        }
+                                                    }
+                                                }
+                                                StrokeEditor {
+                                                    stroke: st.stroke.clone(),
+                                                    on_change: move |stroke| {
+                                                        if let Item::Star(ref mut star) = sigil.write().layers[idx].item {
+                                                            star.stroke = stroke;
+                                                        }
+                                                    }
+                                                }
+                                            },
+                                            Item::RegularPolygon(rp) => rsx! {
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Width: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        value: "{rp.width}",
+                                                        oninput: move |evt| {
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::RegularPolygon(ref mut polygon) = sigil.write().layers[idx].item {
+                                                                    polygon.width = val;
+                                                                }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Height: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        value: "{rp.height}",
+                                                        oninput: move |evt| {
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::RegularPolygon(ref mut polygon) = sigil.write().layers[idx].item {
+                                                                    polygon.height = val;
+                                                                }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Sides: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        value: "{rp.sides}",
+                                                        oninput: move |evt| {
+                                                            if let Ok(val) = evt.value().parse::<Bindable<u32>>()
+                                                                && let Item::RegularPolygon(ref mut polygon) = sigil.write().layers[idx].item {
+                                                                    polygon.sides = val;
+                                                                }
+                                                        }
+                                                    }
+                                                }
+                                                FillEditor {
+                                                    label: "Color",
+                                                    fill: rp.color.clone(),
+                                                    on_change: move |fill| {
+                                                        if let Item::RegularPolygon(ref mut polygon) = sigil.write().layers[idx].item {
+                                                            polygon.color = fill;
+                                                        }
+                                                    }
+                                                }
+                                                StrokeEditor {
+                                                    stroke: rp.stroke.clone(),
+                                                    on_change: move |stroke| {
+                                                        if let Item::RegularPolygon(ref mut polygon) = sigil.write().layers[idx].item {
+                                                            polygon.stroke = stroke;
+                                                        }
+                                                    }
+                                                }
+                                            },
+                                            Item::Group(g) => rsx! {
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Width: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        value: "{g.width}",
+                                                        oninput: move |evt| {
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Group(ref mut group) = sigil.write().layers[idx].item {
+                                                                    group.width = val;
+                                                                }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Height: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        value: "{g.height}",
+                                                        oninput: move |evt| {
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Group(ref mut group) = sigil.write().layers[idx].item {
+                                                                    group.height = val;
+                                                                }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group checkbox-group",
+                                                    label {
+                                                        input {
+                                                            r#type: "checkbox",
+                                                            checked: *g.clip,
+                                                            onchange: move |evt| {
+                                                                if let Item::Group(ref mut group) = sigil.write().layers[idx].item {
+                                                                    group.clip = evt.checked().into();
+                                                                }
+                                                            }
+                                                        }
+                                                        " Clip Content"
+                                                    }
+                                                }
+                                                div { class: "empty-state", style: "color: #888;", "{g.children.len()} layers. Ungroup with Ctrl+Shift+G to edit them." }
+                                            },
+                                            Item::Repeat(r) => rsx! {
+                                                div {
+                                                    class: "control-group",
+                                                    label { "List Variable: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        value: "{r.source}",
+                                                        oninput: move |evt| {
+                                                            if let Item::Repeat(ref mut repeat) = sigil.write().layers[idx].item {
+                                                                repeat.source = evt.value();
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Cell Width: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        value: "{r.width}",
+                                                        oninput: move |evt| {
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Repeat(ref mut repeat) = sigil.write().layers[idx].item {
+                                                                    repeat.width = val;
+                                                                }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Cell Height: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        value: "{r.height}",
+                                                        oninput: move |evt| {
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Repeat(ref mut repeat) = sigil.write().layers[idx].item {
+                                                                    repeat.height = val;
+                                                                }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Direction: " }
+                                                    select {
+                                                        value: "{r.direction.name()}",
+                                                        oninput: move |evt| {
+                                                            if let Some(direction) = RepeatDirection::ALL.into_iter().find(|direction| direction.name() == evt.value())
+                                                                && let Item::Repeat(ref mut repeat) = sigil.write().layers[idx].item {
+                                                                    repeat.direction = direction;
+                                                                }
+                                                        },
+                                                        for direction in RepeatDirection::ALL {
+                                                            option { value: "{direction.name()}", "{direction.name()}" }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Gap: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        value: "{r.gap}",
+                                                        oninput: move |evt| {
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Repeat(ref mut repeat) = sigil.write().layers[idx].item {
+                                                                    repeat.gap = val;
+                                                                }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Max Items: " }
+                                                    input {
+                                                        r#type: "number",
+                                                        min: "0",
+                                                        placeholder: "all",
+                                                        value: "{r.max_items.map(|max| max.to_string()).unwrap_or_default()}",
+                                                        oninput: move |evt| {
+                                                            if let Item::Repeat(ref mut repeat) = sigil.write().layers[idx].item {
+                                                                repeat.max_items = evt.value().parse().ok();
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                                div { class: "empty-state", style: "color: #888;", "{r.template.len()} template layers, drawn for each entry of the list. Preview entries as a JSON array in the preview values." }
+                                            },
+                                            Item::Path(p) => {
+                                                let view_box = p.view_box.map(|vb| format_numbers(&vb)).unwrap_or_default();
+                                                let fill_rule = match p.fill_rule {
+                                                    FillRule::Nonzero => "nonzero",
+                                                    FillRule::EvenOdd => "evenodd",
+                                                };
+                                                let path_error = p.segments().err().map(|err| err.to_string());
+                                                rsx! {
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "Path Data: " }
+                                                        textarea {
+                                                            value: "{p.d}",
+                                                            oninput: move |evt| {
+                                                                if let Item::Path(ref mut path) = sigil.write().layers[idx].item {
+                                                                    path.d = evt.value();
+                                                                }
+                                                            }
+                                                        }
+                                                        if let Some(error) = path_error {
+                                                            div { class: "error-text", "{error}" }
+                                                        }
+                                                    }
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "Width: " }
+                                                        input {
+                                                            r#type: "text",
+                                                            value: "{p.width}",
+                                                            oninput: move |evt| {
+                                                                if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                    && let Item::Path(ref mut path) = sigil.write().layers[idx].item {
+                                                                        path.width = val;
+                                                                    }
+                                                            }
+                                                        }
+                                                    }
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "Height: " }
+                                                        input {
+                                                            r#type: "text",
+                                                            value: "{p.height}",
+                                                            oninput: move |evt| {
+                                                                if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                    && let Item::Path(ref mut path) = sigil.write().layers[idx].item {
+                                                                        path.height = val;
+                                                                    }
+                                                            }
+                                                        }
+                                                    }
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "View Box: " }
+                                                        input {
+                                                            r#type: "text",
+                                                            placeholder: "min-x min-y width height",
+                                                            value: "{view_box}",
+                                                            oninput: move |evt| {
+                                                                if let Item::Path(ref mut path) = sigil.write().layers[idx].item {
+                                                                    path.view_box = parse_numbers(&evt.value()).try_into().ok();
+                                                                }
+                                                            }
+                                                        }
+                                                    }
+                                                    div {
+                                                        class: "control-group checkbox-group",
+                                                        label {
+                                                            input {
+                                                                r#type: "checkbox",
+                                                                checked: p.fill.is_some(),
+                                                                onchange: move |evt| {
+                                                                    if let Item::Path(ref mut path) = sigil.write().layers[idx].item {
+                                                                        path.fill = evt.checked().then(|| "#cccccc".into());
+                                                                    }
+                                                                }
+                                                            }
+                                                            " Fill"
+                                                        }
+                                                    }
+                                                    if let Some(fill) = p.fill.clone() {
+                                                        FillEditor {
+                                                            label: "Color",
+                                                            fill,
+                                                            on_change: move |fill| {
+                                                                if let Item::Path(ref mut path) = sigil.write().layers[idx].item {
+                                                                    path.fill = Some(fill);
+                                                                }
+                                                            }
+                                                        }
+                                                        div {
+                                                            class: "control-group",
+                                                            label { "Fill Rule: " }
+                                                            select {
+                                                                value: "{fill_rule}",
+                                                                oninput: move |evt| {
+                                                                    if let Item::Path(ref mut path) = sigil.write().layers[idx].item {
+                                                                        path.fill_rule = match evt.value().as_str() {
+                                                                            "evenodd" => FillRule::EvenOdd,
+                                                                            _ => FillRule::Nonzero,
+                                                                        };
+                                                                    }
+                                                                },
+                                                                option { value: "nonzero", "Nonzero" }
+                                                                option { value: "evenodd", "Even-Odd" }
+                                                            }
+                                                        }
+                                                    }
+                                                    StrokeEditor {
+                                                        stroke: p.stroke.clone(),
+                                                        on_change: move |stroke| {
+                                                            if let Item::Path(ref mut path) = sigil.write().layers[idx].item {
+                                                                path.stroke = stroke;
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                            },
+                                            Item::Text(t) => rsx! {
+                                                if t.spans.is_empty() {
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "Text: " }
+                                                        input {
+                                                            r#type: "text",
+                                                            value: "{t.text}",
+                                                            oninput: move |evt| {
+                                                                if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                    text.text = evt.value();
+                                                                }
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                                for (span_idx, span) in t.spans.iter().cloned().enumerate() {
+                                                    div {
+                                                        class: "span-editor",
+                                                        div {
+                                                            class: "control-group",
+                                                            label { "Span {span_idx + 1}: " }
+                                                            input {
+                                                                r#type: "text",
+                                                                value: "{span.text}",
+                                                                oninput: move |evt| {
+                                                                    if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                        text.spans[span_idx].text = evt.value();
+                                                                    }
+                                                                }
+                                                            }
+                                                        }
+                                                        div {
+                                                            class: "control-group",
+                                                            label { "Span Color: " }
+                                                            input {
+                                                                r#type: "color",
+                                                                value: "{color_input_value(span.color.as_deref().unwrap_or(t.color.primary_color()))}",
+                                                                oninput: move |evt| {
+                                                                    if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                        text.spans[span_idx].color = Some(evt.value());
+                                                                    }
+                                                                }
+                                                            }
+                                                        }
+                                                        div {
+                                                            class: "control-group",
+                                                            label { "Span Weight: " }
+                                                            select {
+                                                                value: "{span.font_weight.as_ref().map(|w| w.to_string()).unwrap_or_default()}",
+                                                                oninput: move |evt| {
+                                                                    if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                        text.spans[span_idx].font_weight = evt.value().parse::<u16>().ok().map(Bindable::new);
+                                                                    }
+                                                                },
+                                                                option { value: "", "Inherit" }
+                                                                option { value: "300", "Light" }
+                                                                option { value: "400", "Regular" }
+                                                                option { value: "700", "Bold" }
+                                                                option { value: "900", "Black" }
+                                                            }
+                                                        }
+                                                        div {
+                                                            class: "control-group",
+                                                            label { "Span Size: " }
+                                                            input {
+                                                                r#type: "text",
+                                                                placeholder: "inherit",
+                                                                value: "{span.font_size.as_ref().map(|v| v.to_string()).unwrap_or_default()}",
+                                                                oninput: move |evt| {
+                                                                    if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                        text.spans[span_idx].font_size = parse_positive(&evt.value());
+                                                                    }
+                                                                }
+                                                            }
+                                                        }
+                                                        button {
+                                                            class: "action-btn danger",
+                                                            onclick: move |_| {
+                                                                if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                    text.spans.remove(span_idx);
+                                                                }
+                                                            },
+                                                            "Remove Span"
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    button {
+                                                        class: "action-btn",
+                                                        onclick: move |_| {
+                                                            if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                // The plain text becomes the first span so nothing disappears from the canvas
+                                                                if text.spans.is_empty() {
+                                                                    text.spans.push(TextSpan { text: text.text.clone(), ..Default::default() });
+                                                                }
+                                                                text.spans.push(TextSpan::default());
+                                                            }
+                                                        },
+                                                        "Add Span"
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Font Size: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        value: "{t.font_size}",
+                                                        oninput: move |evt| {
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                    text.font_size = val;
+                                                                }
+                                                        }
+                                                    }
+                                                }
+                                                FillEditor {
+                                                    label: "Color",
+                                                    fill: t.color.clone(),
+                                                    on_change: move |fill| {
+                                                        if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                            text.color = fill;
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Font Family: " }
+                                                    select {
+                                                        value: "{t.font_family}",
+                                                        oninput: move |evt| {
+                                                            if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                text.font_family = evt.value();
+                                                            }
+                                                        },
+                                                        option { value: "Sans Serif", style: "font-family: sans-serif;", "Sans Serif" }
+                                                        option { value: "Serif", style: "font-family: serif;", "Serif" }
+                                                        option { value: "Monospace", style: "font-family: monospace;", "Monospace" }
+                                                        option { value: "Cursive", style: "font-family: cursive;", "Cursive" }
+                                                        option { value: "Fantasy", style: "font-family: fantasy;", "Fantasy" }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Font Weight: " }
+                                                    select {
+                                                        value: "{t.font_weight.as_deref().copied().unwrap_or(DEFAULT_FONT_WEIGHT)}",
+                                                        oninput: move |evt| {
+                                                            if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                text.font_weight = evt.value().parse::<u16>().ok().filter(|w| *w != DEFAULT_FONT_WEIGHT).map(Bindable::new);
+                                                            }
+                                                        },
+                                                        option { value: "100", "Thin" }
+                                                        option { value: "300", "Light" }
+                                                        option { value: "400", "Regular" }
+                                                        option { value: "500", "Medium" }
+                                                        option { value: "600", "Semibold" }
+                                                        option { value: "700", "Bold" }
+                                                        option { value: "900", "Black" }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Font Style: " }
+                                                    select {
+                                                        value: "{font_style_name(t.font_style)}",
+                                                        oninput: move |evt| {
+                                                            if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                text.font_style = match evt.value().as_str() {
+                                                                    "italic" => FontStyle::Italic,
+                                                                    "oblique" => FontStyle::Oblique,
+                                                                    _ => FontStyle::Normal,
+                                                                };
+                                                            }
+                                                        },
+                                                        option { value: "normal", "Normal" }
+                                                        option { value: "italic", "Italic" }
+                                                        option { value: "oblique", "Oblique" }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Letter Spacing: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        value: "{t.letter_spacing}",
+                                                        oninput: move |evt| {
+                                                            if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                && let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                    text.letter_spacing = val;
+                                                                }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Transform: " }
+                                                    select {
+                                                        value: "{text_transform_name(t.text_transform)}",
+                                                        oninput: move |evt| {
+                                                            if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                text.text_transform = match evt.value().as_str() {
+                                                                    "uppercase" => TextTransform::Uppercase,
+                                                                    "lowercase" => TextTransform::Lowercase,
+                                                                    "capitalize" => TextTransform::Capitalize,
+                                                                    _ => TextTransform::None,
+                                                                };
+                                                            }
+                                                        },
+                                                        option { value: "none", "None" }
+                                                        option { value: "uppercase", "Uppercase" }
+                                                        option { value: "lowercase", "Lowercase" }
+                                                        option { value: "capitalize", "Capitalize" }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group checkbox-group",
+                                                    label {
+                                                        input {
+                                                            r#type: "checkbox",
+                                                            checked: *t.underline,
+                                                            onchange: move |evt| {
+                                                                if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                    text.underline = evt.checked().into();
+                                                                }
+                                                            }
+                                                        }
+                                                        " Underline"
+                                                    }
+                                                    label {
+                                                        input {
+                                                            r#type: "checkbox",
+                                                            checked: *t.strikethrough,
+                                                            onchange: move |evt| {
+                                                                if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                    text.strikethrough = evt.checked().into();
+                                                                }
+                                                            }
+                                                        }
+                                                        " Strikethrough"
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Stroke Width: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        placeholder: "none",
+                                                        value: "{t.stroke.as_ref().map(|s| s.width.to_string()).unwrap_or_default()}",
+                                                        oninput: move |evt| {
+                                                            if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                text.stroke = parse_positive(&evt.value()).map(|width| TextStroke {
+                                                                    color: text.stroke.as_ref().map_or_else(|| "#000000".to_string(), |s| s.color.clone()),
+                                                                    width,
+                                                                });
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                                if let Some(stroke) = t.stroke.clone() {
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "Stroke Color: " }
+                                                        input {
+                                                            r#type: "color",
+                                                            value: "{color_input_value(&stroke.color)}",
+                                                            oninput: move |evt| {
+                                                                if let Item::Text(ref mut text) = sigil.write().layers[idx].item
+                                                                    && let Some(stroke) = text.stroke.as_mut() {
+                                                                        stroke.color = evt.value();
+                                                                    }
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group checkbox-group",
+                                                    label {
+                                                        input {
+                                                            r#type: "checkbox",
+                                                            checked: t.shadow.is_some(),
+                                                            onchange: move |evt| {
+                                                                if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                    text.shadow = evt.checked().then(|| TextShadow {
+                                                                        color: "#000000".to_string(),
+                                                                        offset_x: 2.0.into(),
+                                                                        offset_y: 2.0.into(),
+                                                                        blur: 4.0.into(),
+                                                                    });
+                                                                }
+                                                            }
+                                                        }
+                                                        " Shadow"
+                                                    }
+                                                }
+                                                if let Some(shadow) = t.shadow.clone() {
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "Shadow X: " }
+                                                        input {
+                                                            r#type: "text",
+                                                            value: "{shadow.offset_x}",
+                                                            oninput: move |evt| {
+                                                                if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                    && let Item::Text(ref mut text) = sigil.write().layers[idx].item
+                                                                    && let Some(shadow) = text.shadow.as_mut() {
+                                                                        shadow.offset_x = val;
+                                                                    }
+                                                            }
+                                                        }
+                                                    }
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "Shadow Y: " }
+                                                        input {
+                                                            r#type: "text",
+                                                            value: "{shadow.offset_y}",
+                                                            oninput: move |evt| {
+                                                                if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                    && let Item::Text(ref mut text) = sigil.write().layers[idx].item
+                                                                    && let Some(shadow) = text.shadow.as_mut() {
+                                                                        shadow.offset_y = val;
+                                                                    }
+                                                            }
+                                                        }
+                                                    }
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "Shadow Blur: " }
+                                                        input {
+                                                            r#type: "text",
+                                                            value: "{shadow.blur}",
+                                                            oninput: move |evt| {
+                                                                if let Ok(val) = evt.value().parse::<Bindable<f32>>()
+                                                                    && let Item::Text(ref mut text) = sigil.write().layers[idx].item
+                                                                    && let Some(shadow) = text.shadow.as_mut() {
+                                                                        shadow.blur = if val.binding().is_some() { val } else { val.max(0.0).into() };
+                                                                    }
+                                                            }
+                                                        }
+                                                    }
+                                                    div {
+                                                        class: "control-group",
+                                                        label { "Shadow Color: " }
+                                                        input {
+                                                            r#type: "color",
+                                                            value: "{color_input_value(&shadow.color)}",
+                                                            oninput: move |evt| {
+                                                                if let Item::Text(ref mut text) = sigil.write().layers[idx].item
+                                                                    && let Some(shadow) = text.shadow.as_mut() {
+                                                                        shadow.color = evt.value();
+                                                                    }
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Max Width: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        placeholder: "auto",
+                                                        value: "{t.max_width.as_ref().map(|v| v.to_string()).unwrap_or_default()}",
+                                                        oninput: move |evt| {
+                                                            if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                text.max_width = parse_positive(&evt.value());
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Max Height: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        placeholder: "auto",
+                                                        value: "{t.max_height.as_ref().map(|v| v.to_string()).unwrap_or_default()}",
+                                                        oninput: move |evt| {
+                                                            if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                text.max_height = parse_positive(&evt.value());
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Max Lines: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        placeholder: "unlimited",
+                                                        value: "{t.max_lines.as_ref().map(|v| v.to_string()).unwrap_or_default()}",
+                                                        oninput: move |evt| {
+                                                            if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                text.max_lines = parse_positive(&evt.value());
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Line Height: " }
+                                                    input {
+                                                        r#type: "text",
+                                                        placeholder: "1.2",
+                                                        value: "{t.line_height.as_ref().map(|v| v.to_string()).unwrap_or_default()}",
+                                                        oninput: move |evt| {
+                                                            if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                text.line_height = parse_positive(&evt.value());
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Align: " }
+                                                    select {
+                                                        value: "{text_align_name(t.align)}",
+                                                        oninput: move |evt| {
+                                                            if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                text.align = match evt.value().as_str() {
+                                                                    "center" => TextAlign::Center,
+                                                                    "right" => TextAlign::Right,
+                                                                    "justify" => TextAlign::Justify,
+                                                                    _ => TextAlign::Left,
+                                                                };
+                                                            }
+                                                        },
+                                                        option { value: "left", "Left" }
+                                                        option { value: "center", "Center" }
+                                                        option { value: "right", "Right" }
+                                                        option { value: "justify", "Justify" }
+                                                    }
+                                                }
+                                                div {
+                                                    class: "control-group",
+                                                    label { "Vertical Align: " }
+                                                    select {
+                                                        value: "{vertical_align_name(t.vertical_align)}",
+                                                        oninput: move |evt| {
+                                                            if let Item::Text(ref mut text) = sigil.write().layers[idx].item {
+                                                                text.vertical_align = match evt.value().as_str() {
+                                                                    "middle" => VerticalAlign::Middle,
+                                                                    "bottom" => VerticalAlign::Bottom,
+                                                                    _ => VerticalAlign::Top,
+                                                                };
+                                                            }
+                                                        },
+                                                        option { value: "top", "Top" }
+                                                        option { value: "middle", "Middle" }
+                                                        option { value: "bottom", "Bottom" }
+                                                    }
+                                                }
+                                            }
+                                        };
+
+                                        rsx! {
+                                            div {
+                                                class: "control-group",
+                                                label { "X: " }
+                                                input {
+                                                    r#type: "text",
+                                                    value: "{layer.x}",
+                                                    oninput: move |evt| {
+                                                        if let Ok(val) = evt.value().parse::<Bindable<f32>>() {
+                                                            sigil.write().layers[idx].x = val;
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                            div {
+                                                class: "control-group",
+                                                label { "Y: " }
+                                                input {
+                                                    r#type: "text",
+                                                    value: "{layer.y}",
+                                                    oninput: move |evt| {
+                                                        if let Ok(val) = evt.value().parse::<Bindable<f32>>() {
+                                                            sigil.write().layers[idx].y = val;
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                            div {
+                                                class: "control-group",
+                                                label { "Rotation: " }
+                                                input {
+                                                    r#type: "text",
+                                                    value: "{layer.rotation}",
+                                                    oninput: move |evt| {
+                                                        if let Ok(val) = evt.value().parse::<Bindable<f32>>() {
+                                                            sigil.write().layers[idx].rotation = val;
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                            div {
+                                                class: "control-group",
+                                                label { "Opacity: " }
+                                                input {
+                                                    r#type: "number",
+                                                    min: "0",
+                                                    max: "1",
+                                                    step: "0.05",
+                                                    value: "{layer.opacity}",
+                                                    oninput: move |evt| {
+                                                        if let Ok(val) = evt.value().parse::<f32>() {
+                                                            sigil.write().layers[idx].opacity = val.clamp(0.0, 1.0).into();
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                            div {
+                                                class: "control-group",
+                                                label { "Condition: " }
+                                                input {
+                                                    r#type: "text",
+                                                    placeholder: "always shown",
+                                                    value: "{layer.condition.clone().unwrap_or_default()}",
+                                                    oninput: move |evt| {
+                                                        let condition = evt.value();
+                                                        sigil.write().layers[idx].condition = (!condition.trim().is_empty()).then_some(condition);
+                                                    }
+                                                }
+                                            }
+                                            div {
+                                                class: "control-group",
+                                                label { "Blend Mode: " }
+                                                select {
+                                                    value: "{layer.blend_mode.css_name()}",
+                                                    oninput: move |evt| {
+                                                        if let Some(mode) = BlendMode::from_css_name(&evt.value()) {
+                                                            sigil.write().layers[idx].blend_mode = mode;
+                                                        }
+                                                    },
+                                                    for mode in BlendMode::ALL {
+                                                        option { value: "{mode.css_name()}", "{mode.css_name()}" }
+                                                    }
+                                                }
+                                            }
+                                            EffectsEditor {
+                                                effects: layer.effects.clone(),
+                                                on_change: move |effects| sigil.write().layers[idx].effects = effects,
+                                            }
+                                            MaskEditor {
+                                                mask: layer.mask.clone(),
+                                                layer_ids: sigil.read().layers.iter().filter(|l| l.id != layer.id).map(|l| l.id.clone()).collect::<Vec<_>>(),
+                                                bounds: {
+                                                    let (w, h) = layer_size(layer, &text_dimensions.read());
+                                                    [*layer.x, *layer.y, w, h]
+                                                },
+                                                on_change: move |mask| sigil.write().layers[idx].mask = mask,
+                                            }
+                                            
+                                            {properties}
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                h3 { "Layers" }
+
+                div {
+                    class: "add-layer-controls",
+                    select {
+                        value: "{add_layer_type}",
+                        oninput: move |evt| add_layer_type.set(evt.value()),
+                        option { value: "Rectangle", "Rectangle" }
+                        option { value: "Text", "Text" }
+                        option { value: "Image", "Image" }
+                        option { value: "Ellipse", "Ellipse" }
+                        option { value: "Line", "Line" }
+                        option { value: "Polygon", "Polygon" }
+                        option { value: "Star", "Star" }
+                        option { value: "Regular Polygon", "Regular Polygon" }
+                        option { value: "Path", "Path" }
+                        option { value: "Repeat", "Repeat" }
+                    }
+                    button {
+                        class: "primary-btn",
+                        onclick: move |_| {
+                            let current_id = *layer_id_counter.read();
+                            layer_id_counter.set(current_id + 1);
                             
                             let layer_type = add_layer_type.read().clone();
                             let new_layer = match layer_type.as_str() {
                                 "Rectangle" => Layer {
                                     id: format!("rect_{}", current_id),
-                                    x: 50.0, y: 50.0, rotation: 0.0,
-                                    visible: true,
-                                    item: Item::Rect(RectItem { width: 100.0, height: 100.0, color: "#cccccc".to_string(), border_radius: 0.0 })
+                                    x: 50.0.into(), y: 50.0.into(), rotation: 0.0.into(),
+                                    visible: true.into(),
+                                    opacity: 1.0.into(),
+                                    blend_mode: BlendMode::Normal,
+                                    effects: Vec::new(),
+                                    mask: None,
+                                    condition: None,
+                                    item: Item::Rect(RectItem { width: 100.0.into(), height: 100.0.into(), color: "#cccccc".into(), border_radius: 0.0.into(), stroke: None })
                                 },
                                 "Text" => Layer {
                                     id: format!("text_{}", current_id),
-                                    x: 50.0, y: 50.0, rotation: 0.0,
-                                    visible: true,
-                                    item: Item::Text(TextItem { text: "New Text".to_string(), font_size: 24.0, color: "#ffffff".to_string(), font_family: "Sans Serif".to_string() })
+                                    x: 50.0.into(), y: 50.0.into(), rotation: 0.0.into(),
+                                    visible: true.into(),
+                                    opacity: 1.0.into(),
+                                    blend_mode: BlendMode::Normal,
+                                    effects: Vec::new(),
+                                    mask: None,
+                                    condition: None,
+                                    item: Item::Text(TextItem { text: "New Text".to_string(), font_size: 24.0.into(), color: "#ffffff".into(), font_family: "Sans Serif".to_string(), ..Default::default() })
                                 },
                                 "Image" => Layer {
                                     id: format!("img_{}", current_id),
-                                    x: 50.0, y: 50.0, rotation: 0.0,
-                                    visible: true,
-                                    item: Item::Image(ImageItem { width: 100.0, height: 100.0, source: "".to_string(), border_radius: 0.0 })
+                                    x: 50.0.into(), y: 50.0.into(), rotation: 0.0.into(),
+                                    visible: true.into(),
+                                    opacity: 1.0.into(),
+                                    blend_mode: BlendMode::Normal,
+                                    effects: Vec::new(),
+                                    mask: None,
+                                    condition: None,
+                                    item: Item::Image(ImageItem { width: 100.0.into(), height: 100.0.into(), source: "".to_string(), border_radius: 0.0.into(), stroke: None, fit: ImageFit::Cover, focal_point: [0.5.into(), 0.5.into()], crop: None, filters: Vec::new() })
+                                },
+                                "Ellipse" => Layer {
+                                    id: format!("ellipse_{}", current_id),
+                                    x: 50.0.into(), y: 50.0.into(), rotation: 0.0.into(),
+                                    visible: true.into(),
+                                    opacity: 1.0.into(),
+                                    blend_mode: BlendMode::Normal,
+                                    effects: Vec::new(),
+                                    mask: None,
+                                    condition: None,
+                                    item: Item::Ellipse(EllipseItem { width: 100.0.into(), height: 100.0.into(), color: "#cccccc".into(), stroke: None })
+                                },
+                                "Line" => Layer {
+                                    id: format!("line_{}", current_id),
+                                    x: 50.0.into(), y: 50.0.into(), rotation: 0.0.into(),
+                                    visible: true.into(),
+                                    opacity: 1.0.into(),
+                                    blend_mode: BlendMode::Normal,
+                                    effects: Vec::new(),
+                                    mask: None,
+                                    condition: None,
+                                    item: Item::Line(LineItem { x1: 0.0.into(), y1: 0.0.into(), x2: 100.0.into(), y2: 0.0.into(), color: "#cccccc".to_string(), stroke_width: 4.0.into(), cap: LineCap::Butt, dash: Vec::new() })
+                                },
+                                "Polygon" => Layer {
+                                    id: format!("polygon_{}", current_id),
+                                    x: 50.0.into(), y: 50.0.into(), rotation: 0.0.into(),
+                                    visible: true.into(),
+                                    opacity: 1.0.into(),
+                                    blend_mode: BlendMode::Normal,
+                                    effects: Vec::new(),
+                                    mask: None,
+                                    condition: None,
+                                    item: Item::Polygon(PolygonItem { points: vec![[50.0, 0.0], [100.0, 100.0], [0.0, 100.0]], color: "#cccccc".into(), stroke: None })
+                                },
+                                "Star" => Layer {
+                                    id: format!("star_{}", current_id),
+                                    x: 50.0.into(), y: 50.0.into(), rotation: 0.0.into(),
+                                    visible: true.into(),
+                                    opacity: 1.0.into(),
+                                    blend_mode: BlendMode::Normal,
+                                    effects: Vec::new(),
+                                    mask: None,
+                                    condition: None,
+                                    item: Item::Star(StarItem { width: 100.0.into(), height: 100.0.into(), points: 5.into(), inner_ratio: 0.5.into(), color: "#cccccc".into(), stroke: None })
+                                },
+                                "Regular Polygon" => Layer {
+                                    id: format!("polygon_{}", current_id),
+                                    x: 50.0.into(), y: 50.0.into(), rotation: 0.0.into(),
+                                    visible: true.into(),
+                                    opacity: 1.0.into(),
+                                    blend_mode: BlendMode::Normal,
+                                    effects: Vec::new(),
+                                    mask: None,
+                                    condition: None,
+                                    item: Item::RegularPolygon(RegularPolygonItem { width: 100.0.into(), height: 100.0.into(), sides: 6.into(), color: "#cccccc".into(), stroke: None })
+                                },
+                                "Path" => Layer {
+                                    id: format!("path_{}", current_id),
+                                    x: 50.0.into(), y: 50.0.into(), rotation: 0.0.into(),
+                                    visible: true.into(),
+                                    opacity: 1.0.into(),
+                                    blend_mode: BlendMode::Normal,
+                                    effects: Vec::new(),
+                                    mask: None,
+                                    condition: None,
+                                    item: Item::Path(PathItem {
+                                        d: "M12 21.35l-1.45-1.32C5.4 15.36 2 12.28 2 8.5 2 5.42 4.42 3 7.5 3c1.74 0 3.41.81 4.5 2.09C13.09 3.81 14.76 3 16.5 3 19.58 3 22 5.42 22 8.5c0 3.78-3.4 6.86-8.55 11.54L12 21.35z".to_string(),
+                                        width: 100.0.into(),
+                                        height: 100.0.into(),
+                                        view_box: Some([0.0, 0.0, 24.0, 24.0]),
+                                        fill: Some("#cccccc".into()),
+                                        fill_rule: FillRule::Nonzero,
+                                        stroke: None,
+                                    })
+                                },
+                                "Repeat" => Layer {
+                                    id: format!("repeat_{}", current_id),
+                                    x: 50.0.into(), y: 50.0.into(), rotation: 0.0.into(),
+                                    visible: true.into(),
+                                    opacity: 1.0.into(),
+                                    blend_mode: BlendMode::Normal,
+                                    effects: Vec::new(),
+                                    mask: None,
+                                    condition: None,
+                                    item: Item::Repeat(RepeatItem {
+                                        source: "items".to_string(),
+                                        width: 200.0.into(),
+                                        height: 40.0.into(),
+                                        direction: RepeatDirection::Vertical,
+                                        gap: 8.0.into(),
+                                        max_items: None,
+                                        template: vec![
+                                            Layer {
+                                                id: "row".to_string(),
+                                                x: 0.0.into(), y: 0.0.into(), rotation: 0.0.into(),
+                                                visible: true.into(),
+                                                opacity: 1.0.into(),
+                                                blend_mode: BlendMode::Normal,
+                                                effects: Vec::new(),
+                                                mask: None,
+                                                condition: None,
+                                                item: Item::Rect(RectItem { width: 200.0.into(), height: 40.0.into(), color: "#333333".into(), border_radius: 4.0.into(), stroke: None })
+                                            },
+                                            Layer {
+                                                id: "label".to_string(),
+                                                x: 8.0.into(), y: 8.0.into(), rotation: 0.0.into(),
+                                                visible: true.into(),
+                                                opacity: 1.0.into(),
+                                                blend_mode: BlendMode::Normal,
+                                                effects: Vec::new(),
+                                                mask: None,
+                                                condition: None,
+                                                item: Item::Text(TextItem { text: "{index + 1}. {item.name}".to_string(), font_size: 18.0.into(), color: "#ffffff".into(), font_family: "Sans Serif".to_string(), ..Default::default() })
+                                            },
+                                        ],
+                                    })
+                                },
+                                _ => return,
+                            };
+                            sigil.write().layers.push(new_layer);
+
+                            let new_idx = sigil.read().layers.len() - 1;
+                            selected_layers.write().clear();
+                            selected_layers.write().insert(new_idx);
+                        },
+                        "Add"
+                    }
+                }
+
+                div {
+                    class: "layer-actions",
+                    button { 
+                        class: "action-btn", 
+                        title: "Move Up",
+                        disabled: selected_layers.read().len() != 1,
+                        onclick: move |_| {
+                            let idx_opt = selected_layers.read().iter().next().cloned();
+                            if let Some(idx) = idx_opt
+                                && idx < sigil.read().layers.len() - 1 {
+                                    sigil.write().layers.swap(idx, idx + 1);
+                                    selected_layers.write().clear();
+                                    selected_layers.write().insert(idx + 1);
+                                }
+                        },
+                        "Up" 
+                    }
+                    button { 
+                        class: "action-btn", 
+                        title: "Move Down",
+                        disabled: selected_layers.read().len() != 1,
+                        onclick: move |_| {
+                            let idx_opt = selected_layers.read().iter().next().cloned();
+                            if let Some(idx) = idx_opt
+                                && idx > 0 {
+                                    sigil.write().layers.swap(idx, idx - 1);
+                                    selected_layers.write().clear();
+                                    selected_layers.write().insert(idx - 1);
+                                }
+                        },
+                        "Down" 
+                    }
+                    button {
+                        class: "action-btn",
+                        title: "Group (Ctrl+G)",
+                        disabled: selected_layers.read().is_empty(),
+                        onclick: move |_| {
+                            let current_id = *layer_id_counter.read() + 1;
+                            let indices = selected_layers.read().clone();
+                            if let Some(group_idx) = group_layers(&mut sigil.write().layers, &indices, format!("group_{}", current_id), &text_dimensions.read()) {
+                                layer_id_counter.set(current_id);
+                                selected_layers.write().clear();
+                                selected_layers.write().insert(group_idx);
+                            }
+                        },
+                        "Group"
+                    }
+                    button {
+                        class: "action-btn",
+                        title: "Ungroup (Ctrl+Shift+G)",
+                        disabled: {
+                            let layers = &sigil.read().layers;
+                            selected_layers.read().len() != 1
+                                || !selected_layers.read().iter().all(|&idx| matches!(layers.get(idx).map(|l| &l.item), Some(Item::Group(_))))
+                        },
+                        onclick: move |_| {
+                            let idx_opt = selected_layers.read().iter().next().cloned();
+                            if let Some(idx) = idx_opt {
+                                let children = ungroup_layer(&mut sigil.write().layers, idx, &text_dimensions.read());
+                                selected_layers.set(children.into_iter().collect());
+                            }
+                        },
+                        "Ungroup"
+                    }
+                    button { 
+                        class: "action-btn danger", 
+                        title: "Delete Layer",
+                        disabled: selected_layers.read().is_empty(),
+                        onclick: move |_| {
+                            let to_remove: Vec<usize> = selected_layers.read().iter().cloned().collect();
+                            if !to_remove.is_empty() {
+                                let mut sorted = to_remove;
+                                sorted.sort_by(|a, b| b.cmp(a));
+                                for idx in sorted {
+                                    sigil.write().layers.remove(idx);
+                                }
+                                selected_layers.write().clear();
+                            }
+                        },
+                        "Del" 
+                    }
+                }
+
+                div {
+                    class: "layers-list",
+                    onmouseleave: move |_| {
+                        drag_over_state.set(None);
+                    },
+                    for (idx, layer) in sigil.read().layers.iter().enumerate() {
+                        div {
+                            key: "{layer.id}",
+                            class: if selected_layers.read().contains(&idx) { "layer-item selected" } else { "layer-item" },
+                            style: if masked_by(&sigil.read().layers, layer).is_some() { "margin-left: 16px;" } else { "" },
+                            draggable: true,
+                            ondragstart: move |_| {
+                                dragging_layer_index.set(Some(idx));
+                            },
+                            ondragover: move |evt| {
+                                evt.prevent_default();
+                                let coords = evt.element_coordinates();
+                                let is_top = coords.y < 20.0; 
+                                drag_over_state.set(Some((idx, is_top)));
+                            },
+                            ondrop: move |evt| {
+                                evt.prevent_default();
+                                if let Some(from_idx) = *dragging_layer_index.read()
+                                    && from_idx != idx {
+                                        let mut s = sigil.write();
+                                        if from_idx < s.layers.len() {
+                                            let item = s.layers.remove(from_idx);
+                                            let is_top = (*drag_over_state.read()).map(|(_, top)| top).unwrap_or(true);
+                                            let mut target_idx = idx;
+                                            if from_idx < idx {
+                                                target_idx -= 1;
+                                            }
+                                            
+                                            if !is_top {
+                                                target_idx += 1;
+                                            }
+                                            
+                                            if target_idx <= s.layers.len() {
+                                                s.layers.insert(target_idx, item);
+                                                selected_layers.write().clear();
+                                                selected_layers.write().insert(target_idx);
+                                            }
+                                        }
+                                    }
+                                dragging_layer_index.set(None);
+                                drag_over_state.set(None);
+                            },
+                            
+                            onclick: move |evt| {
+                                let is_ctrl = evt.modifiers().contains(Modifiers::CONTROL) || evt.modifiers().contains(Modifiers::META);
+                                let is_shift = evt.modifiers().contains(Modifiers::SHIFT);
+                                
+                                if is_ctrl || is_shift {
+                                    if selected_layers.read().contains(&idx) {
+                                        selected_layers.write().remove(&idx);
+                                    } else {
+                                        selected_layers.write().insert(idx);
+                                    }
+                                } else {
+                                    selected_layers.write().clear();
+                                    selected_layers.write().insert(idx);
+                                }
+                            },
+
+                            if let Some((over_idx, is_top)) = *drag_over_state.read() {
+                                if over_idx == idx {
+                                    div {
+                                        class: if is_top { "drop-indicator top" } else { "drop-indicator bottom" }
+                                    }
+                                }
+                            }
+                            if matches!(layer.item, Item::Group(_)) {
+                                GroupToggle { id: layer.id.clone(), expanded_groups }
+                            }
+                            div {
+                                class: "layer-info",
+                                div { strong { "{layer.id}" } }
+                                div { "Type: {item_type_name(&layer.item)}" }
+                                if let Some(mask_id) = masked_by(&sigil.read().layers, layer) {
+                                    div { class: "layer-note", "Masked by {mask_id}" }
+                                } else if is_mask_layer(&sigil.read().layers, &layer.id) {
+                                    div { class: "layer-note", "Mask" }
+                                }
+                            }
+                            div {
+                                class: "layer-controls",
+                                button {
+                                    class: "icon-btn",
+                                    onclick: move |evt| {
+                                        evt.stop_propagation();
+                                        let current = *sigil.read().layers[idx].visible;
+                                        sigil.write().layers[idx].visible = (!current).into();
+                                    },
+                                    if *layer.visible { "👁️" } else { "🚫" }
+                                }
+                                button {
+                                    class: "icon-btn",
+                                    onclick: move |evt| {
+                                        evt.stop_propagation();
+                                        if locked_layers.read().contains(&idx) {
+                                            locked_layers.write().remove(&idx);
+                                        } else {
+                                            locked_layers.write().insert(idx);
+                                        }
+                                    },
+                                    if locked_layers.read().contains(&idx) { "🔒" } else { "🔓" }
+                                }
+                            }
+                        }
+                        if let Item::Group(group) = &layer.item
+                            && expanded_groups.read().contains(&layer.id)
+                        {
+                            LayerTreeChildren {
+                                layers: group.children.clone(),
+                                depth: 1,
+                                expanded_groups,
+                                on_select: move |_| {
+                                    selected_layers.write().clear();
+                                    selected_layers.write().insert(idx);
                                 },
-                                _ => return,
-                            };
-                            sigil.write().layers.push(new_layer);
+                            }
+                        }
+                    }
+                }
+            }
 
-                            let new_idx = sigil.read().layers.len() - 1;
-                            selected_layers.write().clear();
-                            selected_layers.write().insert(new_idx);
-                        },
-                        "Add"
+            div {
+                class: "right-panel",
+                onclick: move |_| {
+                    selected_layers.write().clear();
+                },
+                
+                h2 { "Preview (Drag items to move)" }
+
+                div {
+                    class: "canvas-container",
+                    style: "
+                        width: {sigil.read().width}px; 
+                        height: {sigil.read().height}px; 
+                        {background_css(&sigil.read().background)}
+                        cursor: {cursor_style};
+                    ",
+                    onclick: move |_| {
+                        selected_layers.write().clear();
+                    },
+
+                    {background_image("editor-background", &sigil.read().background, sigil.read().width as f32, sigil.read().height as f32)}
+                    
+                    for (idx, layer) in preview_layers(&sigil.read(), &preview_values.read()) {
+                        if *layer.visible {
+                            {
+                                let is_selected = selected_layers.read().contains(&idx);
+                                let is_locked = locked_layers.read().contains(&idx);
+                                let layers = &sigil.read().layers;
+                                // Mask layers are only drawn into their masks in the output; ghost them so they can still be moved
+                                let shown = if is_mask_layer(layers, &layer.id) {
+                                    Layer { opacity: 0.25.into(), ..layer.clone() }
+                                } else {
+                                    layer.clone()
+                                };
+                                let wrapper_style = match layer.mask.as_ref().and_then(|mask| mask_css(mask, layers)) {
+                                    Some(mask) => format!("position: absolute; left: 0; top: 0; width: 100%; height: 100%; pointer-events: none; {}", mask),
+                                    None => "display: contents;".to_string(),
+                                };
+                                rsx!{
+                                    div {
+                                    key: "{layer.id}",
+                                    class: "mask-wrapper",
+                                    style: "{wrapper_style}",
+                                    RenderLayer {
+                                        layer: shown,
+                                        is_selected,
+                                        is_locked,
+                                        text_dimensions: text_dimensions,
+                                        on_move_start: move |evt: MouseEvent| {
+                                        if locked_layers.read().contains(&idx) {
+                                            return;
+                                        }
+
+                                        let is_ctrl = evt.modifiers().contains(Modifiers::CONTROL) || evt.modifiers().contains(Modifiers::META);
+                                        let is_shift = evt.modifiers().contains(Modifiers::SHIFT);
+                                        
+                                        if !selected_layers.read().contains(&idx) {
+                                            if !is_ctrl && !is_shift {
+                                                selected_layers.write().clear();
+                                            }
+                                            selected_layers.write().insert(idx);
+                                        } else if is_ctrl {
+                                        }
+
+                                        let coords = evt.page_coordinates();
+
+                                        let mut original_positions = Vec::new();
+                                        for &sel_idx in selected_layers.read().iter() {
+                                            if let Some(l) = sigil.read().layers.get(sel_idx)
+                                                && !locked_layers.read().contains(&sel_idx) {
+                                                    original_positions.push((sel_idx, *l.x, *l.y));
+                                                }
+                                        }
+
+                                        if !original_positions.is_empty() {
+                                            dragging.set(Some((idx, DragMode::Move {
+                                                start_x: coords.x,
+                                                start_y: coords.y,
+                                                original_positions,
+                                            })));
+                                        }
+                                        evt.stop_propagation();
+                                    }
+                                }
+                                }
+                            }
+                            }
+                        }
+                    }
+
+                    {
+                        let indices: Vec<usize> = selected_layers.read().iter().cloned().collect();
+                        indices.into_iter().map(|idx| {
+                            if let Some(layer) = sigil.read().layers.get(idx) {
+                                let hidden = !*layer.visible || sigil.read().resolve_layer(layer, &preview_values.read()).is_none();
+                                if hidden || locked_layers.read().contains(&idx) {
+                                    return rsx!({});
+                                }
+
+                                let layer_rot = *layer.rotation;
+                                let layer_x = *layer.x;
+                                let layer_y = *layer.y;
+                                rsx! {
+                                    SelectionOverlay {
+                                        key: "overlay_{idx}",
+                                        layer: layer.clone(),
+                                        text_dimensions: text_dimensions,
+                                        on_resize_start: move |(handle, evt): (HandleType, MouseEvent)| {
+                                            if selected_layers.read().len() == 1 {
+                                                let coords = evt.page_coordinates();
+                                                let (w, h) = layer_size(&sigil.read().layers[idx], &text_dimensions.read());
+                                                dragging.set(Some((idx, DragMode::Resize {
+                                                    handle,
+                                                    start_x: coords.x,
+                                                    start_y: coords.y,
+                                                    orig_x: layer_x,
+                                                    orig_y: layer_y,
+                                                    orig_w: w,
+                                                    orig_h: h,
+                                                })));
+                                                evt.stop_propagation();
+                                            }
+                                        },
+                                        on_rotate_start: move |evt: MouseEvent| {
+                                            if selected_layers.read().len() == 1 {
+                                                let coords = evt.page_coordinates();
+                                                let (_w, h) = layer_size(&sigil.read().layers[idx], &text_dimensions.read());
+                                                let rot_rad = sigil.read().layers[idx].rotation.to_radians();
+
+                                                let dist = h as f64 / 2.0 + 30.0;
+                                                let vec_x = dist * (rot_rad.sin() as f64);
+                                                let vec_y = -dist * (rot_rad.cos() as f64);
+                                                
+                                                let center_x = coords.x - vec_x;
+                                                let center_y = coords.y - vec_y;
+                                                
+                                                let start_angle = (coords.y - center_y).atan2(coords.x - center_x);
+
+                                                dragging.set(Some((idx, DragMode::Rotate {
+                                                    orig_rotation: layer_rot,
+                                                    center_x,
+                                                    center_y,
+                                                    start_angle,
+                                                })));
+                                                evt.stop_propagation();
+                                            }
+                                        }
+                                    }
+                                }
+                            } else {
+                                rsx!({})
+                            }
+                        })
+                    }
+
+                    for guide in guides.read().iter() {
+                        if guide.is_vertical {
+                            div {
+                                class: "smart-guide vertical",
+                                style: "left: {guide.pos}px; top: {guide.start}px; height: {guide.end - guide.start}px;"
+                            }
+                        } else {
+                            div {
+                                class: "smart-guide horizontal",
+                                style: "top: {guide.pos}px; left: {guide.start}px; width: {guide.end - guide.start}px;"
+                            }
+                        }
+                    }
+                }
+
+                div {
+                    class: "json-output",
+                    pre {
+                        "{serde_json::to_string_pretty(&*sigil.read()).unwrap()}"
+                    }
+                }
+            }
+        }
+        
+        if *show_load_modal.read() {
+            div { class: "modal-overlay",
+                onclick: move |_| {
+                    show_load_modal.set(false);
+                },
+                div { class: "modal", onclick: move |evt| evt.stop_propagation(),
+                    h3 { "Load JSON" }
+                    textarea {
+                        value: "{load_json_text.read()}",
+                        placeholder: "Paste Sigil JSON here...",
+                        autofocus: "true",
+                        oninput: move |evt| {
+                            load_json_text.set(evt.value());
+                        }
+                    }
+                    if let Some(err) = &*load_error.read() {
+                        div { class: "error-text", "{err}" }
+                    }
+                    IssueList { issues: load_issues() }
+                    div { class: "modal-actions",
+                        button {
+                            class: "primary-btn",
+                            onclick: move |_| {
+                                // Stored templates may be from an older version of the format
+                                let loaded = serde_json::from_str(&load_json_text.read())
+                                    .map_err(|e| format!("Invalid JSON: {}", e))
+                                    .and_then(|json| sigil_core::migrate(json).map_err(|e| e.to_string()));
+                                match loaded {
+                                    Ok(new_sigil) => {
+                                        sigil.set(new_sigil);
+                                        selected_layers.write().clear();
+                                        guides.write().clear();
+                                        text_dimensions.write().clear();
+                                        layer_id_counter.set(sigil.read().layers.len() as i32);
+                                        load_error.set(None);
+                                        show_load_modal.set(false);
+                                    },
+                                    Err(e) => {
+                                        load_error.set(Some(e));
+                                    }
+                                }
+                            },
+                            "Load"
+                        }
+                        button {
+                            onclick: move |_| {
+                                show_load_modal.set(false);
+                                load_error.set(None);
+                            },
+                            "Cancel"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn RenderLayer(
+    layer: Layer, 
+    is_selected: bool, 
+    is_locked: bool,
+    text_dimensions: Signal<HashMap<String, (f32, f32)>>,
+    on_move_start: EventHandler<MouseEvent>,
+) -> Element {
+    let mut text_dims_write = text_dimensions;
+    
+    // The selection outline takes the place of the item's own stroke while selected.
+    // Vector shapes draw their stroke in their SVG instead.
+    let stroke = match &layer.item {
+        Item::Rect(r) => r.stroke.as_ref(),
+        Item::Image(i) => i.stroke.as_ref(),
+        Item::Slider(s) => s.stroke.as_ref(),
+        _ => None,
+    };
+    let outline = match stroke {
+        _ if is_selected => "outline: 2px solid #0055ff;".to_string(),
+        Some(stroke) if *stroke.width > 0.0 => stroke_css(stroke),
+        _ => "outline: none;".to_string(),
+    };
+    let compositing = layer_css(&layer);
+    
+    match &layer.item {
+        Item::Slider(s) => {
+             rsx! {
+                div {
+                    key: "{layer.id}",
+                    style: "position: absolute; left: {layer.x}px; top: {layer.y}px; width: {s.width}px; height: {s.height}px; background-color: {s.background_color}; border-radius: {s.border_radius}px; transform: rotate({layer.rotation}deg); cursor: move; {outline} {compositing}",
+                    onmousedown: move |evt| on_move_start.call(evt),
+                }
+            }
+        },
+        Item::Rect(r) => {
+             rsx! {
+                div {
+                    key: "{layer.id}",
+                    style: "position: absolute; left: {layer.x}px; top: {layer.y}px; width: {r.width}px; height: {r.height}px; background: {css_fill(&r.color)}; border-radius: {r.border_radius}px; transform: rotate({layer.rotation}deg); cursor: move; {outline} {compositing}",
+                    onmousedown: move |evt| on_move_start.call(evt),
+                }
+            }
+        },
+        Item::Image(i) => {
+            let style = format!(
+                "position: absolute; left: {}px; top: {}px; width: {}px; height: {}px; border-radius: {}px; transform: rotate({}deg); cursor: move; {outline} user-select: none; {compositing}",
+                layer.x, layer.y, i.width, i.height, i.border_radius, layer.rotation
+            );
+            rsx! {
+                div {
+                    key: "{layer.id}",
+                    style: "{style} overflow: hidden;",
+                    onmousedown: move |evt| on_move_start.call(evt),
+                    {image_content(&format!("editor-{}", layer.id), i)}
+                }
+            }
+        },
+        Item::Ellipse(_) | Item::Line(_) | Item::Polygon(_) | Item::Star(_) | Item::RegularPolygon(_) | Item::Path(_) => {
+            let (width, height) = layer.item.size().unwrap_or_default();
+            rsx! {
+                div {
+                    key: "{layer.id}",
+                    style: "position: absolute; left: {layer.x}px; top: {layer.y}px; width: {width}px; height: {height}px; transform: rotate({layer.rotation}deg); cursor: move; {outline} {compositing}",
+                    onmousedown: move |evt| on_move_start.call(evt),
+                    {shape_svg(&format!("editor-{}", layer.id), &layer.item)}
+                }
+            }
+        },
+        Item::Group(g) => {
+            let clip = if *g.clip { "overflow: hidden;" } else { "" };
+            rsx! {
+                div {
+                    key: "{layer.id}",
+                    style: "position: absolute; left: {layer.x}px; top: {layer.y}px; width: {g.width}px; height: {g.height}px; transform: rotate({layer.rotation}deg); cursor: move; {clip} {outline} user-select: none; {compositing}",
+                    onmousedown: move |evt| on_move_start.call(evt),
+                    {render_layers(&g.children)}
+                }
+            }
+        },
+        // The canvas draws resolved layers, in which repeaters have become groups
+        Item::Repeat(_) => VNode::empty(),
+        Item::Text(t) => {
+            let box_style = text_box_css(t);
+            let content_style = text_content_css(t);
+            
+            rsx! {
+                div {
+                    key: "{layer.id}",
+                    style: "position: absolute; left: {layer.x}px; top: {layer.y}px; {box_style} transform: rotate({layer.rotation}deg); cursor: move; {outline} user-select: none; {compositing}",
+                    onmousedown: move |evt| on_move_start.call(evt),
+                    onmounted: move |evt| {
+                        let layer_id = layer.id.clone();
+                        async move {
+                            if let Ok(rect) = evt.get_client_rect().await {
+                                let w = rect.width() as f32;
+                                let h = rect.height() as f32;
+                                text_dims_write.write().insert(layer_id, (w, h));
+                            }
+                        }
+                    },
+                    div { style: "{content_style}",
+                        if t.spans.is_empty() {
+                            "{t.text}"
+                        } else {
+                            for span in t.spans.iter() {
+                                span { style: "{text_span_css(span)}", "{span.text}" }
+                            }
+                        }
                     }
                 }
+            }
+        }
+    }
+}
+
+/// Picks a sibling layer or an inline ellipse as the layer's mask, and how it masks. `bounds` is the layer's
+/// `[x, y, width, height]`, which a new inline ellipse fills.
+#[component]
+pub fn MaskEditor(mask: Option<LayerMask>, layer_ids: Vec<String>, bounds: [f32; 4], on_change: EventHandler<Option<LayerMask>>) -> Element {
+    let source = match mask.as_ref().map(|mask| &mask.source) {
+        None => String::new(),
+        Some(MaskSource::Layer { id }) => format!("layer:{}", id),
+        Some(MaskSource::Shape { .. }) => "shape".to_string(),
+    };
+    let mode = mask.as_ref().map_or(MaskMode::Alpha, |mask| mask.mode);
+    let mode_name = match mode {
+        MaskMode::Alpha => "alpha",
+        MaskMode::Luminance => "luminance",
+        MaskMode::Clip => "clip",
+    };
+    let current = mask.clone();
+
+    rsx! {
+        div {
+            class: "control-group",
+            label { "Mask: " }
+            select {
+                value: "{source}",
+                oninput: move |evt| {
+                    let value = evt.value();
+                    let source = if let Some(id) = value.strip_prefix("layer:") {
+                        MaskSource::Layer { id: id.to_string() }
+                    } else if value == "shape" {
+                        // An ellipse over the layer's box; edit it in the JSON to use another shape
+                        let [x, y, width, height] = bounds;
+                        MaskSource::Shape {
+                            x,
+                            y,
+                            rotation: 0.0,
+                            item: Box::new(Item::Ellipse(EllipseItem { width: width.into(), height: height.into(), color: "#ffffff".into(), stroke: None })),
+                        }
+                    } else {
+                        on_change.call(None);
+                        return;
+                    };
+                    on_change.call(Some(LayerMask { source, mode }));
+                },
+                option { value: "", "None" }
+                for id in layer_ids {
+                    option { value: "layer:{id}", "Layer: {id}" }
+                }
+                option { value: "shape", "Inline Shape" }
+            }
+        }
+        if let Some(current) = current {
+            div {
+                class: "control-group",
+                label { "Mask Mode: " }
+                select {
+                    value: "{mode_name}",
+                    oninput: move |evt| {
+                        let mode = match evt.value().as_str() {
+                            "luminance" => MaskMode::Luminance,
+                            "clip" => MaskMode::Clip,
+                            _ => MaskMode::Alpha,
+                        };
+                        on_change.call(Some(LayerMask { mode, ..current.clone() }));
+                    },
+                    option { value: "alpha", "Alpha" }
+                    option { value: "luminance", "Luminance" }
+                    option { value: "clip", "Clip" }
+                }
+            }
+        }
+    }
+}
+
+/// Expands or collapses a group in the layers list.
+#[component]
+pub fn GroupToggle(id: String, expanded_groups: Signal<HashSet<String>>) -> Element {
+    let mut expanded_groups = expanded_groups;
+    let expanded = expanded_groups.read().contains(&id);
+
+    rsx! {
+        button {
+            class: "icon-btn tree-toggle",
+            onclick: move |evt| {
+                evt.stop_propagation();
+                if expanded {
+                    expanded_groups.write().remove(&id);
+                } else {
+                    expanded_groups.write().insert(id.clone());
+                }
+            },
+            if expanded { "▾" } else { "▸" }
+        }
+    }
+}
+
+/// Rows for the children of an expanded group, indented by nesting depth. Clicking one selects the top-level group.
+#[component]
+pub fn LayerTreeChildren(
+    layers: Vec<Layer>,
+    depth: usize,
+    expanded_groups: Signal<HashSet<String>>,
+    on_select: EventHandler<MouseEvent>,
+) -> Element {
+    let indent = depth * 16;
 
+    rsx! {
+        for child in layers {
+            div {
+                key: "{child.id}",
+                class: "layer-item layer-child",
+                style: "margin-left: {indent}px;",
+                onclick: move |evt| on_select.call(evt),
+                if matches!(child.item, Item::Group(_)) {
+                    GroupToggle { id: child.id.clone(), expanded_groups }
+                }
                 div {
-                    class: "layer-actions",
-                    button { 
-                        class: "action-btn", 
-                        title: "Move Up",
-                        disabled: selected_layers.read().len() != 1,
-                        onclick: move |_| {
-                            let idx_opt = selected_layers.read().iter().next().cloned();
-                            if let Some(idx) = idx_opt {
-                                if idx < sigil.read().layers.len() - 1 {
-                                    sigil.write().layers.swap(idx, idx + 1);
-                                    selected_layers.write().clear();
-                                    selected_layers.write().insert(idx + 1);
+                    class: "layer-info",
+                    div { strong { "{child.id}" } }
+                    div { "Type: {item_type_name(&child.item)}" }
+                }
+            }
+            if let Item::Group(group) = &child.item
+                && expanded_groups.read().contains(&child.id)
+            {
+                LayerTreeChildren {
+                    layers: group.children.clone(),
+                    depth: depth + 1,
+                    expanded_groups,
+                    on_select,
+                }
+            }
+        }
+    }
+}
+
+/// Edits a solid color or gradient, emitting the whole updated fill on every change.
+#[component]
+pub fn FillEditor(label: String, fill: Fill, on_change: EventHandler<Fill>) -> Element {
+    let kind = match &fill {
+        Fill::Solid(_) => "solid",
+        Fill::Gradient(Gradient::Linear(_)) => "linear",
+        Fill::Gradient(Gradient::Radial(_)) => "radial",
+    };
+    let current = fill.clone();
+
+    let controls = match fill {
+        Fill::Solid(color) => rsx! {
+            div {
+                class: "control-group",
+                input {
+                    r#type: "color",
+                    value: "{color_input_value(&color)}",
+                    oninput: move |evt| on_change.call(Fill::Solid(evt.value())),
+                }
+            }
+        },
+        Fill::Gradient(gradient) => {
+            let geometry = match &gradient {
+                Gradient::Linear(linear) => {
+                    let (gradient, linear) = (gradient.clone(), linear.clone());
+                    rsx! {
+                        div {
+                            class: "control-group",
+                            label { "Angle: " }
+                            input {
+                                r#type: "number",
+                                value: "{linear.angle}",
+                                oninput: move |evt| {
+                                    if let Ok(angle) = evt.value().parse::<f32>() {
+                                        let mut gradient = gradient.clone();
+                                        if let Gradient::Linear(ref mut linear) = gradient {
+                                            linear.angle = angle;
+                                        }
+                                        on_change.call(Fill::Gradient(gradient));
+                                    }
                                 }
                             }
-                        },
-                        "Up" 
+                        }
                     }
-                    button { 
-                        class: "action-btn", 
-                        title: "Move Down",
-                        disabled: selected_layers.read().len() != 1,
-                        onclick: move |_| {
-                            let idx_opt = selected_layers.read().iter().next().cloned();
-                            if let Some(idx) = idx_opt {
-                                if idx > 0 {
-                                    sigil.write().layers.swap(idx, idx - 1);
-                                    selected_layers.write().clear();
-                                    selected_layers.write().insert(idx - 1);
+                }
+                Gradient::Radial(radial) => {
+                    let (gradient_x, gradient_y, radial) = (gradient.clone(), gradient.clone(), radial.clone());
+                    rsx! {
+                        div {
+                            class: "control-group",
+                            label { "Center X: " }
+                            input {
+                                r#type: "number",
+                                step: "0.05",
+                                value: "{radial.center_x}",
+                                oninput: move |evt| {
+                                    if let Ok(center_x) = evt.value().parse::<f32>() {
+                                        let mut gradient = gradient_x.clone();
+                                        if let Gradient::Radial(ref mut radial) = gradient {
+                                            radial.center_x = center_x;
+                                        }
+                                        on_change.call(Fill::Gradient(gradient));
+                                    }
                                 }
                             }
-                        },
-                        "Down" 
-                    }
-                    button { 
-                        class: "action-btn danger", 
-                        title: "Delete Layer",
-                        disabled: selected_layers.read().is_empty(),
-                        onclick: move |_| {
-                            let to_remove: Vec<usize> = selected_layers.read().iter().cloned().collect();
-                            if !to_remove.is_empty() {
-                                let mut sorted = to_remove;
-                                sorted.sort_by(|a, b| b.cmp(a));
-                                for idx in sorted {
-                                    sigil.write().layers.remove(idx);
+                        }
+                        div {
+                            class: "control-group",
+                            label { "Center Y: " }
+                            input {
+                                r#type: "number",
+                                step: "0.05",
+                                value: "{radial.center_y}",
+                                oninput: move |evt| {
+                                    if let Ok(center_y) = evt.value().parse::<f32>() {
+                                        let mut gradient = gradient_y.clone();
+                                        if let Gradient::Radial(ref mut radial) = gradient {
+                                            radial.center_y = center_y;
+                                        }
+                                        on_change.call(Fill::Gradient(gradient));
+                                    }
                                 }
-                                selected_layers.write().clear();
                             }
-                        },
-                        "Del" 
+                        }
                     }
                 }
+            };
 
-                div {
-                    class: "layers-list",
-                    onmouseleave: move |_| {
-                        drag_over_state.set(None);
-                    },
-                    for (idx, layer) in sigil.read().layers.iter().enumerate() {
-                        div {
-                            key: "{layer.id}",
-                            class: if selected_layers.read().contains(&idx) { "layer-item selected" } else { "layer-item" },
-                            draggable: true,
-                            ondragstart: move |_| {
-                                dragging_layer_index.set(Some(idx));
-                            },
-                            ondragover: move |evt| {
-                                evt.prevent_default();
-                                let coords = evt.element_coordinates();
-                                let is_top = coords.y < 20.0; 
-                                drag_over_state.set(Some((idx, is_top)));
-                            },
-                            ondrop: move |evt| {
-                                evt.prevent_default();
-                                if let Some(from_idx) = *dragging_layer_index.read() {
-                                    if from_idx != idx {
-                                        let mut s = sigil.write();
-                                        if from_idx < s.layers.len() {
-                                            let item = s.layers.remove(from_idx);
-                                            let is_top = (*drag_over_state.read()).map(|(_, top)| top).unwrap_or(true);
-                                            let mut target_idx = idx;
-                                            if from_idx < idx {
-                                                target_idx -= 1;
-                                            }
-                                            
-                                            if !is_top {
-                                                target_idx += 1;
-                                            }
-                                            
-                                            if target_idx <= s.layers.len() {
-                                                s.layers.insert(target_idx, item);
-                                                selected_layers.write().clear();
-                                                selected_layers.write().insert(target_idx);
-                                            }
-                                        }
-                                    }
+            rsx! {
+                {geometry}
+                for (stop_idx, stop) in gradient.stops().iter().cloned().enumerate() {
+                    div {
+                        class: "control-group gradient-stop",
+                        input {
+                            r#type: "color",
+                            value: "{color_input_value(&stop.color)}",
+                            oninput: {
+                                let gradient = gradient.clone();
+                                move |evt: FormEvent| {
+                                    let mut gradient = gradient.clone();
+                                    gradient.stops_mut()[stop_idx].color = evt.value();
+                                    on_change.call(Fill::Gradient(gradient));
                                 }
-                                dragging_layer_index.set(None);
-                                drag_over_state.set(None);
-                            },
-                            
-                            onclick: move |evt| {
-                                let is_ctrl = evt.modifiers().contains(Modifiers::CONTROL) || evt.modifiers().contains(Modifiers::META);
-                                let is_shift = evt.modifiers().contains(Modifiers::SHIFT);
-                                
-                                if is_ctrl || is_shift {
-                                    if selected_layers.read().contains(&idx) {
-                                        selected_layers.write().remove(&idx);
-                                    } else {
-                                        selected_layers.write().insert(idx);
+                            }
+                        }
+                        input {
+                            r#type: "number",
+                            min: "0",
+                            max: "1",
+                            step: "0.05",
+                            value: "{stop.offset}",
+                            oninput: {
+                                let gradient = gradient.clone();
+                                move |evt: FormEvent| {
+                                    if let Ok(offset) = evt.value().parse::<f32>() {
+                                        let mut gradient = gradient.clone();
+                                        gradient.stops_mut()[stop_idx].offset = offset.clamp(0.0, 1.0);
+                                        on_change.call(Fill::Gradient(gradient));
                                     }
-                                } else {
-                                    selected_layers.write().clear();
-                                    selected_layers.write().insert(idx);
                                 }
-                            },
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
 
-                            if let Some((over_idx, is_top)) = *drag_over_state.read() {
-                                if over_idx == idx {
-                                    div {
-                                        class: if is_top { "drop-indicator top" } else { "drop-indicator bottom" }
+    rsx! {
+        div {
+            class: "control-group",
+            label { "{label}: " }
+            select {
+                value: "{kind}",
+                oninput: move |evt| on_change.call(convert_fill(&current, &evt.value())),
+                option { value: "solid", "Solid" }
+                option { value: "linear", "Linear Gradient" }
+                option { value: "radial", "Radial Gradient" }
+            }
+        }
+        {controls}
+    }
+}
+
+/// Edits an optional shape stroke. A width of zero removes the stroke.
+#[component]
+pub fn StrokeEditor(stroke: Option<ShapeStroke>, on_change: EventHandler<Option<ShapeStroke>>) -> Element {
+    let current = stroke.clone().unwrap_or(ShapeStroke {
+        color: "#000000".to_string(),
+        width: 0.0.into(),
+        align: StrokeAlign::Inside,
+        dash: Vec::new(),
+    });
+    let align = match current.align {
+        StrokeAlign::Inside => "inside",
+        StrokeAlign::Center => "center",
+        StrokeAlign::Outside => "outside",
+    };
+    let dash = format_numbers(&current.dash);
+    let (for_width, for_color, for_align, for_dash) = (current.clone(), current.clone(), current.clone(), current.clone());
+
+    rsx! {
+        div {
+            class: "control-group",
+            label { "Stroke Width: " }
+            input {
+                r#type: "text",
+                value: "{current.width}",
+                oninput: move |evt| {
+                    match parse_positive(&evt.value()) {
+                        Some(width) => on_change.call(Some(ShapeStroke { width, ..for_width.clone() })),
+                        None => on_change.call(None),
+                    }
+                }
+            }
+        }
+        if stroke.is_some() {
+            div {
+                class: "control-group",
+                label { "Stroke Color: " }
+                input {
+                    r#type: "color",
+                    value: "{color_input_value(&current.color)}",
+                    oninput: move |evt| on_change.call(Some(ShapeStroke { color: evt.value(), ..for_color.clone() })),
+                }
+            }
+            div {
+                class: "control-group",
+                label { "Stroke Align: " }
+                select {
+                    value: "{align}",
+                    oninput: move |evt| {
+                        let align = match evt.value().as_str() {
+                            "center" => StrokeAlign::Center,
+                            "outside" => StrokeAlign::Outside,
+                            _ => StrokeAlign::Inside,
+                        };
+                        on_change.call(Some(ShapeStroke { align, ..for_align.clone() }));
+                    },
+                    option { value: "inside", "Inside" }
+                    option { value: "center", "Center" }
+                    option { value: "outside", "Outside" }
+                }
+            }
+            div {
+                class: "control-group",
+                label { "Dash: " }
+                input {
+                    r#type: "text",
+                    placeholder: "e.g. 4 2",
+                    value: "{dash}",
+                    oninput: move |evt| {
+                        let dash = parse_numbers(&evt.value()).into_iter().filter(|d| *d > 0.0).collect();
+                        on_change.call(Some(ShapeStroke { dash, ..for_dash.clone() }));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Label and accessors of a numeric shadow field.
+type ShadowField = (&'static str, fn(&Shadow) -> &Bindable<f32>, fn(&mut Shadow) -> &mut Bindable<f32>);
+
+const SHADOW_FIELDS: [ShadowField; 4] = [
+    ("X", |shadow| &shadow.offset_x, |shadow| &mut shadow.offset_x),
+    ("Y", |shadow| &shadow.offset_y, |shadow| &mut shadow.offset_y),
+    ("Blur", |shadow| &shadow.blur, |shadow| &mut shadow.blur),
+    ("Spread", |shadow| &shadow.spread, |shadow| &mut shadow.spread),
+];
+
+/// Edits a layer's effect list, emitting the whole updated list on every change.
+#[component]
+pub fn EffectsEditor(effects: Vec<Effect>, on_change: EventHandler<Vec<Effect>>) -> Element {
+    rsx! {
+        div {
+            class: "control-group",
+            label { "Effects: " }
+        }
+        for (effect_idx, effect) in effects.iter().cloned().enumerate() {
+            div {
+                class: "effect-editor",
+                div {
+                    class: "control-group",
+                    select {
+                        value: "{effect_kind(&effect)}",
+                        oninput: {
+                            let effects = effects.clone();
+                            move |evt: FormEvent| {
+                                let mut effects = effects.clone();
+                                effects[effect_idx] = convert_effect(&effects[effect_idx], &evt.value());
+                                on_change.call(effects);
+                            }
+                        },
+                        option { value: "drop_shadow", "Drop Shadow" }
+                        option { value: "inner_shadow", "Inner Shadow" }
+                        option { value: "layer_blur", "Layer Blur" }
+                        option { value: "background_blur", "Background Blur" }
+                    }
+                    button {
+                        onclick: {
+                            let effects = effects.clone();
+                            move |_| {
+                                let mut effects = effects.clone();
+                                effects.remove(effect_idx);
+                                on_change.call(effects);
+                            }
+                        },
+                        "Remove"
+                    }
+                }
+                match &effect {
+                    Effect::DropShadow(shadow) | Effect::InnerShadow(shadow) => rsx! {
+                        div {
+                            class: "control-group",
+                            label { "Color: " }
+                            input {
+                                r#type: "color",
+                                value: "{color_input_value(&shadow.color)}",
+                                oninput: {
+                                    let effects = effects.clone();
+                                    move |evt: FormEvent| {
+                                        let mut effects = effects.clone();
+                                        if let Effect::DropShadow(ref mut shadow) | Effect::InnerShadow(ref mut shadow) = effects[effect_idx] {
+                                            shadow.color = evt.value();
+                                        }
+                                        on_change.call(effects);
                                     }
                                 }
                             }
+                        }
+                        for (label, get, set) in SHADOW_FIELDS {
                             div {
-                                class: "layer-info",
-                                div { strong { "{layer.id}" } }
-                                div { "Type: {item_type_name(&layer.item)}" }
-                            }
-                            div {
-                                class: "layer-controls",
-                                button {
-                                    class: "icon-btn",
-                                    onclick: move |evt| {
-                                        evt.stop_propagation();
-                                        let current = sigil.read().layers[idx].visible;
-                                        sigil.write().layers[idx].visible = !current;
-                                    },
-                                    if layer.visible { "👁️" } else { "🚫" }
+                                class: "control-group",
+                                label { "{label}: " }
+                                input {
+                                    r#type: "text",
+                                    value: "{get(shadow)}",
+                                    oninput: {
+                                        let effects = effects.clone();
+                                        move |evt: FormEvent| {
+                                            if let Ok(value) = evt.value().parse::<Bindable<f32>>() {
+                                                let mut effects = effects.clone();
+                                                if let Effect::DropShadow(ref mut shadow) | Effect::InnerShadow(ref mut shadow) = effects[effect_idx] {
+                                                    *set(shadow) = value;
+                                                }
+                                                on_change.call(effects);
+                                            }
+                                        }
+                                    }
                                 }
-                                button {
-                                    class: "icon-btn",
-                                    onclick: move |evt| {
-                                        evt.stop_propagation();
-                                        if locked_layers.read().contains(&idx) {
-                                            locked_layers.write().remove(&idx);
-                                        } else {
-                                            locked_layers.write().insert(idx);
+                            }
+                        }
+                    },
+                    Effect::LayerBlur { radius } | Effect::BackgroundBlur { radius } => rsx! {
+                        div {
+                            class: "control-group",
+                            label { "Radius: " }
+                            input {
+                                r#type: "text",
+                                value: "{radius}",
+                                oninput: {
+                                    let effects = effects.clone();
+                                    move |evt: FormEvent| {
+                                        if let Ok(value) = evt.value().parse::<Bindable<f32>>() {
+                                            let mut effects = effects.clone();
+                                            if let Effect::LayerBlur { ref mut radius } | Effect::BackgroundBlur { ref mut radius } = effects[effect_idx] {
+                                                *radius = if value.binding().is_some() { value } else { value.max(0.0).into() };
+                                            }
+                                            on_change.call(effects);
                                         }
-                                    },
-                                    if locked_layers.read().contains(&idx) { "🔒" } else { "🔓" }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                }
+            }
+        }
+        button {
+            onclick: {
+                let effects = effects.clone();
+                move |_| {
+                    let mut effects = effects.clone();
+                    effects.push(convert_effect(&Effect::LayerBlur { radius: 0.0.into() }, "drop_shadow"));
+                    on_change.call(effects);
+                }
+            },
+            "Add Effect"
+        }
+    }
+}
+
+/// Edits the declared template variables. Constraints such as `min` or `options` are left to the JSON.
+#[component]
+pub fn VariablesEditor(variables: Vec<VariableDef>, on_change: EventHandler<Vec<VariableDef>>) -> Element {
+    rsx! {
+        div {
+            class: "control-group",
+            label { "Variables: " }
+        }
+        for (variable_idx, variable) in variables.iter().cloned().enumerate() {
+            div {
+                class: "effect-editor",
+                div {
+                    class: "control-group",
+                    input {
+                        r#type: "text",
+                        placeholder: "name",
+                        value: "{variable.name}",
+                        oninput: {
+                            let variables = variables.clone();
+                            move |evt: FormEvent| {
+                                let mut variables = variables.clone();
+                                variables[variable_idx].name = evt.value();
+                                on_change.call(variables);
+                            }
+                        }
+                    }
+                    select {
+                        value: "{variable.kind}",
+                        oninput: {
+                            let variables = variables.clone();
+                            move |evt: FormEvent| {
+                                if let Some(kind) = VariableType::ALL.into_iter().find(|kind| kind.name() == evt.value()) {
+                                    let mut variables = variables.clone();
+                                    variables[variable_idx].kind = kind;
+                                    on_change.call(variables);
                                 }
                             }
+                        },
+                        for kind in VariableType::ALL {
+                            option { value: "{kind}", "{kind}" }
+                        }
+                    }
+                    button {
+                        onclick: {
+                            let variables = variables.clone();
+                            move |_| {
+                                let mut variables = variables.clone();
+                                variables.remove(variable_idx);
+                                on_change.call(variables);
+                            }
+                        },
+                        "Remove"
+                    }
+                }
+                div {
+                    class: "control-group",
+                    label { "Default: " }
+                    input {
+                        r#type: "text",
+                        placeholder: "required",
+                        value: "{variable.default.clone().unwrap_or_default()}",
+                        oninput: {
+                            let variables = variables.clone();
+                            move |evt: FormEvent| {
+                                let mut variables = variables.clone();
+                                let value = evt.value();
+                                variables[variable_idx].default = (!value.is_empty()).then_some(value);
+                                on_change.call(variables);
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "control-group",
+                    label { "Description: " }
+                    input {
+                        r#type: "text",
+                        value: "{variable.description}",
+                        oninput: {
+                            let variables = variables.clone();
+                            move |evt: FormEvent| {
+                                let mut variables = variables.clone();
+                                variables[variable_idx].description = evt.value();
+                                on_change.call(variables);
+                            }
                         }
                     }
                 }
             }
+        }
+        button {
+            onclick: {
+                let variables = variables.clone();
+                move |_| {
+                    let mut variables = variables.clone();
+                    variables.push(VariableDef::new(format!("var{}", variables.len() + 1)));
+                    on_change.call(variables);
+                }
+            },
+            "Add Variable"
+        }
+    }
+}
 
+/// Sample values for previewing the template, such as toggling a flag to see each branch of a layer condition.
+/// Values left empty fall back to the variable's default.
+#[component]
+pub fn PreviewValuesEditor(variables: Vec<VariableDef>, values: HashMap<String, String>, on_change: EventHandler<HashMap<String, String>>) -> Element {
+    rsx! {
+        if !variables.is_empty() {
             div {
-                class: "right-panel",
-                onclick: move |_| {
-                    selected_layers.write().clear();
-                },
-                
-                h2 { "Preview (Drag items to move)" }
+                class: "control-group",
+                label { "Preview Values: " }
+            }
+        }
+        for variable in variables.iter().cloned() {
+            div {
+                class: "control-group",
+                label { "{variable.name}: " }
+                if variable.kind == VariableType::Boolean {
+                    input {
+                        r#type: "checkbox",
+                        checked: values.get(&variable.name).or(variable.default.as_ref()).is_some_and(|value| value == "true"),
+                        onchange: {
+                            let values = values.clone();
+                            let name = variable.name.clone();
+                            move |evt: FormEvent| {
+                                let mut values = values.clone();
+                                values.insert(name.clone(), evt.checked().to_string());
+                                on_change.call(values);
+                            }
+                        }
+                    }
+                } else {
+                    input {
+                        r#type: "text",
+                        placeholder: "{variable.default.clone().unwrap_or_default()}",
+                        value: "{values.get(&variable.name).cloned().unwrap_or_default()}",
+                        oninput: {
+                            let values = values.clone();
+                            let name = variable.name.clone();
+                            move |evt: FormEvent| {
+                                let mut values = values.clone();
+                                let value = evt.value();
+                                if value.is_empty() {
+                                    values.remove(&name);
+                                } else {
+                                    values.insert(name.clone(), value);
+                                }
+                                on_change.call(values);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
 
-                div {
-                    class: "canvas-container",
-                    style: "
-                        width: {sigil.read().width}px; 
-                        height: {sigil.read().height}px; 
-                        background-color: {sigil.read().background};
-                        cursor: {cursor_style};
-                    ",
-                    onclick: move |_| {
-                        selected_layers.write().clear();
-                    },
-                    
-                    for (idx, layer) in sigil.read().layers.iter().enumerate() {
-                        if layer.visible {
-                            {
-                                let is_selected = selected_layers.read().contains(&idx);
-                                let is_locked = locked_layers.read().contains(&idx);
-                                rsx!{
-                                    RenderLayer {
-                                        key: "{layer.id}",
-                                        layer: layer.clone(),
-                                        is_selected,
-                                        is_locked,
-                                        text_dimensions: text_dimensions,
-                                        on_move_start: move |evt: MouseEvent| {
-                                        if locked_layers.read().contains(&idx) {
-                                            return;
-                                        }
+/// Problems found by `Sigil::validate`, with their JSON paths. Clicking one inside a layer selects the top level
+/// layer it belongs to.
+#[component]
+pub fn IssueList(issues: Vec<ValidationIssue>, on_select: Option<EventHandler<usize>>) -> Element {
+    let errors = issues.iter().filter(|issue| issue.severity == Severity::Error).count();
+    let warnings = issues.len() - errors;
 
-                                        let is_ctrl = evt.modifiers().contains(Modifiers::CONTROL) || evt.modifiers().contains(Modifiers::META);
-                                        let is_shift = evt.modifiers().contains(Modifiers::SHIFT);
-                                        
-                                        if !selected_layers.read().contains(&idx) {
-                                            if !is_ctrl && !is_shift {
-                                                selected_layers.write().clear();
-                                            }
-                                            selected_layers.write().insert(idx);
-                                        } else if is_ctrl {
-                                        }
+    rsx! {
+        if !issues.is_empty() {
+            div {
+                class: "control-group",
+                label { "Problems: {errors} errors, {warnings} warnings" }
+            }
+            div {
+                class: "issue-list",
+                for issue in issues.iter().cloned() {
+                    div {
+                        class: if issue.severity == Severity::Error { "issue issue-error" } else { "issue issue-warning" },
+                        onclick: {
+                            let path = issue.path.clone();
+                            move |_| {
+                                if let (Some(on_select), Some(idx)) = (on_select, top_level_layer(&path)) {
+                                    on_select.call(idx);
+                                }
+                            }
+                        },
+                        span { class: "issue-path", "{issue.path}" }
+                        span { "{issue.message}" }
+                    }
+                }
+            }
+        }
+    }
+}
 
-                                        let coords = evt.page_coordinates();
+/// The index of the top level layer a validation path such as `layers[2].item.data.color` points into.
+fn top_level_layer(path: &str) -> Option<usize> {
+    path.strip_prefix("layers[")?.split(']').next()?.parse().ok()
+}
 
-                                        let mut original_positions = Vec::new();
-                                        for &sel_idx in selected_layers.read().iter() {
-                                            if let Some(l) = sigil.read().layers.get(sel_idx) {
-                                                if !locked_layers.read().contains(&sel_idx) {
-                                                    original_positions.push((sel_idx, l.x, l.y));
-                                                }
-                                            }
-                                        }
+/// Edits the canvas background: a color or gradient, an image or a pattern.
+#[component]
+pub fn BackgroundEditor(background: Background, on_change: EventHandler<Background>) -> Element {
+    let kind = match &background {
+        Background::Solid(_) | Background::Gradient(_) => "fill",
+        Background::Image(_) => "image",
+        Background::Pattern(_) => "pattern",
+    };
 
-                                        if !original_positions.is_empty() {
-                                            dragging.set(Some((idx, DragMode::Move {
-                                                start_x: coords.x,
-                                                start_y: coords.y,
-                                                original_positions,
-                                            })));
-                                        }
-                                        evt.stop_propagation();
+    rsx! {
+        div {
+            class: "control-group",
+            label { "Canvas Background: " }
+            select {
+                value: "{kind}",
+                oninput: {
+                    let background = background.clone();
+                    move |evt: FormEvent| on_change.call(convert_background(&background, &evt.value()))
+                },
+                option { value: "fill", "Color / Gradient" }
+                option { value: "image", "Image" }
+                option { value: "pattern", "Pattern" }
+            }
+        }
+        match background.clone() {
+            Background::Solid(_) | Background::Gradient(_) => rsx! {
+                FillEditor {
+                    label: "Background Fill",
+                    fill: background.as_fill().unwrap_or_default(),
+                    on_change: move |fill: Fill| on_change.call(fill.into()),
+                }
+            },
+            Background::Image(image) => {
+                let overlay = image.overlay.clone();
+                rsx! {
+                    div {
+                        class: "control-group",
+                        label { "Source: " }
+                        input {
+                            r#type: "text",
+                            value: "{image.source}",
+                            oninput: {
+                                let image = image.clone();
+                                move |evt: FormEvent| on_change.call(Background::Image(BackgroundImage { source: evt.value(), ..image.clone() }))
+                            }
+                        }
+                    }
+                    div {
+                        class: "control-group",
+                        label { "Fit: " }
+                        select {
+                            value: "{image.fit.css_name()}",
+                            oninput: {
+                                let image = image.clone();
+                                move |evt: FormEvent| {
+                                    if let Some(fit) = ImageFit::from_css_name(&evt.value()) {
+                                        on_change.call(Background::Image(BackgroundImage { fit, ..image.clone() }));
                                     }
                                 }
-                            }
+                            },
+                            for fit in ImageFit::ALL {
+                                option { value: "{fit.css_name()}", "{fit.css_name()}" }
                             }
                         }
                     }
-
-                    {
-                        let indices: Vec<usize> = selected_layers.read().iter().cloned().collect();
-                        indices.into_iter().map(|idx| {
-                            if let Some(layer) = sigil.read().layers.get(idx) {
-                                if !layer.visible || locked_layers.read().contains(&idx) {
-                                    return rsx!({});
+                    div {
+                        class: "control-group",
+                        label { "Focal Point: " }
+                        input {
+                            r#type: "text",
+                            placeholder: "x y (0 to 1)",
+                            value: "{format_bindables(&image.focal_point)}",
+                            oninput: {
+                                let image = image.clone();
+                                move |evt: FormEvent| {
+                                    if let Ok(focal_point) = <[Bindable<f32>; 2]>::try_from(parse_bindables(&evt.value())) {
+                                        on_change.call(Background::Image(BackgroundImage { focal_point, ..image.clone() }));
+                                    }
                                 }
-
-                                let layer_rot = layer.rotation;
-                                let layer_x = layer.x;
-                                let layer_y = layer.y;
-                                rsx! {
-                                    SelectionOverlay {
-                                        key: "overlay_{idx}",
-                                        layer: layer.clone(),
-                                        text_dimensions: text_dimensions,
-                                        on_resize_start: move |(handle, evt): (HandleType, MouseEvent)| {
-                                            if selected_layers.read().len() == 1 {
-                                                let coords = evt.page_coordinates();
-                                                let (w, h) = match &sigil.read().layers[idx].item {
-                                                    Item::Rect(r) => (r.width, r.height),
-                                                    Item::Image(i) => (i.width, i.height),
-                                                    Item::Text(t) => {
-                                                        if let Some(&(tw, th)) = text_dimensions.read().get(&sigil.read().layers[idx].id) {
-                                                            (tw, th)
-                                                        } else {
-                                                            (t.text.len() as f32 * t.font_size * 0.6, t.font_size)
-                                                        }
-                                                    },
-                                                    Item::Slider(s) => (s.width, s.height),
-                                                };
-                                                dragging.set(Some((idx, DragMode::Resize {
-                                                    handle,
-                                                    start_x: coords.x,
-                                                    start_y: coords.y,
-                                                    orig_x: layer_x,
-                                                    orig_y: layer_y,
-                                                    orig_w: w,
-                                                    orig_h: h,
-                                                })));
-                                                evt.stop_propagation();
-                                            }
-                                        },
-                                        on_rotate_start: move |evt: MouseEvent| {
-                                            if selected_layers.read().len() == 1 {
-                                                let coords = evt.page_coordinates();
-                                                let (w, h) = match &sigil.read().layers[idx].item {
-                                                    Item::Rect(r) => (r.width, r.height),
-                                                    Item::Image(i) => (i.width, i.height),
-                                                    Item::Text(t) => {
-                                                        if let Some(&(tw, th)) = text_dimensions.read().get(&sigil.read().layers[idx].id) {
-                                                            (tw, th)
-                                                        } else {
-                                                            (t.text.len() as f32 * t.font_size * 0.6, t.font_size)
-                                                        }
-                                                    },
-                                                    Item::Slider(s) => (s.width, s.height),
-                                                };
-                                                let rot_rad = sigil.read().layers[idx].rotation.to_radians();
-
-                                                let dist = h as f64 / 2.0 + 30.0;
-                                                let vec_x = dist * (rot_rad.sin() as f64);
-                                                let vec_y = -dist * (rot_rad.cos() as f64);
-                                                
-                                                let center_x = coords.x - vec_x;
-                                                let center_y = coords.y - vec_y;
-                                                
-                                                let start_angle = (coords.y - center_y).atan2(coords.x - center_x);
-
-                                                dragging.set(Some((idx, DragMode::Rotate {
-                                                    orig_rotation: layer_rot,
-                                                    center_x,
-                                                    center_y,
-                                                    start_angle,
-                                                })));
-                                                evt.stop_propagation();
-                                            }
-                                        }
+                            }
+                        }
+                    }
+                    div {
+                        class: "control-group checkbox-group",
+                        label {
+                            input {
+                                r#type: "checkbox",
+                                checked: overlay.is_some(),
+                                onchange: {
+                                    let image = image.clone();
+                                    move |evt: FormEvent| {
+                                        let overlay = evt.checked().then(|| "#00000080".into());
+                                        on_change.call(Background::Image(BackgroundImage { overlay, ..image.clone() }));
                                     }
                                 }
-                            } else {
-                                rsx!({})
                             }
-                        })
+                            "Overlay"
+                        }
                     }
-
-                    for guide in guides.read().iter() {
-                        if guide.is_vertical {
-                            div {
-                                class: "smart-guide vertical",
-                                style: "left: {guide.pos}px; top: {guide.start}px; height: {guide.end - guide.start}px;"
-                            }
-                        } else {
-                            div {
-                                class: "smart-guide horizontal",
-                                style: "top: {guide.pos}px; left: {guide.start}px; width: {guide.end - guide.start}px;"
-                            }
+                    if let Some(overlay) = overlay.clone() {
+                        FillEditor {
+                            label: "Overlay",
+                            fill: overlay,
+                            on_change: {
+                                let image = image.clone();
+                                move |fill: Fill| on_change.call(Background::Image(BackgroundImage { overlay: Some(fill), ..image.clone() }))
+                            },
                         }
                     }
-                }
-
-                div {
-                    class: "json-output",
-                    pre {
-                        "{serde_json::to_string_pretty(&*sigil.read()).unwrap()}"
+                    FiltersEditor {
+                        label: "Background Filters",
+                        filters: image.filters.clone(),
+                        on_change: {
+                            let image = image.clone();
+                            move |filters| on_change.call(Background::Image(BackgroundImage { filters, ..image.clone() }))
+                        },
                     }
                 }
             }
-        }
-        
-        if *show_load_modal.read() {
-            div { class: "modal-overlay",
-                onclick: move |_| {
-                    show_load_modal.set(false);
-                },
-                div { class: "modal", onclick: move |evt| evt.stop_propagation(),
-                    h3 { "Load JSON" }
-                    textarea {
-                        value: "{load_json_text.read()}",
-                        placeholder: "Paste Sigil JSON here...",
-                        autofocus: "true",
-                        oninput: move |evt| {
-                            load_json_text.set(evt.value());
+            Background::Pattern(pattern) => {
+                let tile_size = pattern.tile_size.as_ref().map(|size| format_bindables(size)).unwrap_or_default();
+                rsx! {
+                    div {
+                        class: "control-group",
+                        label { "Source: " }
+                        input {
+                            r#type: "text",
+                            value: "{pattern.source}",
+                            oninput: {
+                                let pattern = pattern.clone();
+                                move |evt: FormEvent| on_change.call(Background::Pattern(BackgroundPattern { source: evt.value(), ..pattern.clone() }))
+                            }
                         }
                     }
-                    if let Some(err) = &*load_error.read() {
-                        div { class: "error-text", "{err}" }
-                    }
-                    div { class: "modal-actions",
-                        button {
-                            class: "primary-btn",
-                            onclick: move |_| {
-                                match serde_json::from_str::<Sigil>(&load_json_text.read()) {
-                                    Ok(new_sigil) => {
-                                        sigil.set(new_sigil);
-                                        selected_layers.write().clear();
-                                        guides.write().clear();
-                                        text_dimensions.write().clear();
-                                        layer_id_counter.set(sigil.read().layers.len() as i32);
-                                        load_error.set(None);
-                                        show_load_modal.set(false);
-                                    },
-                                    Err(e) => {
-                                        load_error.set(Some(format!("Invalid JSON: {}", e)));
-                                    }
+                    div {
+                        class: "control-group",
+                        label { "Tile Size: " }
+                        input {
+                            r#type: "text",
+                            placeholder: "width height (image size if empty)",
+                            value: "{tile_size}",
+                            oninput: {
+                                let pattern = pattern.clone();
+                                move |evt: FormEvent| {
+                                    let tile_size = parse_bindables(&evt.value()).try_into().ok();
+                                    on_change.call(Background::Pattern(BackgroundPattern { tile_size, ..pattern.clone() }))
                                 }
-                            },
-                            "Load"
-                        }
-                        button {
-                            onclick: move |_| {
-                                show_load_modal.set(false);
-                                load_error.set(None);
-                            },
-                            "Cancel"
+                            }
                         }
                     }
                 }
@@ -1287,77 +3585,233 @@ pub fn SigilEditor() -> Element {
     }
 }
 
+/// Switches the background to another kind, keeping its image source where possible.
+fn convert_background(background: &Background, kind: &str) -> Background {
+    let source = match background {
+        Background::Image(image) => image.source.clone(),
+        Background::Pattern(pattern) => pattern.source.clone(),
+        Background::Solid(_) | Background::Gradient(_) => String::new(),
+    };
+
+    match kind {
+        "image" => Background::Image(BackgroundImage::new(source)),
+        "pattern" => Background::Pattern(BackgroundPattern { source, tile_size: None }),
+        _ => background.as_fill().unwrap_or_else(|| "#222222".into()).into(),
+    }
+}
+
+/// Edits an image's or the background's filter list, emitting the whole updated list on every change.
 #[component]
-pub fn RenderLayer(
-    layer: Layer, 
-    is_selected: bool, 
-    is_locked: bool,
-    text_dimensions: Signal<HashMap<String, (f32, f32)>>,
-    on_move_start: EventHandler<MouseEvent>,
-) -> Element {
-    let mut text_dims_write = text_dimensions;
-    
-    let border_style = if is_selected { "2px solid #0055ff" } else { "none" };
-    
-    match &layer.item {
-        Item::Slider(s) => {
-             rsx! {
-                div {
-                    key: "{layer.id}",
-                    style: "position: absolute; left: {layer.x}px; top: {layer.y}px; width: {s.width}px; height: {s.height}px; background-color: {s.background_color}; border-radius: {s.border_radius}px; transform: rotate({layer.rotation}deg); cursor: move; outline: {border_style};",
-                    onmousedown: move |evt| on_move_start.call(evt),
-                }
-            }
-        },
-        Item::Rect(r) => {
-             rsx! {
+pub fn FiltersEditor(label: String, filters: Vec<ImageFilter>, on_change: EventHandler<Vec<ImageFilter>>) -> Element {
+    rsx! {
+        div {
+            class: "control-group",
+            label { "{label}: " }
+        }
+        for (filter_idx, filter) in filters.iter().cloned().enumerate() {
+            div {
+                class: "effect-editor",
                 div {
-                    key: "{layer.id}",
-                    style: "position: absolute; left: {layer.x}px; top: {layer.y}px; width: {r.width}px; height: {r.height}px; background-color: {r.color}; border-radius: {r.border_radius}px; transform: rotate({layer.rotation}deg); cursor: move; outline: {border_style};",
-                    onmousedown: move |evt| on_move_start.call(evt),
+                    class: "control-group",
+                    select {
+                        value: "{filter_kind(&filter)}",
+                        oninput: {
+                            let filters = filters.clone();
+                            move |evt: FormEvent| {
+                                let mut filters = filters.clone();
+                                filters[filter_idx] = new_filter(&evt.value());
+                                on_change.call(filters);
+                            }
+                        },
+                        for (kind, name) in FILTER_KINDS {
+                            option { value: "{kind}", "{name}" }
+                        }
+                    }
+                    button {
+                        onclick: {
+                            let filters = filters.clone();
+                            move |_| {
+                                let mut filters = filters.clone();
+                                filters.remove(filter_idx);
+                                on_change.call(filters);
+                            }
+                        },
+                        "Remove"
+                    }
                 }
-            }
-        },
-        Item::Image(i) => {
-            rsx! {
-                img {
-                    key: "{layer.id}",
-                    src: "{i.source}",
-                    style: "position: absolute; left: {layer.x}px; top: {layer.y}px; width: {i.width}px; height: {i.height}px; border-radius: {i.border_radius}px; transform: rotate({layer.rotation}deg); cursor: move; outline: {border_style}; user-select: none;",
-                    draggable: "false",
-                    onmousedown: move |evt| on_move_start.call(evt),
+                if let ImageFilter::Tint { color, .. } = &filter {
+                    div {
+                        class: "control-group",
+                        label { "Color: " }
+                        input {
+                            r#type: "color",
+                            value: "{color_input_value(color)}",
+                            oninput: {
+                                let filters = filters.clone();
+                                move |evt: FormEvent| {
+                                    let mut filters = filters.clone();
+                                    if let ImageFilter::Tint { ref mut color, .. } = filters[filter_idx] {
+                                        *color = evt.value();
+                                    }
+                                    on_change.call(filters);
+                                }
+                            }
+                        }
+                    }
                 }
-            }
-        },
-        Item::Text(t) => {
-            let font_family = match t.font_family.as_str() {
-                "Sans Serif" => "sans-serif",
-                "Serif" => "serif",
-                "Monospace" => "monospace",
-                "Cursive" => "cursive",
-                "Fantasy" => "fantasy",
-                _ => "sans-serif",
-            };
-            
-            rsx! {
                 div {
-                    key: "{layer.id}",
-                    style: "position: absolute; left: {layer.x}px; top: {layer.y}px; font-size: {t.font_size}px; color: {t.color}; font-family: {font_family}; transform: rotate({layer.rotation}deg); cursor: move; white-space: nowrap; outline: {border_style}; user-select: none;",
-                    onmousedown: move |evt| on_move_start.call(evt),
-                    onmounted: move |evt| {
-                        let layer_id = layer.id.clone();
-                        async move {
-                            if let Ok(rect) = evt.get_client_rect().await {
-                                let w = rect.width() as f32;
-                                let h = rect.height() as f32;
-                                text_dims_write.write().insert(layer_id, (w, h));
+                    class: "control-group",
+                    label { "{filter_value_label(&filter)}: " }
+                    input {
+                        r#type: "number",
+                        step: if matches!(filter, ImageFilter::HueRotate { .. } | ImageFilter::Blur { .. }) { "1" } else { "0.05" },
+                        value: "{filter_value(&filter)}",
+                        oninput: {
+                            let filters = filters.clone();
+                            move |evt: FormEvent| {
+                                if let Ok(value) = evt.value().parse::<f32>() {
+                                    let mut filters = filters.clone();
+                                    set_filter_value(&mut filters[filter_idx], value);
+                                    on_change.call(filters);
+                                }
                             }
                         }
-                    },
-                    "{t.text}"
+                    }
                 }
             }
         }
+        button {
+            onclick: {
+                let filters = filters.clone();
+                move |_| {
+                    let mut filters = filters.clone();
+                    filters.push(new_filter("grayscale"));
+                    on_change.call(filters);
+                }
+            },
+            "Add Filter"
+        }
+    }
+}
+
+const FILTER_KINDS: [(&str, &str); 9] = [
+    ("grayscale", "Grayscale"),
+    ("sepia", "Sepia"),
+    ("invert", "Invert"),
+    ("brightness", "Brightness"),
+    ("contrast", "Contrast"),
+    ("saturate", "Saturate"),
+    ("hue_rotate", "Hue Rotate"),
+    ("tint", "Tint"),
+    ("blur", "Blur"),
+];
+
+fn filter_kind(filter: &ImageFilter) -> &'static str {
+    match filter {
+        ImageFilter::Grayscale { .. } => "grayscale",
+        ImageFilter::Sepia { .. } => "sepia",
+        ImageFilter::Invert { .. } => "invert",
+        ImageFilter::Brightness { .. } => "brightness",
+        ImageFilter::Contrast { .. } => "contrast",
+        ImageFilter::Saturate { .. } => "saturate",
+        ImageFilter::HueRotate { .. } => "hue_rotate",
+        ImageFilter::Tint { .. } => "tint",
+        ImageFilter::Blur { .. } => "blur",
+    }
+}
+
+/// A filter of the given kind at full strength, or a visible amount for the unbounded ones.
+fn new_filter(kind: &str) -> ImageFilter {
+    match kind {
+        "sepia" => ImageFilter::Sepia { amount: 1.0 },
+        "invert" => ImageFilter::Invert { amount: 1.0 },
+        "brightness" => ImageFilter::Brightness { amount: 1.2 },
+        "contrast" => ImageFilter::Contrast { amount: 1.2 },
+        "saturate" => ImageFilter::Saturate { amount: 1.5 },
+        "hue_rotate" => ImageFilter::HueRotate { degrees: 90.0 },
+        "tint" => ImageFilter::Tint { color: "#5865f2".to_string(), amount: 1.0 },
+        "blur" => ImageFilter::Blur { radius: 8.0 },
+        _ => ImageFilter::Grayscale { amount: 1.0 },
+    }
+}
+
+fn filter_value_label(filter: &ImageFilter) -> &'static str {
+    match filter {
+        ImageFilter::HueRotate { .. } => "Degrees",
+        ImageFilter::Blur { .. } => "Radius",
+        _ => "Amount",
+    }
+}
+
+fn filter_value(filter: &ImageFilter) -> f32 {
+    match filter {
+        ImageFilter::Grayscale { amount }
+        | ImageFilter::Sepia { amount }
+        | ImageFilter::Invert { amount }
+        | ImageFilter::Brightness { amount }
+        | ImageFilter::Contrast { amount }
+        | ImageFilter::Saturate { amount }
+        | ImageFilter::Tint { amount, .. } => *amount,
+        ImageFilter::HueRotate { degrees } => *degrees,
+        ImageFilter::Blur { radius } => *radius,
+    }
+}
+
+fn set_filter_value(filter: &mut ImageFilter, value: f32) {
+    match filter {
+        ImageFilter::Grayscale { amount }
+        | ImageFilter::Sepia { amount }
+        | ImageFilter::Invert { amount }
+        | ImageFilter::Brightness { amount }
+        | ImageFilter::Contrast { amount }
+        | ImageFilter::Saturate { amount }
+        | ImageFilter::Tint { amount, .. } => *amount = value.max(0.0),
+        ImageFilter::HueRotate { degrees } => *degrees = value,
+        ImageFilter::Blur { radius } => *radius = value.max(0.0),
+    }
+}
+
+fn effect_kind(effect: &Effect) -> &'static str {
+    match effect {
+        Effect::DropShadow(_) => "drop_shadow",
+        Effect::InnerShadow(_) => "inner_shadow",
+        Effect::LayerBlur { .. } => "layer_blur",
+        Effect::BackgroundBlur { .. } => "background_blur",
+    }
+}
+
+/// Switches an effect to another kind, keeping its shadow or blur settings where possible.
+fn convert_effect(effect: &Effect, kind: &str) -> Effect {
+    let (shadow, radius) = match effect {
+        Effect::DropShadow(shadow) | Effect::InnerShadow(shadow) => (shadow.clone(), shadow.blur.clone()),
+        Effect::LayerBlur { radius } | Effect::BackgroundBlur { radius } => (
+            Shadow { color: "#00000080".to_string(), offset_x: 0.0.into(), offset_y: 4.0.into(), blur: 12.0.into(), spread: 0.0.into() },
+            radius.clone(),
+        ),
+    };
+
+    match kind {
+        "inner_shadow" => Effect::InnerShadow(shadow),
+        "layer_blur" => Effect::LayerBlur { radius },
+        "background_blur" => Effect::BackgroundBlur { radius },
+        _ => Effect::DropShadow(shadow),
+    }
+}
+
+/// Switches a fill to another kind, keeping its colors where possible.
+fn convert_fill(fill: &Fill, kind: &str) -> Fill {
+    let stops = match fill {
+        Fill::Gradient(gradient) => gradient.stops().to_vec(),
+        Fill::Solid(color) => vec![
+            GradientStop { color: color.clone(), offset: 0.0 },
+            GradientStop { color: "#000000".to_string(), offset: 1.0 },
+        ],
+    };
+
+    match kind {
+        "linear" => Fill::Gradient(Gradient::Linear(LinearGradient { angle: 180.0, stops })),
+        "radial" => Fill::Gradient(Gradient::Radial(RadialGradient { center_x: 0.5, center_y: 0.5, stops })),
+        _ => Fill::Solid(fill.primary_color().to_string()),
     }
 }
 
@@ -1368,18 +3822,7 @@ pub fn SelectionOverlay(
     on_resize_start: EventHandler<(HandleType, MouseEvent)>,
     on_rotate_start: EventHandler<MouseEvent>,
 ) -> Element {
-    let (w, h) = match &layer.item {
-        Item::Rect(r) => (r.width, r.height),
-        Item::Image(i) => (i.width, i.height),
-        Item::Text(t) => {
-            if let Some(&(tw, th)) = text_dimensions.read().get(&layer.id) {
-                (tw, th)
-            } else {
-                (t.text.len() as f32 * t.font_size * 0.6, t.font_size)
-            }
-        },
-        Item::Slider(s) => (s.width, s.height),
-    };
+    let (w, h) = layer_size(&layer, &text_dimensions.read());
 
     let handle_size = 8.0;
     let offset = -handle_size / 2.0;
@@ -1388,7 +3831,7 @@ pub fn SelectionOverlay(
         div {
             style: "position: absolute; left: {layer.x}px; top: {layer.y}px; width: {w}px; height: {h}px; pointer-events: none; transform: rotate({layer.rotation}deg);",
 
-            if matches!(layer.item, Item::Rect(_) | Item::Image(_) | Item::Slider(_)) {
+            if !matches!(layer.item, Item::Text(_)) {
                 div {
                     style: "position: absolute; left: {offset}px; top: {offset}px; width: {handle_size}px; height: {handle_size}px; background: white; border: 1px solid #0055ff; pointer-events: auto; cursor: nw-resize;",
                     onmousedown: move |evt| on_resize_start.call((HandleType::TopLeft, evt)),
@@ -1417,11 +3860,229 @@ pub fn SelectionOverlay(
     }
 }
 
+/// `<input type="color">` only understands `#rrggbb`, so show other CSS colors by their opaque hex value.
+fn color_input_value(color: &str) -> String {
+    Rgba::parse(color).map_or_else(|_| "#000000".to_string(), |color| color.to_hex_rgb())
+}
+
 fn item_type_name(item: &Item) -> &'static str {
     match item {
         Item::Rect(_) => "Rectangle",
         Item::Text(_) => "Text",
         Item::Image(_) => "Image",
         Item::Slider(_) => "Slider",
+        Item::Ellipse(_) => "Ellipse",
+        Item::Line(_) => "Line",
+        Item::Polygon(_) => "Polygon",
+        Item::Star(_) => "Star",
+        Item::RegularPolygon(_) => "Regular Polygon",
+        Item::Path(_) => "Path",
+        Item::Group(_) => "Group",
+        Item::Repeat(_) => "Repeat",
+    }
+}
+
+fn line_cap_name(cap: LineCap) -> &'static str {
+    match cap {
+        LineCap::Butt => "butt",
+        LineCap::Round => "round",
+        LineCap::Square => "square",
+    }
+}
+
+/// Numbers separated by spaces or commas, as typed into dash inputs.
+fn parse_numbers(input: &str) -> Vec<f32> {
+    input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(|part| part.parse::<f32>().ok())
+        .collect()
+}
+
+/// An optional bindable field as typed into a text input: `None` when empty, and literals must be positive.
+fn parse_positive<T: std::str::FromStr + Default + PartialOrd>(input: &str) -> Option<Bindable<T>> {
+    input.parse::<Bindable<T>>().ok().filter(|value| value.binding().is_some() || **value > T::default())
+}
+
+fn format_numbers(numbers: &[f32]) -> String {
+    numbers.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+/// Numbers or `{...}` bindings separated by spaces, as typed into crop, focal point and tile size inputs.
+fn parse_bindables(input: &str) -> Vec<Bindable<f32>> {
+    input.split_whitespace().filter_map(|part| part.parse().ok()).collect()
+}
+
+fn format_bindables(values: &[Bindable<f32>]) -> String {
+    values.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+/// Points written like SVG's `points` attribute: `x,y` pairs separated by spaces.
+fn parse_points(input: &str) -> Vec<[f32; 2]> {
+    parse_numbers(input).chunks_exact(2).map(|pair| [pair[0], pair[1]]).collect()
+}
+
+fn format_points(points: &[[f32; 2]]) -> String {
+    points.iter().map(|[x, y]| format!("{},{}", x, y)).collect::<Vec<_>>().join(" ")
+}
+
+/// On-canvas size of a layer's box. Text uses the size measured in the DOM, or a rough estimate before it is mounted.
+fn layer_size(layer: &Layer, text_dimensions: &HashMap<String, (f32, f32)>) -> (f32, f32) {
+    match &layer.item {
+        Item::Text(t) => {
+            if let Some(&(tw, th)) = text_dimensions.get(&layer.id) {
+                (tw, th)
+            } else {
+                (t.text.len() as f32 * *t.font_size * 0.6, *t.font_size)
+            }
+        },
+        item => item.size().unwrap_or_default(),
+    }
+}
+
+/// Id of the sibling layer masking `layer`, if it is masked by one that exists.
+fn masked_by<'a>(layers: &[Layer], layer: &'a Layer) -> Option<&'a str> {
+    let id = layer.mask.as_ref()?.layer_id()?;
+    layers.iter().any(|other| other.id == id).then_some(id)
+}
+
+/// The declared variables, followed by the undeclared ones that repeaters and layer conditions read.
+fn preview_variables(sigil: &Sigil) -> Vec<VariableDef> {
+    let mut variables = sigil.variables.clone();
+    for layer in sigil.all_layers() {
+        if let Item::Repeat(repeat) = &layer.item
+            && !variables.iter().any(|variable| variable.name == repeat.source)
+        {
+            variables.push(VariableDef { kind: VariableType::List, ..VariableDef::new(repeat.source.clone()) });
+        }
+    }
+    for condition in sigil.all_layers().filter_map(|layer| layer.condition.as_deref()) {
+        if let Ok(expr) = sigil_core::expr::parse(condition) {
+            // Entries of repeaters come from their list
+            let is_entry = |name: &str| name == "index" || name == "item" || name.starts_with("item.");
+            for name in expr.variables() {
+                if !is_entry(name) && !variables.iter().any(|variable| variable.name == name) {
+                    variables.push(VariableDef::new(name));
+                }
+            }
+        }
+    }
+    variables
+}
+
+/// The top level layers resolved with the preview values, with their indices. Layers whose condition does not
+/// hold are left out.
+fn preview_layers(sigil: &Sigil, values: &HashMap<String, String>) -> Vec<(usize, Layer)> {
+    sigil.layers.iter().enumerate().filter_map(|(idx, layer)| Some((idx, sigil.resolve_layer(layer, values)?))).collect()
+}
+
+/// Whether a sibling uses the layer as its mask, so the layer is not drawn itself.
+fn is_mask_layer(layers: &[Layer], id: &str) -> bool {
+    layers.iter().any(|layer| layer.mask.as_ref().and_then(LayerMask::layer_id) == Some(id))
+}
+
+/// Moves the layers at `indices` into a new group spanning their boxes, placed where the topmost of them was.
+/// Returns the index of the group, or `None` if there was nothing to group.
+fn group_layers(layers: &mut Vec<Layer>, indices: &HashSet<usize>, id: String, text_dimensions: &HashMap<String, (f32, f32)>) -> Option<usize> {
+    let mut sorted: Vec<usize> = indices.iter().copied().filter(|&idx| idx < layers.len()).collect();
+    sorted.sort_unstable();
+    let &top = sorted.last()?;
+
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+    for &idx in &sorted {
+        let layer = &layers[idx];
+        let (w, h) = layer_size(layer, text_dimensions);
+        min_x = min_x.min(*layer.x);
+        min_y = min_y.min(*layer.y);
+        max_x = max_x.max(*layer.x + w);
+        max_y = max_y.max(*layer.y + h);
+    }
+
+    let mut children: Vec<Layer> = sorted.iter().rev().map(|&idx| layers.remove(idx)).collect();
+    children.reverse();
+    for child in &mut children {
+        child.x = (*child.x - min_x).into();
+        child.y = (*child.y - min_y).into();
+    }
+
+    let group_idx = top + 1 - sorted.len();
+    layers.insert(group_idx, Layer {
+        id,
+        x: min_x.into(),
+        y: min_y.into(),
+        rotation: 0.0.into(),
+        visible: true.into(),
+        opacity: 1.0.into(),
+        blend_mode: BlendMode::Normal,
+        effects: Vec::new(),
+        mask: None,
+        condition: None,
+        item: Item::Group(GroupItem { width: (max_x - min_x).into(), height: (max_y - min_y).into(), clip: false.into(), children }),
+    });
+    Some(group_idx)
+}
+
+/// Replaces the group at `idx` with its children, keeping them where they appear on the canvas. The group's
+/// rotation, opacity and visibility are folded into each child; its blend mode, effects and clipping are dropped.
+/// Returns the indices of the children, or nothing if the layer is not a group.
+fn ungroup_layer(layers: &mut Vec<Layer>, idx: usize, text_dimensions: &HashMap<String, (f32, f32)>) -> Vec<usize> {
+    if !matches!(layers.get(idx).map(|layer| &layer.item), Some(Item::Group(_))) {
+        return Vec::new();
+    }
+    let group_layer = layers.remove(idx);
+    let Item::Group(group) = group_layer.item else {
+        unreachable!()
+    };
+
+    let (sin, cos) = group_layer.rotation.to_radians().sin_cos();
+    let (group_cx, group_cy) = (*group.width / 2.0, *group.height / 2.0);
+    let count = group.children.len();
+
+    let children = group.children.into_iter().map(|mut child| {
+        // Rotate the child's center around the group's center, then re-anchor its box on it
+        let (w, h) = layer_size(&child, text_dimensions);
+        let (dx, dy) = (*child.x + w / 2.0 - group_cx, *child.y + h / 2.0 - group_cy);
+        child.x = (*group_layer.x + group_cx + dx * cos - dy * sin - w / 2.0).into();
+        child.y = (*group_layer.y + group_cy + dx * sin + dy * cos - h / 2.0).into();
+        child.rotation = (*child.rotation + *group_layer.rotation).into();
+        child.opacity = (*child.opacity * *group_layer.opacity).into();
+        child.visible = (*child.visible && *group_layer.visible).into();
+        child
+    });
+    layers.splice(idx..idx, children);
+
+    (idx..idx + count).collect()
+}
+
+fn text_align_name(align: TextAlign) -> &'static str {
+    match align {
+        TextAlign::Left => "left",
+        TextAlign::Center => "center",
+        TextAlign::Right => "right",
+        TextAlign::Justify => "justify",
+    }
+}
+
+fn vertical_align_name(align: VerticalAlign) -> &'static str {
+    match align {
+        VerticalAlign::Top => "top",
+        VerticalAlign::Middle => "middle",
+        VerticalAlign::Bottom => "bottom",
+    }
+}
+
+fn font_style_name(style: FontStyle) -> &'static str {
+    match style {
+        FontStyle::Normal => "normal",
+        FontStyle::Italic => "italic",
+        FontStyle::Oblique => "oblique",
+    }
+}
+
+fn text_transform_name(transform: TextTransform) -> &'static str {
+    match transform {
+        TextTransform::None => "none",
+        TextTransform::Uppercase => "uppercase",
+        TextTransform::Lowercase => "lowercase",
+        TextTransform::Capitalize => "capitalize",
     }
 }
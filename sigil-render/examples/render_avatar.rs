@@ -9,7 +9,7 @@
 */
 
 
-use sigil_core::{ImageItem, Item, Layer, RectItem, Sigil, TextItem};
+use sigil_core::{BlendMode, FORMAT_VERSION, ImageFit, ImageItem, Item, Layer, RectItem, Sigil, TextItem};
 use sigil_render::Renderer;
 use std::collections::HashMap;
 use std::fs::File;
@@ -32,63 +32,97 @@ fn main() {
     }
 
     let mut avatar_bytes = Vec::new();
-    avatar_img.write_to(&mut std::io::Cursor::new(&mut avatar_bytes), image::ImageOutputFormat::Png).unwrap();
+    avatar_img.write_to(&mut std::io::Cursor::new(&mut avatar_bytes), image::ImageFormat::Png).unwrap();
     println!("Avatar created: {} bytes", avatar_bytes.len());
 
     let mut resources = HashMap::new();
     resources.insert("{avatar}".to_string(), avatar_bytes);
 
     let sigil = Sigil {
+        version: FORMAT_VERSION,
         width: 400,
         height: 200,
-        background: "#222222".to_string(),
+        background: "#222222".into(),
+        variables: Vec::new(),
         layers: vec![
             Layer {
                 id: "card_bg".to_string(),
-                x: 10.0,
-                y: 10.0,
-                rotation: 0.0,
+                x: 10.0.into(),
+                y: 10.0.into(),
+                rotation: 0.0.into(),
+                visible: true.into(),
+                opacity: 1.0.into(),
+                blend_mode: BlendMode::Normal,
+                effects: Vec::new(),
+                mask: None,
+                condition: None,
                 item: Item::Rect(RectItem {
-                    width: 380.0,
-                    height: 180.0,
-                    color: "#333333".to_string(),
-                    border_radius: 16.0,
+                    width: 380.0.into(),
+                    height: 180.0.into(),
+                    color: "#333333".into(),
+                    border_radius: 16.0.into(),
+                    stroke: None,
                 }),
             },
             Layer {
                 id: "user_avatar".to_string(),
-                x: 30.0,
-                y: 50.0,
-                rotation: 0.0,
+                x: 30.0.into(),
+                y: 50.0.into(),
+                rotation: 0.0.into(),
+                visible: true.into(),
+                opacity: 1.0.into(),
+                blend_mode: BlendMode::Normal,
+                effects: Vec::new(),
+                mask: None,
+                condition: None,
                 item: Item::Image(ImageItem {
                     source: "{avatar}".to_string(),
-                    width: 100.0,
-                    height: 100.0,
-                    border_radius: 50.0, // Full circle
+                    width: 100.0.into(),
+                    height: 100.0.into(),
+                    border_radius: 50.0.into(), // Full circle
+                    stroke: None,
+                    fit: ImageFit::Cover,
+                    focal_point: [0.5.into(), 0.5.into()],
+                    crop: None,
+                    filters: Vec::new(),
                 }),
             },
             Layer {
                 id: "username".to_string(),
-                x: 150.0,
-                y: 85.0,
-                rotation: 0.0,
+                x: 150.0.into(),
+                y: 85.0.into(),
+                rotation: 0.0.into(),
+                visible: true.into(),
+                opacity: 1.0.into(),
+                blend_mode: BlendMode::Normal,
+                effects: Vec::new(),
+                mask: None,
+                condition: None,
                 item: Item::Text(TextItem {
                     text: "Test User".to_string(),
-                    font_size: 32.0,
-                    color: "#ffffff".to_string(),
+                    font_size: 32.0.into(),
+                    color: "#ffffff".into(),
                     font_family: "Sans Serif".to_string(),
+                    ..Default::default()
                 }),
             },
             Layer {
                 id: "status".to_string(),
-                x: 150.0,
-                y: 120.0,
-                rotation: 0.0,
+                x: 150.0.into(),
+                y: 120.0.into(),
+                rotation: 0.0.into(),
+                visible: true.into(),
+                opacity: 1.0.into(),
+                blend_mode: BlendMode::Normal,
+                effects: Vec::new(),
+                mask: None,
+                condition: None,
                 item: Item::Text(TextItem {
                     text: "Level 42 Paladin".to_string(),
-                    font_size: 18.0,
-                    color: "#aaaaaa".to_string(),
+                    font_size: 18.0.into(),
+                    color: "#aaaaaa".into(),
                     font_family: "Sans Serif".to_string(),
+                    ..Default::default()
                 }),
             },
         ],
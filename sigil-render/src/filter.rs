@@ -0,0 +1,50 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! Image filters on decoded pixels, before images are premultiplied and cached.
+
+use image::RgbaImage;
+use sigil_core::{apply_color_matrix, ImageFilter};
+
+use crate::raster;
+
+/// Applies `filters` in order. Colors are clamped after each filter, as browsers do.
+pub(crate) fn apply_filters(image: &mut RgbaImage, filters: &[ImageFilter]) {
+    for filter in filters {
+        if let ImageFilter::Blur { radius } = filter {
+            blur(image, *radius);
+        } else if let Some(matrix) = filter.color_matrix() {
+            for pixel in image.pixels_mut() {
+                let channels = pixel.0.map(|c| c as f32 / 255.0);
+                pixel.0 = apply_color_matrix(&matrix, channels).map(|c| (c * 255.0).round() as u8);
+            }
+        }
+    }
+}
+
+/// Blurs with premultiplied colors, so that transparent pixels do not darken the edges.
+fn blur(image: &mut RgbaImage, radius: f32) {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    for pixel in image.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        let premultiply = |c: u8| (c as u32 * a as u32 / 255) as u8;
+        pixel.0 = [premultiply(r), premultiply(g), premultiply(b), a];
+    }
+
+    raster::blur_data(image, width, height, radius);
+
+    for pixel in image.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        if a > 0 {
+            let demultiply = |c: u8| (c as u32 * 255 / a as u32).min(255) as u8;
+            pixel.0 = [demultiply(r), demultiply(g), demultiply(b), a];
+        }
+    }
+}
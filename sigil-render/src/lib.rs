@@ -9,12 +9,18 @@
 */
 
 
-use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping, SwashCache};
-use sigil_core::{Item, Sigil};
+mod filter;
+mod raster;
+
+use cosmic_text::{Align, Attrs, Buffer, Command, Family, FontSystem, Metrics, Shaping, Style, SwashCache, Weight, Wrap};
+use sigil_core::{
+    Background, BlendMode, Effect, EllipseItem, Fill, FittedText, Gradient, GroupItem, Rgba, ShapeStroke, FontStyle, ImageFit, ImageItem, Item, Layer, LayerMask, LineCap, MaskMode, MaskSource, LineItem, PathDataError, PathItem, PathSegment, RectItem, Shadow, Sigil, SliderItem, StrokeAlign, TextAlign, TextItem, TextSpan,
+    ValidationIssue, VerticalAlign, DEFAULT_FONT_WEIGHT, DEFAULT_LINE_HEIGHT,
+};
+use image::RgbaImage;
 use thiserror::Error;
 use tiny_skia::*;
 use std::collections::HashMap;
-use image::GenericImageView;
 
 #[derive(Error, Debug)]
 pub enum RenderError {
@@ -33,6 +39,15 @@ pub enum RenderError {
     #[error("Image decoding error: {0}")]
     ImageError(String),
 
+    #[error("Resource not found: {0}")]
+    ResourceNotFound(String),
+
+    #[error("{0}")]
+    InvalidPathData(#[from] PathDataError),
+
+    #[error("Invalid mask: {0}")]
+    InvalidMask(String),
+
     #[error("Encoding error: {0}")]
     EncodingError(String),
 }
@@ -43,6 +58,9 @@ pub struct Renderer {
     pixmap_buffer: Option<Pixmap>,
     image_cache: HashMap<String, Pixmap>,
     loaded_fonts: std::collections::HashSet<String>,
+    fitted_text: HashMap<String, FittedText>,
+    /// Transform of the groups enclosing the layer being drawn.
+    group_transform: Transform,
 }
 
 impl Default for Renderer {
@@ -59,13 +77,133 @@ impl Renderer {
             pixmap_buffer: None,
             image_cache: HashMap::new(),
             loaded_fonts: std::collections::HashSet::new(),
+            fitted_text: HashMap::new(),
+            group_transform: Transform::identity(),
         }
     }
 
     /// Renders the Sigil to the internal buffer and returns the raw pixel data (Premultiplied RGBA8).
     /// This method reuses the internal buffer to avoid allocation overhead.
     pub fn render_raw(&mut self, sigil: &Sigil, resources: &HashMap<String, Vec<u8>>) -> Result<&[u8], RenderError> {
-        // Load fonts from resources
+        self.load_fonts(resources);
+        self.fitted_text.clear();
+
+        if self.pixmap_buffer.as_ref().is_none_or(|p| p.width() != sigil.width || p.height() != sigil.height) {
+            self.pixmap_buffer = Pixmap::new(sigil.width, sigil.height);
+        }
+
+        let mut pixmap = self.pixmap_buffer.take()
+            .ok_or_else(|| RenderError::PixmapCreationError("Invalid canvas dimensions".into()))?;
+
+        let result = self.draw_sigil(&mut pixmap, sigil, resources);
+        self.pixmap_buffer = Some(pixmap);
+        result?;
+
+        Ok(self.pixmap_buffer.as_ref().unwrap().data())
+    }
+
+    /// Font sizes and text chosen for auto-fit text layers during the last render, keyed by layer id.
+    /// Pass them to [`Sigil::with_fitted_text`] to reproduce the render in the HTML renderers.
+    pub fn fitted_text(&self) -> &HashMap<String, FittedText> {
+        &self.fitted_text
+    }
+
+    /// Checks a resolved sigil with [`Sigil::validate_resolved`], and its fonts and images against the fonts this
+    /// renderer has and `resources`, so problems are reported with their location before rendering.
+    pub fn validate(&mut self, sigil: &Sigil, resources: &HashMap<String, Vec<u8>>) -> Vec<ValidationIssue> {
+        self.load_fonts(resources);
+
+        // Arial is drawn with the default sans-serif font, see `resolve_font_family`
+        let mut fonts = vec!["Arial".to_string()];
+        self.font_system.db().faces().for_each(|face| fonts.extend(face.families.iter().map(|(name, _)| name.clone())));
+        let resources: Vec<String> = resources.keys().cloned().collect();
+
+        let mut issues = sigil.validate_resolved();
+        issues.extend(sigil.validate_resources(&fonts, &resources));
+        issues
+    }
+
+    /// Measures every auto-fit text layer without rendering, loading fonts from `resources` first.
+    pub fn fit_text_layers(&mut self, sigil: &Sigil, resources: &HashMap<String, Vec<u8>>) -> HashMap<String, FittedText> {
+        self.load_fonts(resources);
+
+        let mut fitted = HashMap::new();
+        for layer in sigil.all_layers() {
+            if let Item::Text(text_item) = &layer.item
+                && let Some(result) = self.fit_text(text_item)
+            {
+                fitted.insert(layer.id.clone(), result);
+            }
+        }
+        fitted
+    }
+
+    /// Finds the largest font size within the item's fit range at which its text fits the fit box,
+    /// ellipsizing at the minimum size when allowed. Returns `None` for items without `fit`.
+    pub fn fit_text(&mut self, text_item: &TextItem) -> Option<FittedText> {
+        let fit = text_item.fit.as_ref()?;
+        if text_item.line_height.as_deref().copied().unwrap_or(DEFAULT_LINE_HEIGHT) <= 0.0 {
+            return None;
+        }
+
+        let mut candidate = text_item.clone();
+        candidate.fit = None;
+        candidate.max_width = Some(fit.box_width.clone());
+        candidate.max_height = Some(fit.box_height.clone());
+
+        let min_size = fit.min_font_size.max(1.0);
+        let max_size = fit.max_font_size.max(min_size);
+
+        candidate.set_font_size(max_size);
+        if !self.layout_text(&candidate).overflowed {
+            return Some(fitted_text(&candidate));
+        }
+
+        candidate.set_font_size(min_size);
+        if self.layout_text(&candidate).overflowed {
+            if fit.ellipsis {
+                candidate = self.ellipsize(&candidate);
+            }
+            return Some(fitted_text(&candidate));
+        }
+
+        // `low` always fits and `high` never does.
+        let mut low = min_size;
+        let mut high = max_size;
+        while high - low > FIT_PRECISION {
+            let mid = (low + high) / 2.0;
+            candidate.set_font_size(mid);
+            if self.layout_text(&candidate).overflowed {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+
+        candidate.set_font_size(low);
+        Some(fitted_text(&candidate))
+    }
+
+    /// Returns the item cut to the longest prefix of its text that fits together with a trailing ellipsis.
+    fn ellipsize(&mut self, text_item: &TextItem) -> TextItem {
+        let mut best = truncate_with_ellipsis(text_item, 0);
+        let mut low = 0;
+        let mut high = text_item.plain_text().chars().count();
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            let candidate = truncate_with_ellipsis(text_item, mid);
+
+            if self.layout_text(&candidate).overflowed {
+                high = mid - 1;
+            } else {
+                best = candidate;
+                low = mid;
+            }
+        }
+        best
+    }
+
+    fn load_fonts(&mut self, resources: &HashMap<String, Vec<u8>>) {
         let mut new_fonts = false;
         for (name, data) in resources {
             if (name.ends_with(".ttf") || name.ends_with(".otf") || name.ends_with(".woff2")) && !self.loaded_fonts.contains(name) {
@@ -74,7 +212,7 @@ impl Renderer {
                 new_fonts = true;
             }
         }
-        
+
         if new_fonts {
             // Log all loaded font families for debugging
             println!("[sigil] Loaded font families:");
@@ -86,10 +224,10 @@ impl Renderer {
 
             let mut first_family = None;
             self.font_system.db().faces().for_each(|face| {
-                if first_family.is_none() {
-                    if let Some((name, _)) = face.families.first() {
-                        first_family = Some(name.clone());
-                    }
+                if first_family.is_none()
+                    && let Some((name, _)) = face.families.first()
+                {
+                    first_family = Some(name.clone());
                 }
             });
 
@@ -103,487 +241,1463 @@ impl Renderer {
                 db.set_fantasy_family(family);
             }
         }
+    }
 
-        if self.pixmap_buffer.as_ref().map_or(true, |p| p.width() != sigil.width || p.height() != sigil.height) {
-            self.pixmap_buffer = Pixmap::new(sigil.width, sigil.height);
-        }
+    fn draw_sigil(&mut self, pixmap: &mut Pixmap, sigil: &Sigil, resources: &HashMap<String, Vec<u8>>) -> Result<(), RenderError> {
+        self.draw_background(pixmap, &sigil.background, resources)?;
 
-        let pixmap = self.pixmap_buffer.as_mut()
-            .ok_or_else(|| RenderError::PixmapCreationError("Invalid canvas dimensions".into()))?;
+        self.group_transform = Transform::identity();
+        self.draw_layers(pixmap, &sigil.layers, resources)
+    }
 
-        if let Some(color) = parse_color(&sigil.background) {
-            pixmap.fill(color);
-        } else {
-            let bg_cache_key = format!("bg_{}_{}_{}", sigil.background, sigil.width, sigil.height);
-            let bg_pixmap = if let Some(cached) = self.image_cache.get(&bg_cache_key) {
-                Some(cached)
-            } else if let Some(image_bytes) = resources.get(&sigil.background) {
-                if let Ok(dynamic_image) = image::load_from_memory(image_bytes) {
-                    let target_width = sigil.width;
-                    let target_height = sigil.height;
-                    
-                    let resized = dynamic_image.resize_to_fill(
-                        target_width,
-                        target_height,
-                        image::imageops::FilterType::Lanczos3
-                    );
-                    
-                    let rgba_image = resized.to_rgba8();
-                    let mut pixels = Vec::with_capacity((target_width * target_height * 4) as usize);
-
-                    for pixel in rgba_image.pixels() {
-                        let r = pixel[0];
-                        let g = pixel[1];
-                        let b = pixel[2];
-                        let a = pixel[3];
-
-                        let a_f = a as f32 / 255.0;
-                        pixels.push((r as f32 * a_f) as u8);
-                        pixels.push((g as f32 * a_f) as u8);
-                        pixels.push((b as f32 * a_f) as u8);
-                        pixels.push(a);
-                    }
-                    
-                    if let Some(pixmap) = Pixmap::from_vec(pixels, IntSize::from_wh(target_width, target_height).unwrap()) {
-                        self.image_cache.insert(bg_cache_key.clone(), pixmap);
-                        self.image_cache.get(&bg_cache_key)
-                    } else {
-                        None
-                    }
-                } else {
-                    None
+    fn draw_background(&mut self, pixmap: &mut Pixmap, background: &Background, resources: &HashMap<String, Vec<u8>>) -> Result<(), RenderError> {
+        let (width, height) = (pixmap.width() as f32, pixmap.height() as f32);
+        pixmap.fill(Color::TRANSPARENT);
+
+        match background {
+            Background::Solid(color) => {
+                pixmap.fill(parse_color(color).ok_or_else(|| RenderError::InvalidColorFormat(color.clone()))?);
+            }
+            Background::Gradient(gradient) => {
+                let paint = fill_paint(&Fill::Gradient(gradient.clone()), width, height)?;
+                pixmap.fill_rect(pixmap_rect(pixmap), &paint, Transform::identity(), None);
+            }
+            Background::Image(image) => {
+                let item = ImageItem {
+                    source: image.source.clone(),
+                    width: width.into(),
+                    height: height.into(),
+                    border_radius: 0.0.into(),
+                    stroke: None,
+                    fit: image.fit,
+                    focal_point: image.focal_point.clone(),
+                    crop: None,
+                    filters: image.filters.clone(),
+                };
+                let fitted = self.fitted_image(&item, resources)?;
+                pixmap.draw_pixmap(0, 0, fitted.as_ref(), &PixmapPaint::default(), Transform::identity(), None);
+                if let Some(overlay) = &image.overlay {
+                    let paint = fill_paint(overlay, width, height)?;
+                    pixmap.fill_rect(pixmap_rect(pixmap), &paint, Transform::identity(), None);
                 }
+            }
+            Background::Pattern(pattern) => {
+                let data = resources.get(&pattern.source).ok_or_else(|| RenderError::ResourceNotFound(pattern.source.clone()))?;
+                let [tile_width, tile_height] = match &pattern.tile_size {
+                    Some([width, height]) => [**width, **height],
+                    None => natural_size(&pattern.source, data)?,
+                };
+                let tile = ImageItem {
+                    source: pattern.source.clone(),
+                    width: tile_width.into(),
+                    height: tile_height.into(),
+                    border_radius: 0.0.into(),
+                    stroke: None,
+                    fit: ImageFit::Fill,
+                    focal_point: [0.5.into(), 0.5.into()],
+                    crop: None,
+                    filters: Vec::new(),
+                };
+                let tile = self.fitted_image(&tile, resources)?;
+                let paint = Paint {
+                    shader: Pattern::new(tile.as_ref(), SpreadMode::Repeat, FilterQuality::Bilinear, 1.0, Transform::identity()),
+                    ..Default::default()
+                };
+                pixmap.fill_rect(pixmap_rect(pixmap), &paint, Transform::identity(), None);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw_layers(&mut self, pixmap: &mut Pixmap, layers: &[Layer], resources: &HashMap<String, Vec<u8>>) -> Result<(), RenderError> {
+        // Layers used as another layer's mask are only drawn into that mask
+        let mask_layers: std::collections::HashSet<&str> = layers.iter().filter_map(|layer| layer.mask.as_ref()?.layer_id()).collect();
+
+        for layer in layers {
+            // Hidden layers can still serve as masks, as they do in the HTML renderers
+            if !*layer.visible || mask_layers.contains(layer.id.as_str()) {
+                continue;
+            }
+            if layer.is_opaque_normal() {
+                self.draw_layer(pixmap, layer, resources)?;
             } else {
-                None
-            };
+                let mask = match &layer.mask {
+                    Some(mask) => Some(self.layer_mask(pixmap, mask, layers, resources)?),
+                    None => None,
+                };
+                self.draw_composited_layer(pixmap, layer, mask.as_ref(), resources)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Renders a mask source at the canvas size, in the current group, as coverage for the masked layer.
+    fn layer_mask(&mut self, pixmap: &Pixmap, mask: &LayerMask, siblings: &[Layer], resources: &HashMap<String, Vec<u8>>) -> Result<Mask, RenderError> {
+        let shape_layer;
+        let source = match &mask.source {
+            MaskSource::Layer { id } => siblings
+                .iter()
+                .find(|layer| &layer.id == id)
+                .ok_or_else(|| RenderError::InvalidMask(format!("No layer with id '{}'", id)))?,
+            MaskSource::Shape { x, y, rotation, item } => {
+                shape_layer = Layer {
+                    id: String::new(),
+                    x: (*x).into(),
+                    y: (*y).into(),
+                    rotation: (*rotation).into(),
+                    visible: true.into(),
+                    opacity: 1.0.into(),
+                    blend_mode: BlendMode::Normal,
+                    effects: Vec::new(),
+                    mask: None,
+                    condition: None,
+                    item: item.as_ref().clone(),
+                };
+                &shape_layer
+            }
+        };
+
+        let mut source_pixmap = Pixmap::new(pixmap.width(), pixmap.height())
+            .ok_or_else(|| RenderError::PixmapCreationError("Invalid layer dimensions".into()))?;
+        self.draw_layer(&mut source_pixmap, source, resources)?;
+
+        Ok(match mask.mode {
+            MaskMode::Alpha => Mask::from_pixmap(source_pixmap.as_ref(), MaskType::Alpha),
+            MaskMode::Luminance => Mask::from_pixmap(source_pixmap.as_ref(), MaskType::Luminance),
+            MaskMode::Clip => Mask::from_pixmap(raster::solid_coverage(&source_pixmap).as_ref(), MaskType::Alpha),
+        })
+    }
+
+    /// Draws a translucent, blended, effected or masked layer into an intermediate pixmap first, so the layer's
+    /// effects, opacity, blend mode and mask apply to the item as a whole rather than to each primitive.
+    fn draw_composited_layer(&mut self, pixmap: &mut Pixmap, layer: &Layer, mask: Option<&Mask>, resources: &HashMap<String, Vec<u8>>) -> Result<(), RenderError> {
+        let opacity = layer.opacity.clamp(0.0, 1.0);
+        if opacity <= 0.0 {
+            return Ok(());
+        }
+
+        let mut layer_pixmap = Pixmap::new(pixmap.width(), pixmap.height())
+            .ok_or_else(|| RenderError::PixmapCreationError("Invalid layer dimensions".into()))?;
+        self.draw_layer(&mut layer_pixmap, layer, resources)?;
+        if !layer.effects.is_empty() {
+            let rotation = *layer.rotation + self.group_rotation();
+            layer_pixmap = apply_effects(pixmap, layer_pixmap, layer, rotation, opacity)?;
+        }
+
+        let paint = PixmapPaint {
+            opacity,
+            blend_mode: tiny_skia_blend_mode(layer.blend_mode),
+            quality: FilterQuality::Nearest,
+        };
+        pixmap.draw_pixmap(0, 0, layer_pixmap.as_ref(), &paint, Transform::identity(), mask);
+
+        Ok(())
+    }
+
+    fn draw_layer(&mut self, pixmap: &mut Pixmap, layer: &Layer, resources: &HashMap<String, Vec<u8>>) -> Result<(), RenderError> {
+        match &layer.item {
+            Item::Rect(rect) => self.draw_rect(pixmap, layer, rect),
+            Item::Text(text_item) => self.draw_text(pixmap, layer, text_item),
+            Item::Image(img) => self.draw_image(pixmap, layer, img, resources),
+            Item::Slider(slider) => self.draw_slider(pixmap, layer, slider),
+            Item::Ellipse(ellipse) => self.draw_ellipse(pixmap, layer, ellipse),
+            Item::Line(line) => self.draw_line(pixmap, layer, line),
+            Item::Polygon(polygon) => self.draw_polygon(pixmap, layer, &polygon.points, &polygon.color, polygon.stroke.as_ref()),
+            Item::Star(star) => self.draw_polygon(pixmap, layer, &star.vertices(), &star.color, star.stroke.as_ref()),
+            Item::RegularPolygon(polygon) => self.draw_polygon(pixmap, layer, &polygon.vertices(), &polygon.color, polygon.stroke.as_ref()),
+            Item::Path(path) => self.draw_path(pixmap, layer, path),
+            Item::Group(group) => self.draw_group(pixmap, layer, group, resources),
+            // Resolving turns repeaters into groups; without entries there is nothing to draw
+            Item::Repeat(_) => Ok(()),
+        }
+    }
+
+    /// Draws the children with the group's transform composed onto their own. A clipping group draws
+    /// them onto a copy of the canvas, so their blend modes still see the layers below, and copies
+    /// back only what falls inside its box.
+    fn draw_group(&mut self, pixmap: &mut Pixmap, layer: &Layer, group: &GroupItem, resources: &HashMap<String, Vec<u8>>) -> Result<(), RenderError> {
+        let transform = self.layer_transform(layer, *group.width, *group.height);
+        let parent_transform = std::mem::replace(&mut self.group_transform, transform);
+
+        let result = if *group.clip {
+            self.draw_clipped_children(pixmap, group, transform, resources)
+        } else {
+            self.draw_layers(pixmap, &group.children, resources)
+        };
+
+        self.group_transform = parent_transform;
+        result
+    }
+
+    fn draw_clipped_children(&mut self, pixmap: &mut Pixmap, group: &GroupItem, transform: Transform, resources: &HashMap<String, Vec<u8>>) -> Result<(), RenderError> {
+        let Some(rect) = Rect::from_xywh(0.0, 0.0, *group.width, *group.height) else {
+            return Ok(());
+        };
+        let mut mask = Mask::new(pixmap.width(), pixmap.height())
+            .ok_or_else(|| RenderError::PixmapCreationError("Invalid layer dimensions".into()))?;
+        mask.fill_path(&PathBuilder::from_rect(rect), FillRule::Winding, true, transform);
+
+        let mut children = pixmap.clone();
+        self.draw_layers(&mut children, &group.children, resources)?;
+
+        let paint = PixmapPaint { blend_mode: tiny_skia::BlendMode::Source, ..Default::default() };
+        pixmap.draw_pixmap(0, 0, children.as_ref(), &paint, Transform::identity(), Some(&mask));
+
+        Ok(())
+    }
+
+    /// Places an item of the given size at the layer position, rotated around its center, inside any enclosing groups.
+    fn layer_transform(&self, layer: &Layer, w: f32, h: f32) -> Transform {
+        layer_transform(layer, w, h).post_concat(self.group_transform)
+    }
+
+    /// Rotation of the enclosing groups in degrees.
+    fn group_rotation(&self) -> f32 {
+        self.group_transform.ky.atan2(self.group_transform.sx).to_degrees()
+    }
+
+    fn draw_rect(&mut self, pixmap: &mut Pixmap, layer: &Layer, rect: &RectItem) -> Result<(), RenderError> {
+        let layer_transform = self.layer_transform(layer, *rect.width, *rect.height);
+
+        let paint = fill_paint(&rect.color, *rect.width, *rect.height)?;
+
+        let r = Rect::from_xywh(0.0, 0.0, *rect.width, *rect.height)
+            .ok_or_else(|| {
+                RenderError::InvalidDimensions("Rect width/height must be > 0".into())
+            })?;
+
+        if *rect.border_radius > 0.0 {
+            let path = create_rounded_rect_path(r, *rect.border_radius);
+            if let Some(p) = path {
+                pixmap.fill_path(
+                    &p,
+                    &paint,
+                    FillRule::Winding,
+                    layer_transform,
+                    None,
+                );
+            }
+        } else {
+            pixmap.fill_rect(r, &paint, layer_transform, None);
+        }
+
+        if let Some(stroke) = &rect.stroke {
+            draw_shape_stroke(pixmap, stroke, r, *rect.border_radius, layer_transform)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_text(&mut self, pixmap: &mut Pixmap, layer: &Layer, text_item: &TextItem) -> Result<(), RenderError> {
+        // Validate the fill up front; the gradient geometry depends on the laid out box
+        fill_paint(&text_item.color, 1.0, 1.0)?;
+        for color in text_item.spans.iter().filter_map(|span| span.color.as_ref()) {
+            parse_color(color).ok_or_else(|| RenderError::InvalidColorFormat(color.clone()))?;
+        }
+
+        let fitted_item;
+        let text_item = match self.fit_text(text_item) {
+            Some(fitted) => {
+                let mut item = text_item.clone();
+                item.apply_fit(&fitted);
+                self.fitted_text.insert(layer.id.clone(), fitted);
+                fitted_item = item;
+                &fitted_item
+            }
+            None => text_item,
+        };
+        // cosmic-text cannot lay out lines without height
+        if *text_item.font_size <= 0.0 || text_item.line_height_px() <= 0.0 {
+            return Err(RenderError::InvalidDimensions(format!(
+                "Text layer '{}' needs a positive font size and line height, got {} and {}",
+                layer.id,
+                *text_item.font_size,
+                text_item.line_height_px()
+            )));
+        }
+
+        let stroke = match &text_item.stroke {
+            Some(stroke) if *stroke.width > 0.0 => {
+                let color = parse_color(&stroke.color)
+                    .ok_or_else(|| RenderError::InvalidColorFormat(stroke.color.clone()))?;
+                Some((color, *stroke.width))
+            }
+            _ => None,
+        };
+
+        let layout = self.layout_text(text_item);
+        let layer_transform = self.layer_transform(layer, layout.width, layout.height);
+        let text_paint = fill_paint(&text_item.color, layout.width, layout.height)?;
+        let outline = if stroke.is_some() { self.glyph_outlines(&layout) } else { None };
+        let outline_stroke = |color: Option<Color>| {
+            outline.as_ref().zip(stroke).map(|(path, (stroke_color, width))| (path, color.unwrap_or(stroke_color), width))
+        };
+
+        if let Some(shadow) = &text_item.shadow {
+            let shadow_color = parse_color(&shadow.color)
+                .ok_or_else(|| RenderError::InvalidColorFormat(shadow.color.clone()))?;
+
+            let shadow_transform = layer_transform.pre_translate(*shadow.offset_x, *shadow.offset_y);
+            let stroke_pad = stroke.map_or(0.0, |(_, width)| width);
+            // The layout box does not cover descenders or overhanging glyphs, so pad generously.
+            let pad = *shadow.blur * 1.5 + stroke_pad + text_item.max_font_size();
+            let bounds = Rect::from_xywh(0.0, 0.0, layout.width.max(1.0), layout.height.max(1.0))
+                .and_then(|rect| raster::device_bounds(rect, shadow_transform, pad, pixmap.width(), pixmap.height()));
+
+            if let Some(bounds) = bounds
+                && let Some(mut shadow_pixmap) = Pixmap::new(bounds.width(), bounds.height())
+            {
+                let local_transform = shadow_transform.post_translate(-bounds.x() as f32, -bounds.y() as f32);
+                let mut shadow_paint = Paint::default();
+                shadow_paint.set_color(shadow_color);
+                self.draw_text_body(&mut shadow_pixmap, &layout, text_item, &shadow_paint, outline_stroke(Some(shadow_color)), local_transform);
+                raster::tint(&mut shadow_pixmap, shadow_color);
+                raster::blur(&mut shadow_pixmap, *shadow.blur);
 
-            if let Some(bg_pixmap) = bg_pixmap {
                 pixmap.draw_pixmap(
-                    0, 0,
-                    bg_pixmap.as_ref(),
+                    bounds.x(), bounds.y(),
+                    shadow_pixmap.as_ref(),
                     &PixmapPaint::default(),
                     Transform::identity(),
                     None,
                 );
-            } else {
-                // Fallback to black if neither color nor resource found
-                pixmap.fill(Color::BLACK);
             }
         }
 
-        for layer in &sigil.layers {
-            let (w, h) = match &layer.item {
-                Item::Rect(r) => (r.width, r.height),
-                Item::Image(i) => (i.width, i.height),
-                Item::Text(_) => (0.0, 0.0),
-                Item::Slider(s) => (s.width, s.height),
+        self.draw_text_body(pixmap, &layout, text_item, &text_paint, outline_stroke(None), layer_transform);
+
+        Ok(())
+    }
+
+    /// Draws the glyphs, decorations and the optional `(outline, color, width)` stroke of a laid out text item.
+    ///
+    /// Glyphs without a span color are painted with `text_paint`; decorations of gradient text use its first stop color.
+    fn draw_text_body(
+        &mut self,
+        pixmap: &mut Pixmap,
+        layout: &TextLayout,
+        text_item: &TextItem,
+        text_paint: &Paint,
+        stroke: Option<(&Path, Color, f32)>,
+        transform: Transform,
+    ) {
+        let text_color = match text_paint.shader {
+            Shader::SolidColor(color) => {
+                self.draw_glyphs(pixmap, layout, GlyphFill::All(color), transform);
+                color
+            }
+            _ => {
+                self.draw_shaded_glyphs(pixmap, layout, text_item, text_paint, transform);
+                parse_color(text_item.color.primary_color()).unwrap_or(Color::BLACK)
+            }
+        };
+
+        if *text_item.underline || *text_item.strikethrough {
+            draw_decorations(pixmap, layout, text_item, text_color, transform);
+        }
+
+        if let Some((outline, color, width)) = stroke {
+            let mut paint = Paint::default();
+            paint.set_color(color);
+            paint.anti_alias = true;
+
+            let stroke = Stroke {
+                width,
+                line_join: LineJoin::Round,
+                ..Default::default()
             };
+            pixmap.stroke_path(outline, &paint, &stroke, transform, None);
+        }
+    }
 
-            let cx = w / 2.0;
-            let cy = h / 2.0;
-
-            let layer_transform = Transform::identity()
-                .post_translate(-cx, -cy)
-                .post_rotate(layer.rotation)
-                .post_translate(cx + layer.x, cy + layer.y);
-
-            match &layer.item {
-                Item::Rect(rect) => {
-                    let color = parse_color(&rect.color)
-                        .ok_or_else(|| RenderError::InvalidColorFormat(rect.color.clone()))?;
-
-                    let mut paint = Paint::default();
-                    paint.set_color(color);
-                    paint.anti_alias = true;
-
-                    let r = Rect::from_xywh(0.0, 0.0, rect.width, rect.height)
-                        .ok_or_else(|| {
-                            RenderError::InvalidDimensions("Rect width/height must be > 0".into())
-                        })?;
-
-                    if rect.border_radius > 0.0 {
-                        let path = create_rounded_rect_path(r, rect.border_radius);
-                        if let Some(p) = path {
-                            pixmap.fill_path(
-                                &p,
-                                &paint,
-                                FillRule::Winding,
-                                layer_transform,
-                                None,
-                            );
+    /// Builds a single path from the vector outlines of all visible glyphs, in text box coordinates.
+    fn glyph_outlines(&mut self, layout: &TextLayout) -> Option<Path> {
+        let mut pb = PathBuilder::new();
+
+        for run in layout.buffer.layout_runs().take(layout.visible_lines) {
+            for glyph in run.glyphs {
+                let physical_glyph = glyph.physical((0., 0.), 1.0);
+                let Some(commands) = self.swash_cache.get_outline_commands(&mut self.font_system, physical_glyph.cache_key) else {
+                    continue;
+                };
+
+                let origin_x = glyph.x + glyph.font_size * glyph.x_offset;
+                let origin_y = layout.offset_y + run.line_y + glyph.y - glyph.font_size * glyph.y_offset;
+                // Outlines are y-up around the glyph origin
+                let point = |x: f32, y: f32| (origin_x + x, origin_y - y);
+
+                for command in commands {
+                    match *command {
+                        Command::MoveTo(p) => {
+                            let (x, y) = point(p.x, p.y);
+                            pb.move_to(x, y);
                         }
-                    } else {
-                        pixmap.fill_rect(r, &paint, layer_transform, None);
+                        Command::LineTo(p) => {
+                            let (x, y) = point(p.x, p.y);
+                            pb.line_to(x, y);
+                        }
+                        Command::QuadTo(c, p) => {
+                            let (cx, cy) = point(c.x, c.y);
+                            let (x, y) = point(p.x, p.y);
+                            pb.quad_to(cx, cy, x, y);
+                        }
+                        Command::CurveTo(c1, c2, p) => {
+                            let (c1x, c1y) = point(c1.x, c1.y);
+                            let (c2x, c2y) = point(c2.x, c2.y);
+                            let (x, y) = point(p.x, p.y);
+                            pb.cubic_to(c1x, c1y, c2x, c2y, x, y);
+                        }
+                        Command::Close => pb.close(),
                     }
                 }
-                Item::Text(text_item) => {
-                    let text_color = parse_color(&text_item.color).ok_or_else(|| {
-                        RenderError::InvalidColorFormat(text_item.color.clone())
-                    })?;
-
-                    let metrics = Metrics::new(text_item.font_size, text_item.font_size * 1.2);
-                    let mut buffer = Buffer::new(&mut self.font_system, metrics);
+            }
+        }
 
-                    let mut attrs = Attrs::new();
+        pb.finish()
+    }
 
-                    let family_list: Vec<&str> = text_item.font_family.split(',').map(|s| s.trim()).collect();
-                    let mut family = Family::SansSerif;
+    /// Shapes a text item into a buffer, applying its wrapping width, alignment and line limits.
+    /// Spans are shaped as one rich text paragraph, overriding the item's attributes where set.
+    fn layout_text(&mut self, text_item: &TextItem) -> TextLayout {
+        let metrics = Metrics::new(*text_item.font_size, text_item.line_height_px());
+        let mut buffer = Buffer::new(&mut self.font_system, metrics);
+
+        let font = self.resolve_font_family(&text_item.font_family);
+        let style = match text_item.font_style {
+            FontStyle::Normal => Style::Normal,
+            FontStyle::Italic => Style::Italic,
+            FontStyle::Oblique => Style::Oblique,
+        };
+        let mut attrs = Attrs::new()
+            .family(font.family())
+            .weight(Weight(text_item.font_weight.as_deref().copied().unwrap_or(DEFAULT_FONT_WEIGHT)))
+            .style(style);
+        if *text_item.letter_spacing != 0.0 {
+            // cosmic-text expects tracking in EM
+            attrs = attrs.letter_spacing(*text_item.letter_spacing / *text_item.font_size);
+        }
 
-                    for f in family_list {
-                        match f.to_lowercase().as_str() {
-                            "arial" | "sans-serif" | "sans serif" | "system-ui" | "-apple-system" => {
-                                family = Family::SansSerif;
-                                break;
-                            }
-                            "serif" => {
-                                family = Family::Serif;
-                                break;
-                            }
-                            "mono" | "monospace" => {
-                                family = Family::Monospace;
-                                break;
-                            }
-                            _ => {
-                                // Check if font exists in system
-                                // Normalize font name by removing spaces for comparison
-                                let normalized_query = f.to_lowercase().replace(' ', "");
-                                let mut found_name: Option<String> = None;
-                                
-                                self.font_system.db().faces().for_each(|face| {
-                                    for (name, _) in &face.families {
-                                        let normalized_name = name.to_lowercase().replace(' ', "");
-                                        if normalized_name == normalized_query || name.to_lowercase() == f.to_lowercase() {
-                                            found_name = Some(name.clone());
-                                        }
-                                    }
-                                });
-
-                                if let Some(ref name) = found_name {
-                                    println!("[sigil] Matched font '{}' -> '{}'", f, name);
-                                    family = Family::Name(Box::leak(name.clone().into_boxed_str()));
-                                    break;
-                                }
-                            }
-                        }
+        let spans = text_item.display_spans();
+        let span_fonts: Vec<Option<FontChoice>> = spans
+            .iter()
+            .map(|span| span.font_family.as_deref().map(|family| self.resolve_font_family(family)))
+            .collect();
+        let line_height = text_item.line_height.as_deref().copied().unwrap_or(DEFAULT_LINE_HEIGHT);
+        let span_attrs: Vec<Attrs> = spans
+            .iter()
+            .zip(&span_fonts)
+            .map(|(span, font)| {
+                let mut span_attrs = attrs.clone();
+                if let Some(font) = font {
+                    span_attrs = span_attrs.family(font.family());
+                }
+                if let Some(weight) = span.font_weight.as_deref() {
+                    span_attrs = span_attrs.weight(Weight(*weight));
+                }
+                if let Some(color) = span.color.as_deref().and_then(parse_color) {
+                    let color = color.to_color_u8();
+                    span_attrs = span_attrs.color(cosmic_text::Color::rgba(color.red(), color.green(), color.blue(), color.alpha()));
+                }
+                // Spans without a usable size keep the item's
+                if let Some(&size) = span.font_size.as_deref().filter(|size| **size > 0.0) {
+                    span_attrs = span_attrs.metrics(Metrics::new(size, size * line_height));
+                    if *text_item.letter_spacing != 0.0 {
+                        span_attrs = span_attrs.letter_spacing(*text_item.letter_spacing / size);
                     }
+                }
+                span_attrs
+            })
+            .collect();
+
+        let max_width = text_item.max_width.as_deref().copied();
+        let max_height = text_item.max_height.as_deref().copied();
+        let wrap = if max_width.is_some() { Wrap::WordOrGlyph } else { Wrap::None };
+        buffer.set_wrap(&mut self.font_system, wrap);
+        buffer.set_size(&mut self.font_system, max_width, None);
+
+        let align = match text_item.align {
+            TextAlign::Left => Align::Left,
+            TextAlign::Center => Align::Center,
+            TextAlign::Right => Align::Right,
+            TextAlign::Justify => Align::Justified,
+        };
 
-                    // Log if we're using fallback
-                    match family {
-                        Family::SansSerif => println!("[sigil] Using SansSerif fallback for font_family: {}", text_item.font_family),
-                        _ => {}
-                    }
+        buffer.set_rich_text(
+            &mut self.font_system,
+            spans.iter().zip(span_attrs).map(|(span, span_attrs)| (span.text.as_str(), span_attrs)),
+            &attrs,
+            Shaping::Advanced,
+            Some(align),
+        );
+
+        buffer.shape_until_scroll(&mut self.font_system, false);
+
+        let max_lines = text_item.max_lines.as_deref().map_or(usize::MAX, |n| *n as usize);
+        let mut visible_lines = 0;
+        let mut total_lines = 0;
+        let mut content_width: f32 = 0.0;
+        let mut content_height: f32 = 0.0;
+        let mut overflowed = false;
+
+        for run in buffer.layout_runs() {
+            total_lines += 1;
+            if total_lines > max_lines
+                || max_height.is_some_and(|max_height| run.line_top + run.line_height > max_height + 0.5)
+            {
+                overflowed = true;
+                break;
+            }
+            visible_lines += 1;
+            content_width = content_width.max(run.line_w);
+            content_height = run.line_top + run.line_height;
+            if max_width.is_some_and(|max_width| run.line_w > max_width + 0.5) {
+                overflowed = true;
+            }
+        }
+
+        let width = max_width.unwrap_or(content_width);
+        let height = max_height.unwrap_or(content_height);
+        let offset_y = match text_item.vertical_align {
+            VerticalAlign::Top => 0.0,
+            VerticalAlign::Middle => (height - content_height) / 2.0,
+            VerticalAlign::Bottom => height - content_height,
+        };
+
+        TextLayout { buffer, visible_lines, width, height, offset_y, overflowed }
+    }
+
+    fn resolve_font_family(&self, font_family: &str) -> FontChoice {
+        let family_list: Vec<&str> = font_family.split(',').map(|s| s.trim()).collect();
+        let mut font = FontChoice::Generic(Family::SansSerif);
 
-                    attrs = attrs.family(family);
-
-                    buffer.set_text(
-                        &mut self.font_system,
-                        &text_item.text,
-                        &attrs,
-                        Shaping::Advanced,
-                        None,
-                    );
-
-                    buffer.shape_until_scroll(&mut self.font_system, false);
-
-                    let mut glyphs_drawn = 0;
-
-                    for run in buffer.layout_runs() {
-                        for glyph in run.glyphs {
-                            let physical_glyph = glyph.physical((0., 0.), 1.0);
-
-                            if let Some(image) =
-                                self.swash_cache.get_image(&mut self.font_system, physical_glyph.cache_key)
-                            {
-                                let width = image.placement.width;
-                                let height = image.placement.height;
-
-                                if width == 0 || height == 0 {
-                                    continue;
-                                }
-
-                                let glyph_x = (physical_glyph.x as f32) + (image.placement.left as f32);
-                                let glyph_y = run.line_y + (physical_glyph.y as f32) - (image.placement.top as f32);
-
-                                let size = IntSize::from_wh(width, height).unwrap();
-                                
-                                let mut pixels = Vec::with_capacity((width * height * 4) as usize);
-                                
-                                if image.data.len() == (width * height) as usize {
-                                    let r_f = text_color.red();
-                                    let g_f = text_color.green();
-                                    let b_f = text_color.blue();
-                                    let a_f = text_color.alpha();
-
-                                    for mask_val in image.data.iter() {
-                                        let mask_alpha = *mask_val as f32 / 255.0;
-                                        let final_alpha = a_f * mask_alpha;
-                                        
-                                        pixels.push((r_f * final_alpha * 255.0) as u8);
-                                        pixels.push((g_f * final_alpha * 255.0) as u8);
-                                        pixels.push((b_f * final_alpha * 255.0) as u8);
-                                        pixels.push((final_alpha * 255.0) as u8);
-                                    }
-                                } else if image.data.len() == (width * height * 4) as usize {
-                                    for chunk in image.data.chunks(4) {
-                                        let r = chunk[0];
-                                        let g = chunk[1];
-                                        let b = chunk[2];
-                                        let a = chunk[3];
-                                        
-                                        let a_f = a as f32 / 255.0;
-                                        pixels.push((r as f32 * a_f) as u8);
-                                        pixels.push((g as f32 * a_f) as u8);
-                                        pixels.push((b as f32 * a_f) as u8);
-                                        pixels.push(a);
-                                    }
-                                } else {
-                                    println!("Unknown image format from swash. Length: {}", image.data.len());
-                                    continue;
-                                }
-
-                                if let Some(glyph_pixmap) = Pixmap::from_vec(pixels, size) {
-                                    let glyph_transform = layer_transform
-                                        .pre_translate(glyph_x, glyph_y);
-
-                                    pixmap.draw_pixmap(
-                                        0, 0,
-                                        glyph_pixmap.as_ref(),
-                                        &PixmapPaint::default(),
-                                        glyph_transform,
-                                        None,
-                                    );
-                                    glyphs_drawn += 1;
-                                }
-                            } else {
-                                println!("Failed to get image from cache for a glyph!");
+        for f in family_list {
+            match f.to_lowercase().as_str() {
+                "arial" | "sans-serif" | "sans serif" | "system-ui" | "-apple-system" => {
+                    font = FontChoice::Generic(Family::SansSerif);
+                    break;
+                }
+                "serif" => {
+                    font = FontChoice::Generic(Family::Serif);
+                    break;
+                }
+                "mono" | "monospace" => {
+                    font = FontChoice::Generic(Family::Monospace);
+                    break;
+                }
+                _ => {
+                    // Check if font exists in system
+                    // Normalize font name by removing spaces for comparison
+                    let normalized_query = f.to_lowercase().replace(' ', "");
+                    let mut found_name: Option<String> = None;
+                    
+                    self.font_system.db().faces().for_each(|face| {
+                        for (name, _) in &face.families {
+                            let normalized_name = name.to_lowercase().replace(' ', "");
+                            if normalized_name == normalized_query || name.to_lowercase() == f.to_lowercase() {
+                                found_name = Some(name.clone());
                             }
                         }
+                    });
+
+                    if let Some(name) = found_name {
+                        println!("[sigil] Matched font '{}' -> '{}'", f, name);
+                        font = FontChoice::Named(name);
+                        break;
                     }
                 }
+            }
+        }
 
+        // Log if we're using fallback
+        if let FontChoice::Generic(Family::SansSerif) = font {
+            println!("[sigil] Using SansSerif fallback for font_family: {}", font_family);
+        }
 
-                Item::Image(img) => {
-                    let cache_key = format!("{}_{}_{}", img.source, img.width, img.height);
-                    
-                    let image_pixmap = if let Some(cached) = self.image_cache.get(&cache_key) {
-                        Some(cached)
-                    } else if let Some(image_bytes) = resources.get(&img.source) {
-                        let dynamic_image = match image::load_from_memory(image_bytes) {
-                            Ok(img) => img,
-                            Err(e) => {
-                                println!("Failed to decode image: {}", e);
-                                continue;
-                            }
-                        };
+        font
+    }
 
-                        let target_width = img.width as u32;
-                        let target_height = img.height as u32;
+    /// Paints the glyphs without a span color through a coverage mask, then draws the span colored ones on top.
+    fn draw_shaded_glyphs(&mut self, pixmap: &mut Pixmap, layout: &TextLayout, text_item: &TextItem, paint: &Paint, transform: Transform) {
+        if let Some(mut coverage) = Pixmap::new(pixmap.width(), pixmap.height()) {
+            self.draw_glyphs(&mut coverage, layout, GlyphFill::Unstyled(Color::WHITE), transform);
+            let mask = Mask::from_pixmap(coverage.as_ref(), MaskType::Alpha);
 
-                        if target_width == 0 || target_height == 0 {
-                             println!("Skipping image with 0 dimensions");
-                             continue;
-                        }
+            // Glyphs can overhang the layout box, so cover a margin around it as well
+            let pad = text_item.max_font_size();
+            if let Some(rect) = Rect::from_ltrb(-pad, -pad, layout.width + pad, layout.height + pad) {
+                pixmap.fill_rect(rect, paint, transform, Some(&mask));
+            }
+        }
 
-                        let resized = dynamic_image.resize_exact(
-                            target_width,
-                            target_height,
-                            image::imageops::FilterType::Lanczos3
-                        );
+        self.draw_glyphs(pixmap, layout, GlyphFill::Styled, transform);
+    }
 
-                        let rgba_image = resized.to_rgba8();
-                        let mut pixels = Vec::with_capacity((target_width * target_height * 4) as usize);
+    fn draw_glyphs(&mut self, pixmap: &mut Pixmap, layout: &TextLayout, fill: GlyphFill, layer_transform: Transform) {
+        for run in layout.buffer.layout_runs().take(layout.visible_lines) {
+            for glyph in run.glyphs {
+                let span_color = glyph.color_opt.map(|c| Color::from_rgba8(c.r(), c.g(), c.b(), c.a()));
+                let glyph_color = match (fill, span_color) {
+                    (GlyphFill::All(_), Some(color)) | (GlyphFill::Styled, Some(color)) => color,
+                    (GlyphFill::All(color), None) | (GlyphFill::Unstyled(color), None) => color,
+                    _ => continue,
+                };
+
+                let physical_glyph = glyph.physical((0., 0.), 1.0);
+
+                if let Some(image) =
+                    self.swash_cache.get_image(&mut self.font_system, physical_glyph.cache_key)
+                {
+                    let width = image.placement.width;
+                    let height = image.placement.height;
+
+                    if width == 0 || height == 0 {
+                        continue;
+                    }
 
-                        for pixel in rgba_image.pixels() {
-                            let r = pixel[0];
-                            let g = pixel[1];
-                            let b = pixel[2];
-                            let a = pixel[3];
+                    let glyph_x = (physical_glyph.x as f32) + (image.placement.left as f32);
+                    let glyph_y = layout.offset_y + run.line_y + (physical_glyph.y as f32) - (image.placement.top as f32);
 
+                    let size = IntSize::from_wh(width, height).unwrap();
+                    
+                    let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+                    
+                    if image.data.len() == (width * height) as usize {
+                        let r_f = glyph_color.red();
+                        let g_f = glyph_color.green();
+                        let b_f = glyph_color.blue();
+                        let a_f = glyph_color.alpha();
+
+                        for mask_val in image.data.iter() {
+                            let mask_alpha = *mask_val as f32 / 255.0;
+                            let final_alpha = a_f * mask_alpha;
+                            
+                            pixels.push((r_f * final_alpha * 255.0) as u8);
+                            pixels.push((g_f * final_alpha * 255.0) as u8);
+                            pixels.push((b_f * final_alpha * 255.0) as u8);
+                            pixels.push((final_alpha * 255.0) as u8);
+                        }
+                    } else if image.data.len() == (width * height * 4) as usize {
+                        for chunk in image.data.chunks(4) {
+                            let r = chunk[0];
+                            let g = chunk[1];
+                            let b = chunk[2];
+                            let a = chunk[3];
+                            
                             let a_f = a as f32 / 255.0;
                             pixels.push((r as f32 * a_f) as u8);
                             pixels.push((g as f32 * a_f) as u8);
                             pixels.push((b as f32 * a_f) as u8);
                             pixels.push(a);
                         }
-
-                        if let Some(pixmap) = Pixmap::from_vec(pixels, IntSize::from_wh(target_width, target_height).unwrap()) {
-                            self.image_cache.insert(cache_key.clone(), pixmap);
-                            self.image_cache.get(&cache_key)
-                        } else {
-                            None
-                        }
-                    } else {
-                        println!("Warning: Resource '{}' not found", img.source);
-                        None
-                    };
-
-                    if let Some(image_pixmap) = image_pixmap {
-                        let pattern = Pattern::new(
-                            image_pixmap.as_ref(),
-                            SpreadMode::Pad,
-                            FilterQuality::Bilinear,
-                            1.0,
-                            Transform::identity(),
-                        );
-
-                            let mut paint = Paint::default();
-                            paint.shader = pattern;
-                            paint.anti_alias = true;
-
-                            let draw_rect = Rect::from_xywh(0.0, 0.0, img.width, img.height).unwrap();
-
-                            let path = if img.border_radius > 0.0 {
-                                create_rounded_rect_path(draw_rect, img.border_radius)
-                            } else {
-                                let mut pb = PathBuilder::new();
-                                pb.push_rect(draw_rect);
-                                pb.finish()
-                            };
-
-                            if let Some(p) = path {
-                                pixmap.fill_path(
-                                    &p,
-                                    &paint,
-                                    FillRule::Winding,
-                                    layer_transform,
-                                    None,
-                                );
-                            }
-                        }
-                }
-                Item::Slider(slider) => {
-                    let bg_color = parse_color(&slider.background_color)
-                        .ok_or_else(|| RenderError::InvalidColorFormat(slider.background_color.clone()))?;
-                    let fill_color = parse_color(&slider.fill_color)
-                        .ok_or_else(|| RenderError::InvalidColorFormat(slider.fill_color.clone()))?;
-
-                    let mut bg_paint = Paint::default();
-                    bg_paint.set_color(bg_color);
-                    bg_paint.anti_alias = true;
-
-                    let bg_rect = Rect::from_xywh(0.0, 0.0, slider.width, slider.height)
-                        .ok_or_else(|| RenderError::InvalidDimensions("Slider width/height must be > 0".into()))?;
-
-                    if slider.border_radius > 0.0 {
-                        let path = create_rounded_rect_path(bg_rect, slider.border_radius);
-                        if let Some(p) = path {
-                            pixmap.fill_path(&p, &bg_paint, FillRule::Winding, layer_transform, None);
-                        }
                     } else {
-                        pixmap.fill_rect(bg_rect, &bg_paint, layer_transform, None);
+                        println!("Unknown image format from swash. Length: {}", image.data.len());
+                        continue;
                     }
 
-                    let fill_width = (slider.value / slider.max_value.max(1.0)) * slider.width;
-                    if fill_width > 0.0 {
-                        let mut fill_paint = Paint::default();
-                        fill_paint.set_color(fill_color);
-                        fill_paint.anti_alias = true;
-
-                        let fill_rect = Rect::from_xywh(0.0, 0.0, fill_width, slider.height)
-                            .ok_or_else(|| RenderError::InvalidDimensions("Fill width/height must be > 0".into()))?;
+                    if let Some(glyph_pixmap) = Pixmap::from_vec(pixels, size) {
+                        let glyph_transform = layer_transform
+                            .pre_translate(glyph_x, glyph_y);
 
-                        if slider.border_radius > 0.0 {
-                            let path = create_rounded_rect_path(fill_rect, slider.border_radius);
-                            if let Some(p) = path {
-                                pixmap.fill_path(&p, &fill_paint, FillRule::Winding, layer_transform, None);
-                            }
-                        } else {
-                            pixmap.fill_rect(fill_rect, &fill_paint, layer_transform, None);
-                        }
+                        pixmap.draw_pixmap(
+                            0, 0,
+                            glyph_pixmap.as_ref(),
+                            &PixmapPaint::default(),
+                            glyph_transform,
+                            None,
+                        );
                     }
+                } else {
+                    println!("Failed to get image from cache for a glyph!");
                 }
             }
         }
-
-        Ok(self.pixmap_buffer.as_ref().unwrap().data())
     }
 
-    pub fn render(&mut self, sigil: &Sigil, resources: &HashMap<String, Vec<u8>>) -> Result<Vec<u8>, RenderError> {
-        self.render_raw(sigil, resources)?;
-        
-        self.pixmap_buffer.as_ref().unwrap()
-            .encode_png()
-            .map_err(|e| RenderError::EncodingError(e.to_string()))
+    /// The image laid out in its box and filtered, cached by everything that affects the result.
+    fn fitted_image(&mut self, img: &ImageItem, resources: &HashMap<String, Vec<u8>>) -> Result<&Pixmap, RenderError> {
+        let cache_key = format!(
+            "{}_{}_{}_{:?}_{:?}_{:?}_{:?}",
+            img.source, img.width, img.height, img.fit, img.focal_point, img.crop, img.filters
+        );
+        if !self.image_cache.contains_key(&cache_key) {
+            let image_bytes = resources.get(&img.source).ok_or_else(|| RenderError::ResourceNotFound(img.source.clone()))?;
+            let fitted = fit_image(image_bytes, img)?;
+            self.image_cache.insert(cache_key.clone(), fitted);
+        }
+
+        Ok(&self.image_cache[&cache_key])
     }
-}
 
-fn create_rounded_rect_path(rect: Rect, radius: f32) -> Option<Path> {
-    let mut pb = PathBuilder::new();
+    fn draw_image(&mut self, pixmap: &mut Pixmap, layer: &Layer, img: &ImageItem, resources: &HashMap<String, Vec<u8>>) -> Result<(), RenderError> {
+        let layer_transform = self.layer_transform(layer, *img.width, *img.height);
+        let image_pixmap = self.fitted_image(img, resources)?;
+        let pattern = Pattern::new(
+            image_pixmap.as_ref(),
+            SpreadMode::Pad,
+            FilterQuality::Bilinear,
+            1.0,
+            Transform::identity(),
+        );
+
+        let paint = Paint {
+            shader: pattern,
+            anti_alias: true,
+            ..Default::default()
+        };
 
-    let x = rect.x();
-    let y = rect.y();
-    let w = rect.width();
-    let h = rect.height();
+        let draw_rect = Rect::from_xywh(0.0, 0.0, *img.width, *img.height).unwrap();
 
-    let r = radius.min(w / 2.0).min(h / 2.0);
+        let path = if *img.border_radius > 0.0 {
+            create_rounded_rect_path(draw_rect, *img.border_radius)
+        } else {
+            let mut pb = PathBuilder::new();
+            pb.push_rect(draw_rect);
+            pb.finish()
+        };
 
-    pb.move_to(x + r, y);
-    pb.line_to(x + w - r, y);
-    pb.quad_to(x + w, y, x + w, y + r);
-    pb.line_to(x + w, y + h - r);
-    pb.quad_to(x + w, y + h, x + w - r, y + h);
-    pb.line_to(x + r, y + h);
-    pb.quad_to(x, y + h, x, y + h - r);
-    pb.line_to(x, y + r);
-    pb.quad_to(x, y, x + r, y);
-    pb.close();
+        if let Some(p) = path {
+            pixmap.fill_path(
+                &p,
+                &paint,
+                FillRule::Winding,
+                layer_transform,
+                None,
+            );
+        }
 
-    pb.finish()
-}
+        if let Some(stroke) = &img.stroke
+            && let Some(rect) = Rect::from_xywh(0.0, 0.0, *img.width, *img.height)
+        {
+            draw_shape_stroke(pixmap, stroke, rect, *img.border_radius, layer_transform)?;
+        }
 
-fn parse_color(hex: &str) -> Option<Color> {
-    if !hex.starts_with('#') || hex.len() != 7 {
-        return None;
+        Ok(())
     }
 
-    let r = u8::from_str_radix(&hex[1..3], 16).ok()?;
-    let g = u8::from_str_radix(&hex[3..5], 16).ok()?;
-    let b = u8::from_str_radix(&hex[5..7], 16).ok()?;
+    fn draw_slider(&mut self, pixmap: &mut Pixmap, layer: &Layer, slider: &SliderItem) -> Result<(), RenderError> {
+        let layer_transform = self.layer_transform(layer, *slider.width, *slider.height);
 
-    Some(Color::from_rgba8(r, g, b, 255))
-}
+        let bg_color = parse_color(&slider.background_color)
+            .ok_or_else(|| RenderError::InvalidColorFormat(slider.background_color.clone()))?;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use sigil_core::{Layer, RectItem, TextItem};
+        let mut bg_paint = Paint::default();
+        bg_paint.set_color(bg_color);
+        bg_paint.anti_alias = true;
 
-    #[allow(unused_imports)]
-    use std::fs::File;
-    #[allow(unused_imports)]
-    use std::io::Write;
+        let bg_rect = Rect::from_xywh(0.0, 0.0, *slider.width, *slider.height)
+            .ok_or_else(|| RenderError::InvalidDimensions("Slider width/height must be > 0".into()))?;
 
-    #[test]
-    fn test_render_rect_and_text() {
-        let sigil = Sigil {
+        if *slider.border_radius > 0.0 {
+            let path = create_rounded_rect_path(bg_rect, *slider.border_radius);
+            if let Some(p) = path {
+                pixmap.fill_path(&p, &bg_paint, FillRule::Winding, layer_transform, None);
+            }
+        } else {
+            pixmap.fill_rect(bg_rect, &bg_paint, layer_transform, None);
+        }
+
+        let fill_width = (*slider.value / slider.max_value.max(1.0)) * *slider.width;
+        if fill_width > 0.0 {
+            let fill_paint = fill_paint(&slider.fill_color, fill_width, *slider.height)?;
+
+            let fill_rect = Rect::from_xywh(0.0, 0.0, fill_width, *slider.height)
+                .ok_or_else(|| RenderError::InvalidDimensions("Fill width/height must be > 0".into()))?;
+
+            if *slider.border_radius > 0.0 {
+                let path = create_rounded_rect_path(fill_rect, *slider.border_radius);
+                if let Some(p) = path {
+                    pixmap.fill_path(&p, &fill_paint, FillRule::Winding, layer_transform, None);
+                }
+            } else {
+                pixmap.fill_rect(fill_rect, &fill_paint, layer_transform, None);
+            }
+        }
+
+        if let Some(stroke) = &slider.stroke {
+            draw_shape_stroke(pixmap, stroke, bg_rect, *slider.border_radius, layer_transform)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_ellipse(&mut self, pixmap: &mut Pixmap, layer: &Layer, ellipse: &EllipseItem) -> Result<(), RenderError> {
+        let layer_transform = self.layer_transform(layer, *ellipse.width, *ellipse.height);
+
+        let path = Rect::from_xywh(0.0, 0.0, *ellipse.width, *ellipse.height)
+            .and_then(PathBuilder::from_oval)
+            .ok_or_else(|| RenderError::InvalidDimensions("Ellipse width/height must be > 0".into()))?;
+
+        let paint = fill_paint(&ellipse.color, *ellipse.width, *ellipse.height)?;
+        pixmap.fill_path(&path, &paint, FillRule::Winding, layer_transform, None);
+
+        if let Some(stroke) = &ellipse.stroke {
+            draw_path_stroke(pixmap, stroke, &path, FillRule::Winding, layer_transform)?;
+        }
+
+        Ok(())
+    }
+
+    /// Fills and strokes a closed polygon; the fill is laid out over the item's box.
+    fn draw_polygon(&mut self, pixmap: &mut Pixmap, layer: &Layer, points: &[[f32; 2]], fill: &Fill, stroke: Option<&ShapeStroke>) -> Result<(), RenderError> {
+        let (width, height) = layer.item.size().unwrap_or_default();
+        let layer_transform = self.layer_transform(layer, width, height);
+
+        let mut pb = PathBuilder::new();
+        for (i, &[x, y]) in points.iter().enumerate() {
+            if i == 0 {
+                pb.move_to(x, y);
+            } else {
+                pb.line_to(x, y);
+            }
+        }
+        pb.close();
+        // Fewer than two distinct points make no shape
+        let Some(path) = pb.finish() else {
+            return Ok(());
+        };
+
+        let paint = fill_paint(fill, width, height)?;
+        pixmap.fill_path(&path, &paint, FillRule::Winding, layer_transform, None);
+
+        if let Some(stroke) = stroke {
+            draw_path_stroke(pixmap, stroke, &path, FillRule::Winding, layer_transform)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_path(&mut self, pixmap: &mut Pixmap, layer: &Layer, path_item: &PathItem) -> Result<(), RenderError> {
+        let layer_transform = self.layer_transform(layer, *path_item.width, *path_item.height);
+
+        let mut pb = PathBuilder::new();
+        for segment in path_item.segments()? {
+            match segment {
+                PathSegment::MoveTo(x, y) => pb.move_to(x, y),
+                PathSegment::LineTo(x, y) => pb.line_to(x, y),
+                PathSegment::QuadTo(x1, y1, x, y) => pb.quad_to(x1, y1, x, y),
+                PathSegment::CubicTo(x1, y1, x2, y2, x, y) => pb.cubic_to(x1, y1, x2, y2, x, y),
+                PathSegment::Close => pb.close(),
+            }
+        }
+        let Some(path) = pb.finish() else {
+            return Ok(());
+        };
+
+        let fill_rule = match path_item.fill_rule {
+            sigil_core::FillRule::Nonzero => FillRule::Winding,
+            sigil_core::FillRule::EvenOdd => FillRule::EvenOdd,
+        };
+
+        if let Some(fill) = &path_item.fill {
+            let paint = fill_paint(fill, *path_item.width, *path_item.height)?;
+            pixmap.fill_path(&path, &paint, fill_rule, layer_transform, None);
+        }
+
+        if let Some(stroke) = &path_item.stroke {
+            draw_path_stroke(pixmap, stroke, &path, fill_rule, layer_transform)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_line(&mut self, pixmap: &mut Pixmap, layer: &Layer, line: &LineItem) -> Result<(), RenderError> {
+        let (width, height) = line.size();
+        let layer_transform = self.layer_transform(layer, width, height);
+
+        let color = parse_color(&line.color)
+            .ok_or_else(|| RenderError::InvalidColorFormat(line.color.clone()))?;
+        if *line.stroke_width <= 0.0 {
+            return Ok(());
+        }
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(*line.x1, *line.y1);
+        pb.line_to(*line.x2, *line.y2);
+        let Some(path) = pb.finish() else {
+            return Ok(());
+        };
+
+        let mut paint = Paint::default();
+        paint.set_color(color);
+        paint.anti_alias = true;
+
+        let style = Stroke {
+            width: *line.stroke_width,
+            line_cap: match line.cap {
+                LineCap::Butt => tiny_skia::LineCap::Butt,
+                LineCap::Round => tiny_skia::LineCap::Round,
+                LineCap::Square => tiny_skia::LineCap::Square,
+            },
+            dash: stroke_dash(&line.dash),
+            ..Default::default()
+        };
+        pixmap.stroke_path(&path, &paint, &style, layer_transform, None);
+
+        Ok(())
+    }
+
+    pub fn render(&mut self, sigil: &Sigil, resources: &HashMap<String, Vec<u8>>) -> Result<Vec<u8>, RenderError> {
+        self.render_raw(sigil, resources)?;
+        
+        self.pixmap_buffer.as_ref().unwrap()
+            .encode_png()
+            .map_err(|e| RenderError::EncodingError(e.to_string()))
+    }
+}
+
+/// Font size step at which the auto-fit search stops.
+const FIT_PRECISION: f32 = 0.5;
+const ELLIPSIS: &str = "…";
+
+fn fitted_text(text_item: &TextItem) -> FittedText {
+    FittedText {
+        font_size: *text_item.font_size,
+        text: text_item.text.clone(),
+        spans: text_item.spans.clone(),
+    }
+}
+
+/// Keeps the first `chars` characters of the item's text, or of its spans, and appends an ellipsis
+/// styled like the last kept character.
+fn truncate_with_ellipsis(text_item: &TextItem, chars: usize) -> TextItem {
+    let mut item = text_item.clone();
+
+    if item.spans.is_empty() {
+        let prefix: String = item.text.chars().take(chars).collect();
+        item.text = format!("{}{}", prefix.trim_end(), ELLIPSIS);
+        return item;
+    }
+
+    let mut remaining = chars;
+    let mut spans = Vec::new();
+    for span in &text_item.spans {
+        let len = span.text.chars().count();
+        spans.push(TextSpan { text: span.text.chars().take(remaining).collect(), ..span.clone() });
+        if len >= remaining {
+            break;
+        }
+        remaining -= len;
+    }
+
+    if let Some(last) = spans.last_mut() {
+        last.text = format!("{}{}", last.text.trim_end(), ELLIPSIS);
+    }
+    item.spans = spans;
+    item
+}
+
+struct TextLayout {
+    buffer: Buffer,
+    /// Number of layout runs that fit within `max_lines` and `max_height`.
+    visible_lines: usize,
+    width: f32,
+    height: f32,
+    /// Vertical offset of the first line inside the text box.
+    offset_y: f32,
+    /// Whether lines were dropped or a line is wider than the box.
+    overflowed: bool,
+}
+
+enum FontChoice {
+    Generic(Family<'static>),
+    Named(String),
+}
+
+impl FontChoice {
+    fn family(&self) -> Family<'_> {
+        match self {
+            FontChoice::Generic(family) => *family,
+            FontChoice::Named(name) => Family::Name(name),
+        }
+    }
+}
+
+/// Draws underline and strikethrough bars under or through the glyphs of each visible line.
+fn draw_decorations(pixmap: &mut Pixmap, layout: &TextLayout, text_item: &TextItem, color: Color, transform: Transform) {
+    let thickness = (*text_item.font_size / 14.0).max(1.0);
+
+    let mut paint = Paint::default();
+    paint.set_color(color);
+    paint.anti_alias = true;
+
+    for run in layout.buffer.layout_runs().take(layout.visible_lines) {
+        let Some(start) = run.glyphs.iter().map(|g| g.x).reduce(f32::min) else {
+            continue;
+        };
+        let end = run.glyphs.iter().map(|g| g.x + g.w).fold(start, f32::max);
+        let baseline = layout.offset_y + run.line_y;
+
+        let mut offsets = Vec::with_capacity(2);
+        if *text_item.underline {
+            offsets.push(*text_item.font_size * 0.12);
+        }
+        if *text_item.strikethrough {
+            offsets.push(-*text_item.font_size * 0.28);
+        }
+
+        for offset in offsets {
+            if let Some(rect) = Rect::from_xywh(start, baseline + offset - thickness / 2.0, end - start, thickness) {
+                pixmap.fill_rect(rect, &paint, transform, None);
+            }
+        }
+    }
+}
+
+/// Applies the layer's effects to its offscreen rendering `content`, returning what should be composited.
+///
+/// Drop shadows go beneath the content and inner shadows on top of it, the first listed shadow on top,
+/// as with CSS `box-shadow`. A background blur blurs `backdrop` in place, within the layer's shape.
+///
+/// Drop shadows follow the content's alpha. Inner shadows and background blurs use its whole shape,
+/// so they stay at full strength on translucent fills like frosted glass cards.
+fn apply_effects(backdrop: &mut Pixmap, content: Pixmap, layer: &Layer, rotation: f32, opacity: f32) -> Result<Pixmap, RenderError> {
+    let shape_pixmap = raster::solid_coverage(&content);
+    let shape = Mask::from_pixmap(shape_pixmap.as_ref(), MaskType::Alpha);
+    let mut result = Pixmap::new(content.width(), content.height())
+        .ok_or_else(|| RenderError::PixmapCreationError("Invalid layer dimensions".into()))?;
+
+    for effect in layer.effects.iter().rev() {
+        if let Effect::DropShadow(shadow) = effect {
+            let shadow = shadow_pixmap(&content, shadow, rotation, false)?;
+            result.draw_pixmap(0, 0, shadow.as_ref(), &PixmapPaint::default(), Transform::identity(), None);
+        }
+    }
+
+    result.draw_pixmap(0, 0, content.as_ref(), &PixmapPaint::default(), Transform::identity(), None);
+
+    for effect in layer.effects.iter().rev() {
+        if let Effect::InnerShadow(shadow) = effect {
+            let shadow = shadow_pixmap(&shape_pixmap, shadow, rotation, true)?;
+            result.draw_pixmap(0, 0, shadow.as_ref(), &PixmapPaint::default(), Transform::identity(), Some(&shape));
+        }
+    }
+
+    for effect in &layer.effects {
+        match effect {
+            Effect::LayerBlur { radius } => raster::blur(&mut result, **radius),
+            Effect::BackgroundBlur { radius } => {
+                let mut blurred = backdrop.clone();
+                raster::blur(&mut blurred, **radius);
+                let paint = PixmapPaint { opacity, ..Default::default() };
+                backdrop.draw_pixmap(0, 0, blurred.as_ref(), &paint, Transform::identity(), Some(&shape));
+            }
+            Effect::DropShadow(_) | Effect::InnerShadow(_) => {}
+        }
+    }
+
+    Ok(result)
+}
+
+/// Renders a shadow of `content`'s alpha. An inset shadow is cast by everything outside it,
+/// to be clipped to the layer's shape afterwards.
+fn shadow_pixmap(content: &Pixmap, shadow: &Shadow, rotation: f32, inset: bool) -> Result<Pixmap, RenderError> {
+    let color = parse_color(&shadow.color).ok_or_else(|| RenderError::InvalidColorFormat(shadow.color.clone()))?;
+    let mut pixmap = Pixmap::new(content.width(), content.height())
+        .ok_or_else(|| RenderError::PixmapCreationError("Invalid layer dimensions".into()))?;
+
+    // Offsets follow the layer's rotation, as they do for a rotated element in CSS.
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let offset = Transform::from_translate(
+        *shadow.offset_x * cos - *shadow.offset_y * sin,
+        *shadow.offset_x * sin + *shadow.offset_y * cos,
+    );
+    let paint = PixmapPaint { quality: FilterQuality::Bilinear, ..Default::default() };
+    pixmap.draw_pixmap(0, 0, content.as_ref(), &paint, offset, None);
+
+    if inset {
+        raster::invert_alpha(&mut pixmap);
+    }
+    raster::spread(&mut pixmap, *shadow.spread);
+    raster::tint(&mut pixmap, color);
+    raster::blur(&mut pixmap, *shadow.blur);
+
+    Ok(pixmap)
+}
+
+fn tiny_skia_blend_mode(blend_mode: BlendMode) -> tiny_skia::BlendMode {
+    match blend_mode {
+        BlendMode::Normal => tiny_skia::BlendMode::SourceOver,
+        BlendMode::Multiply => tiny_skia::BlendMode::Multiply,
+        BlendMode::Screen => tiny_skia::BlendMode::Screen,
+        BlendMode::Overlay => tiny_skia::BlendMode::Overlay,
+        BlendMode::Darken => tiny_skia::BlendMode::Darken,
+        BlendMode::Lighten => tiny_skia::BlendMode::Lighten,
+        BlendMode::ColorDodge => tiny_skia::BlendMode::ColorDodge,
+        BlendMode::ColorBurn => tiny_skia::BlendMode::ColorBurn,
+        BlendMode::HardLight => tiny_skia::BlendMode::HardLight,
+        BlendMode::SoftLight => tiny_skia::BlendMode::SoftLight,
+        BlendMode::Difference => tiny_skia::BlendMode::Difference,
+        BlendMode::Exclusion => tiny_skia::BlendMode::Exclusion,
+        BlendMode::Hue => tiny_skia::BlendMode::Hue,
+        BlendMode::Saturation => tiny_skia::BlendMode::Saturation,
+        BlendMode::Color => tiny_skia::BlendMode::Color,
+        BlendMode::Luminosity => tiny_skia::BlendMode::Luminosity,
+    }
+}
+
+/// Places an item of the given size at the layer position, rotated around its center.
+fn layer_transform(layer: &Layer, w: f32, h: f32) -> Transform {
+    let cx = w / 2.0;
+    let cy = h / 2.0;
+
+    Transform::identity()
+        .post_translate(-cx, -cy)
+        .post_rotate(*layer.rotation)
+        .post_translate(cx + *layer.x, cy + *layer.y)
+}
+
+fn create_rounded_rect_path(rect: Rect, radius: f32) -> Option<Path> {
+    let mut pb = PathBuilder::new();
+
+    let x = rect.x();
+    let y = rect.y();
+    let w = rect.width();
+    let h = rect.height();
+
+    let r = radius.min(w / 2.0).min(h / 2.0);
+
+    pb.move_to(x + r, y);
+    pb.line_to(x + w - r, y);
+    pb.quad_to(x + w, y, x + w, y + r);
+    pb.line_to(x + w, y + h - r);
+    pb.quad_to(x + w, y + h, x + w - r, y + h);
+    pb.line_to(x + r, y + h);
+    pb.quad_to(x, y + h, x, y + h - r);
+    pb.line_to(x, y + r);
+    pb.quad_to(x, y, x + r, y);
+    pb.close();
+
+    pb.finish()
+}
+
+/// Strokes the outline of a (rounded) rect, moving the path in or out so the stroke sits on the requested side of the edge.
+fn draw_shape_stroke(pixmap: &mut Pixmap, stroke: &ShapeStroke, rect: Rect, radius: f32, transform: Transform) -> Result<(), RenderError> {
+    if *stroke.width <= 0.0 {
+        return Ok(());
+    }
+
+    let color = parse_color(&stroke.color)
+        .ok_or_else(|| RenderError::InvalidColorFormat(stroke.color.clone()))?;
+
+    let offset = stroke.center_offset();
+    let Some(outline) = Rect::from_ltrb(rect.left() - offset, rect.top() - offset, rect.right() + offset, rect.bottom() + offset) else {
+        return Ok(());
+    };
+    // Corners stay concentric with the shape's, square corners stay square
+    let radius = if radius > 0.0 { (radius + offset).max(0.0) } else { 0.0 };
+    let Some(path) = create_rounded_rect_path(outline, radius) else {
+        return Ok(());
+    };
+
+    let mut paint = Paint::default();
+    paint.set_color(color);
+    paint.anti_alias = true;
+
+    let style = Stroke {
+        width: *stroke.width,
+        line_join: LineJoin::Miter,
+        dash: stroke_dash(&stroke.dash),
+        ..Default::default()
+    };
+    pixmap.stroke_path(&path, &paint, &style, transform, None);
+
+    Ok(())
+}
+
+/// Strokes a closed path. A path cannot simply be moved in or out like a rect, so inside and outside strokes
+/// are drawn twice as wide and masked to the inside or outside of the shape.
+fn draw_path_stroke(pixmap: &mut Pixmap, stroke: &ShapeStroke, path: &Path, fill_rule: FillRule, transform: Transform) -> Result<(), RenderError> {
+    if *stroke.width <= 0.0 {
+        return Ok(());
+    }
+
+    let color = parse_color(&stroke.color)
+        .ok_or_else(|| RenderError::InvalidColorFormat(stroke.color.clone()))?;
+
+    let mask = match stroke.align {
+        StrokeAlign::Center => None,
+        align => {
+            let mut mask = Mask::new(pixmap.width(), pixmap.height())
+                .ok_or_else(|| RenderError::PixmapCreationError("Invalid stroke mask dimensions".into()))?;
+            mask.fill_path(path, fill_rule, true, transform);
+            if align == StrokeAlign::Outside {
+                mask.invert();
+            }
+            Some(mask)
+        }
+    };
+
+    let mut paint = Paint::default();
+    paint.set_color(color);
+    paint.anti_alias = true;
+
+    let style = Stroke {
+        width: if mask.is_some() { *stroke.width * 2.0 } else { *stroke.width },
+        line_join: LineJoin::Miter,
+        dash: stroke_dash(&stroke.dash),
+        ..Default::default()
+    };
+    pixmap.stroke_path(path, &paint, &style, transform, mask.as_ref());
+
+    Ok(())
+}
+
+/// A tiny-skia dash pattern, or `None` for a solid stroke.
+fn stroke_dash(dash: &[f32]) -> Option<StrokeDash> {
+    let mut dash = dash.to_vec();
+    if dash.len() % 2 == 1 {
+        // An odd list repeats to make an even one, as in SVG
+        dash.extend_from_within(..);
+    }
+    StrokeDash::new(dash, 0.0)
+}
+
+/// Which glyphs `draw_glyphs` draws and in what color.
+#[derive(Clone, Copy)]
+enum GlyphFill {
+    /// Every glyph, in its span color or else the given color.
+    All(Color),
+    /// Only glyphs without a span color.
+    Unstyled(Color),
+    /// Only glyphs with a span color.
+    Styled,
+}
+
+/// Builds an anti-aliased paint for `fill`, laying gradients out over a `width` x `height` box at the origin.
+fn fill_paint(fill: &Fill, width: f32, height: f32) -> Result<Paint<'static>, RenderError> {
+    let mut paint = Paint {
+        anti_alias: true,
+        ..Default::default()
+    };
+
+    let gradient = match fill {
+        Fill::Solid(color) => {
+            paint.set_color(parse_color(color).ok_or_else(|| RenderError::InvalidColorFormat(color.clone()))?);
+            return Ok(paint);
+        }
+        Fill::Gradient(gradient) => gradient,
+    };
+
+    let colors = gradient
+        .stops()
+        .iter()
+        .map(|stop| parse_color(&stop.color).ok_or_else(|| RenderError::InvalidColorFormat(stop.color.clone())))
+        .collect::<Result<Vec<_>, _>>()?;
+    let Some(&first_color) = colors.first() else {
+        return Err(RenderError::InvalidColorFormat("gradient without color stops".into()));
+    };
+    let stops = gradient
+        .stops()
+        .iter()
+        .zip(colors)
+        .map(|(stop, color)| tiny_skia::GradientStop::new(stop.offset, color))
+        .collect();
+
+    let shader = match gradient {
+        Gradient::Linear(linear) => {
+            let (start, end) = linear.endpoints(width, height);
+            LinearGradient::new(
+                Point::from_xy(start[0], start[1]),
+                Point::from_xy(end[0], end[1]),
+                stops,
+                SpreadMode::Pad,
+                Transform::identity(),
+            )
+        }
+        Gradient::Radial(radial) => {
+            let (center, radius) = radial.geometry(width, height);
+            let center = Point::from_xy(center[0], center[1]);
+            RadialGradient::new(center, center, radius, stops, SpreadMode::Pad, Transform::identity())
+        }
+    };
+
+    // Degenerate boxes have no gradient geometry; fall back to the first stop like a browser would
+    paint.shader = shader.unwrap_or(Shader::SolidColor(first_color));
+    Ok(paint)
+}
+
+/// Whether an image resource is SVG, by its file extension or, for sources without one, by its content.
+fn is_svg(source: &str, data: &[u8]) -> bool {
+    let source = source.to_ascii_lowercase();
+    if source.ends_with(".svg") || source.ends_with(".svgz") {
+        return true;
+    }
+
+    let head = String::from_utf8_lossy(&data[..data.len().min(256)]);
+    let head = head.trim_start();
+    head.starts_with("<svg") || (head.starts_with("<?xml") && head.contains("<svg"))
+}
+
+/// The image laid out in its box by `fit`, `focal_point` and `crop`, then filtered: a pixmap of the box size that is
+/// transparent where the image does not reach, so that it can be drawn like an image stretched to the box.
+fn fit_image(data: &[u8], img: &ImageItem) -> Result<Pixmap, RenderError> {
+    let (content, [x, y, _, _]) = if is_svg(&img.source, data) {
+        rasterize_svg(data, img)?
+    } else {
+        decode_image(data, img)?
+    };
+
+    let mut fitted = RgbaImage::new(*img.width as u32, *img.height as u32);
+    image::imageops::replace(&mut fitted, &content, x.round() as i64, y.round() as i64);
+    filter::apply_filters(&mut fitted, &img.filters);
+
+    premultiplied_pixmap(&fitted).ok_or_else(|| RenderError::InvalidDimensions("Image width/height must be > 0".into()))
+}
+
+/// Decodes a raster image and scales the shown part of it to its fitted size, with the fitted rectangle.
+fn decode_image(data: &[u8], img: &ImageItem) -> Result<(RgbaImage, [f32; 4]), RenderError> {
+    let image = image::load_from_memory(data).map_err(|e| RenderError::ImageError(e.to_string()))?;
+    let [crop_x, crop_y, crop_width, crop_height] = img.source_rect(image.width() as f32, image.height() as f32);
+    let rect = img.fit_rect(crop_width, crop_height);
+    let (width, height) = (rect[2].round() as u32, rect[3].round() as u32);
+    if width == 0 || height == 0 {
+        return Err(RenderError::InvalidDimensions("Fitted image width/height must be > 0".into()));
+    }
+
+    // Round the crop's edges rather than its size, so it stays within the image
+    let (left, top) = (crop_x.round() as u32, crop_y.round() as u32);
+    let right = ((crop_x + crop_width).round() as u32).min(image.width());
+    let bottom = ((crop_y + crop_height).round() as u32).min(image.height());
+    if right <= left || bottom <= top {
+        return Err(RenderError::InvalidDimensions("Image crop width/height must be at least one pixel".into()));
+    }
+
+    let cropped = image.crop_imm(left, top, right - left, bottom - top);
+    Ok((cropped.resize_exact(width, height, image::imageops::FilterType::Lanczos3).to_rgba8(), rect))
+}
+
+/// Renders the shown part of an SVG at its fitted size, with the fitted rectangle. Vector images are
+/// rasterized at the target size instead of being scaled as pixels.
+fn rasterize_svg(data: &[u8], img: &ImageItem) -> Result<(RgbaImage, [f32; 4]), RenderError> {
+    let tree = resvg::usvg::Tree::from_data(data, &resvg::usvg::Options::default())
+        .map_err(|e| RenderError::ImageError(e.to_string()))?;
+    let size = tree.size();
+    let [crop_x, crop_y, crop_width, crop_height] = img.source_rect(size.width(), size.height());
+    if crop_width <= 0.0 || crop_height <= 0.0 {
+        return Err(RenderError::InvalidDimensions("Image crop width/height must be > 0".into()));
+    }
+    let rect = img.fit_rect(crop_width, crop_height);
+    let mut pixmap = Pixmap::new(rect[2].round() as u32, rect[3].round() as u32)
+        .ok_or_else(|| RenderError::InvalidDimensions("Fitted image width/height must be > 0".into()))?;
+
+    let transform = Transform::from_scale(pixmap.width() as f32 / crop_width, pixmap.height() as f32 / crop_height)
+        .pre_translate(-crop_x, -crop_y);
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+    let pixels = pixmap.pixels().iter().flat_map(|pixel| {
+        let color = pixel.demultiply();
+        [color.red(), color.green(), color.blue(), color.alpha()]
+    });
+    let image = RgbaImage::from_vec(pixmap.width(), pixmap.height(), pixels.collect()).unwrap();
+    Ok((image, rect))
+}
+
+/// The size an image resource has of its own: its pixel size, or the size an SVG declares.
+fn natural_size(source: &str, data: &[u8]) -> Result<[f32; 2], RenderError> {
+    if is_svg(source, data) {
+        let tree = resvg::usvg::Tree::from_data(data, &resvg::usvg::Options::default())
+            .map_err(|e| RenderError::ImageError(e.to_string()))?;
+        return Ok([tree.size().width(), tree.size().height()]);
+    }
+
+    let (width, height) = image::ImageReader::new(std::io::Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| RenderError::ImageError(e.to_string()))?
+        .into_dimensions()
+        .map_err(|e| RenderError::ImageError(e.to_string()))?;
+    Ok([width as f32, height as f32])
+}
+
+/// Converts decoded pixels to a pixmap, which stores premultiplied colors.
+fn premultiplied_pixmap(image: &RgbaImage) -> Option<Pixmap> {
+    let mut pixels = Vec::with_capacity(image.as_raw().len());
+    for pixel in image.pixels() {
+        let [r, g, b, a] = pixel.0;
+        let a_f = a as f32 / 255.0;
+        pixels.push((r as f32 * a_f) as u8);
+        pixels.push((g as f32 * a_f) as u8);
+        pixels.push((b as f32 * a_f) as u8);
+        pixels.push(a);
+    }
+
+    Pixmap::from_vec(pixels, IntSize::from_wh(image.width(), image.height())?)
+}
+
+fn pixmap_rect(pixmap: &Pixmap) -> Rect {
+    Rect::from_xywh(0.0, 0.0, pixmap.width() as f32, pixmap.height() as f32).unwrap()
+}
+
+/// Parses any CSS color supported by [`sigil_core::color`].
+fn parse_color(input: &str) -> Option<Color> {
+    let color = Rgba::parse(input).ok()?;
+    Some(Color::from_rgba8(color.r, color.g, color.b, color.a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sigil_core::{BackgroundImage, BackgroundPattern, Bindable, Effect, FORMAT_VERSION, GradientStop, ImageCrop, ImageFilter, LinearGradient, PathItem, PolygonItem, RegularPolygonItem, RepeatDirection, RepeatItem, Shadow, StarItem, TextFit, TextShadow, TextSpan, TextStroke, TextTransform};
+
+    #[allow(unused_imports)]
+    use std::fs::File;
+    #[allow(unused_imports)]
+    use std::io::Write;
+
+    /// A layer at (x, y) with every other setting at its default.
+    fn layer(id: &str, x: f32, y: f32, item: Item) -> Layer {
+        Layer { x: x.into(), y: y.into(), ..Layer::new(id, item) }
+    }
+
+    /// A 40x40 template with a black background, the canvas most of these tests draw on.
+    fn canvas(layers: Vec<Layer>) -> Sigil {
+        Sigil { version: FORMAT_VERSION, width: 40, height: 40, background: "#000000".into(), variables: Vec::new(), layers }
+    }
+
+    fn render(renderer: &mut Renderer, sigil: &Sigil, resources: &HashMap<String, Vec<u8>>) -> Result<Vec<u8>, RenderError> {
+        renderer.render_raw(sigil, resources).map(|data| data.to_vec())
+    }
+
+    /// The color at (x, y) of a render of a [`canvas`].
+    fn pixel(data: &[u8], x: usize, y: usize) -> [u8; 3] {
+        let i = (y * 40 + x) * 4;
+        [data[i], data[i + 1], data[i + 2]]
+    }
+
+    #[test]
+    fn test_render_rect_and_text() {
+        let sigil = Sigil {
+            version: FORMAT_VERSION,
             width: 400,
             height: 200,
-            background: "#1a1a1a".to_string(),
+            background: "#1a1a1a".into(),
+            variables: Vec::new(),
             layers: vec![
-                Layer {
-                    id: "box".to_string(),
-                    x: 20.0,
-                    y: 20.0,
-                    rotation: 0.0,
-                    item: Item::Rect(RectItem {
-                        width: 360.0,
-                        height: 160.0,
-                        color: "#333333".to_string(),
-                        border_radius: 20.0,
+                layer(
+                    "box",
+                    20.0,
+                    20.0,
+                    Item::Rect(RectItem {
+                        width: 360.0.into(),
+                        height: 160.0.into(),
+                        color: "#333333".into(),
+                        border_radius: 20.0.into(),
+                        stroke: None,
                     }),
-                },
-                Layer {
-                    id: "hello".to_string(),
-                    x: 50.0,
-                    y: 80.0,
-                    rotation: 0.0,
-                    item: Item::Text(TextItem {
+                ),
+                layer(
+                    "hello",
+                    50.0,
+                    80.0,
+                    Item::Text(TextItem {
                         text: "Hello Sigil!".to_string(),
-                        font_size: 48.0,
-                        color: "#ff00ff".to_string(),
+                        font_size: 48.0.into(),
+                        color: "#ff00ff".into(),
                         font_family: "Arial".to_string(),
+                        ..Default::default()
                     }),
-                },
+                ),
             ],
         };
 
@@ -595,4 +1709,778 @@ mod tests {
         // let mut file = File::create("test_output_text.png").unwrap();
         // file.write_all(&png_bytes).unwrap();
     }
+
+    #[test]
+    fn test_text_wraps_and_clamps_lines() {
+        let mut renderer = Renderer::new();
+        if renderer.font_system.db().faces().next().is_none() {
+            return;
+        }
+
+        let mut text_item = TextItem {
+            text: "The quick brown fox jumps over the lazy dog again and again".to_string(),
+            font_size: 20.0.into(),
+            color: "#ffffff".into(),
+            font_family: "Sans Serif".to_string(),
+            max_width: Some(120.0.into()),
+            ..Default::default()
+        };
+
+        let layout = renderer.layout_text(&text_item);
+        assert!(layout.visible_lines > 2);
+        assert_eq!(layout.width, 120.0);
+        for run in layout.buffer.layout_runs() {
+            assert!(run.line_w <= 120.0 + 0.5);
+        }
+
+        text_item.max_lines = Some(2.into());
+        let layout = renderer.layout_text(&text_item);
+        assert_eq!(layout.visible_lines, 2);
+        assert_eq!(layout.height, 2.0 * text_item.line_height_px());
+
+        text_item.max_lines = None;
+        text_item.max_height = Some(100.0.into());
+        text_item.vertical_align = VerticalAlign::Bottom;
+        let layout = renderer.layout_text(&text_item);
+        let content_height = layout.visible_lines as f32 * text_item.line_height_px();
+        assert!(content_height <= 100.0);
+        assert_eq!(layout.height, 100.0);
+        assert!((layout.offset_y - (100.0 - content_height)).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_fit_text_shrinks_and_ellipsizes() {
+        let mut renderer = Renderer::new();
+        if renderer.font_system.db().faces().next().is_none() {
+            return;
+        }
+
+        let mut text_item = TextItem {
+            text: "Hi".to_string(),
+            font_size: 12.0.into(),
+            color: "#ffffff".into(),
+            font_family: "Sans Serif".to_string(),
+            max_lines: Some(1.into()),
+            fit: Some(TextFit {
+                min_font_size: 10.0.into(),
+                max_font_size: 40.0.into(),
+                box_width: 200.0.into(),
+                box_height: 60.0.into(),
+                ellipsis: true,
+            }),
+            ..Default::default()
+        };
+
+        let fitted = renderer.fit_text(&text_item).unwrap();
+        assert_eq!(fitted.font_size, 40.0);
+        assert_eq!(fitted.text, "Hi");
+
+        text_item.text = "A_rather_long_username".to_string();
+        let fitted = renderer.fit_text(&text_item).unwrap();
+        assert!(fitted.font_size > 10.0 && fitted.font_size < 40.0);
+        let mut applied = text_item.clone();
+        applied.apply_fit(&fitted);
+        assert!(!renderer.layout_text(&applied).overflowed);
+
+        text_item.text = "An_absurdly_long_username_that_cannot_possibly_fit_in_the_box".to_string();
+        let fitted = renderer.fit_text(&text_item).unwrap();
+        assert_eq!(fitted.font_size, 10.0);
+        assert!(fitted.text.ends_with(ELLIPSIS));
+        assert!(fitted.text.len() < text_item.text.len());
+    }
+
+    #[test]
+    fn test_text_stroke_and_shadow() {
+        let mut renderer = Renderer::new();
+        if renderer.font_system.db().faces().next().is_none() {
+            return;
+        }
+
+        let sigil = Sigil {
+            version: FORMAT_VERSION,
+            width: 200,
+            height: 120,
+            background: "#000000".into(),
+            variables: Vec::new(),
+            layers: vec![layer(
+                "label",
+                10.0,
+                10.0,
+                Item::Text(TextItem {
+                    text: "HHHH".to_string(),
+                    font_size: 40.0.into(),
+                    color: "#0000ff".into(),
+                    font_family: "Sans Serif".to_string(),
+                    stroke: Some(TextStroke { color: "#00ff00".to_string(), width: 1.0.into() }),
+                    shadow: Some(TextShadow { color: "#ff0000".to_string(), offset_x: 0.0.into(), offset_y: 50.0.into(), blur: 4.0.into() }),
+                    ..Default::default()
+                }),
+            )],
+        };
+
+        let data = renderer.render_raw(&sigil, &HashMap::new()).unwrap().to_vec();
+        let pixels: Vec<&[u8]> = data.chunks(4).collect();
+        let count = |pred: &dyn Fn(&[u8]) -> bool| pixels.iter().filter(|p| pred(p)).count();
+
+        assert!(count(&|p| p[1] > 200 && p[0] < 50) > 0, "stroke should be visible");
+        assert!(count(&|p| p[2] > 200 && p[1] < 50) > 0, "fill should be visible");
+        // The shadow sits below the glyphs, so it only shows up in the lower half
+        let shadow_rows = pixels.chunks(200).skip(70).flatten().filter(|p| p[0] > 100 && p[1] < 50).count();
+        assert!(shadow_rows > 0, "shadow should be visible below the text");
+    }
+
+    #[test]
+    fn test_rich_text_spans_use_their_own_color() {
+        let mut renderer = Renderer::new();
+        if renderer.font_system.db().faces().next().is_none() {
+            return;
+        }
+
+        let sigil = Sigil {
+            version: FORMAT_VERSION,
+            width: 300,
+            height: 60,
+            background: "#000000".into(),
+            variables: Vec::new(),
+            layers: vec![Layer::new(
+                "welcome",
+                Item::Text(TextItem {
+                    font_size: 32.0.into(),
+                    color: "#ff0000".into(),
+                    font_family: "Sans Serif".to_string(),
+                    spans: vec![
+                        TextSpan { text: "HH ".to_string(), ..Default::default() },
+                        TextSpan { text: "HH".to_string(), color: Some("#00ff00".to_string()), font_weight: Some(700.into()), ..Default::default() },
+                    ],
+                    ..Default::default()
+                }),
+            )],
+        };
+
+        let data = renderer.render_raw(&sigil, &HashMap::new()).unwrap().to_vec();
+        let red = data.chunks(4).filter(|p| p[0] > 200 && p[1] < 50).count();
+        let green = data.chunks(4).filter(|p| p[1] > 200 && p[0] < 50).count();
+        assert!(red > 0 && green > 0);
+
+        let mut fitted = sigil.layers[0].item.clone();
+        let Item::Text(text) = &mut fitted else { unreachable!() };
+        text.fit = Some(TextFit { min_font_size: 30.0.into(), max_font_size: 30.0.into(), box_width: 70.0.into(), box_height: 40.0.into(), ellipsis: true });
+        let result = renderer.fit_text(text).unwrap();
+        assert!(result.spans.iter().map(|span| span.text.as_str()).collect::<String>().ends_with(ELLIPSIS));
+        assert!(result.spans.len() <= 2);
+
+        // Spans without a usable size keep the item's, text without line height is an error rather than a panic
+        let mut sized = sigil.clone();
+        let Item::Text(text) = &mut sized.layers[0].item else { unreachable!() };
+        text.spans[1].font_size = Some(0.0.into());
+        assert!(renderer.render_raw(&sized, &HashMap::new()).is_ok());
+        let Item::Text(text) = &mut sized.layers[0].item else { unreachable!() };
+        text.line_height = Some(0.0.into());
+        assert!(matches!(renderer.render_raw(&sized, &HashMap::new()), Err(RenderError::InvalidDimensions(_))));
+    }
+
+    #[test]
+    fn test_layer_opacity_and_blend_mode() {
+        let mut renderer = Renderer::new();
+        let square = |id: &str, x: f32, color: &str, opacity: f32, blend_mode: BlendMode| Layer {
+            opacity: opacity.into(),
+            blend_mode,
+            ..layer(id, x, 0.0, Item::Rect(RectItem { width: 10.0.into(), height: 10.0.into(), color: color.into(), border_radius: 0.0.into(), stroke: None }))
+        };
+
+        let sigil = Sigil {
+            version: FORMAT_VERSION,
+            width: 20,
+            height: 10,
+            background: "#ffffff".into(),
+            variables: Vec::new(),
+            layers: vec![
+                square("faded", 0.0, "#ff0000", 0.5, BlendMode::Normal),
+                square("multiplied", 10.0, "#808080", 1.0, BlendMode::Multiply),
+            ],
+        };
+
+        let data = renderer.render_raw(&sigil, &HashMap::new()).unwrap();
+        let pixel = |x: usize, y: usize| &data[(y * 20 + x) * 4..(y * 20 + x) * 4 + 4];
+
+        let faded = pixel(5, 5);
+        assert_eq!(faded[0], 255);
+        assert!((126..=129).contains(&faded[1]), "{:?}", faded);
+        assert_eq!(faded[3], 255);
+
+        let multiplied = pixel(15, 5);
+        assert!(multiplied[..3].iter().all(|c| (127..=129).contains(c)), "{:?}", multiplied);
+    }
+
+    #[test]
+    fn test_gradient_fills() {
+        let mut renderer = Renderer::new();
+        let stops = vec![
+            sigil_core::GradientStop { color: "#ff0000".to_string(), offset: 0.0 },
+            sigil_core::GradientStop { color: "#0000ff".to_string(), offset: 1.0 },
+        ];
+
+        let sigil = Sigil {
+            version: FORMAT_VERSION,
+            width: 100,
+            height: 100,
+            background: Background::Gradient(Gradient::Radial(sigil_core::RadialGradient { center_x: 0.5, center_y: 0.5, stops: stops.clone() })),
+            variables: Vec::new(),
+            layers: vec![Layer::new(
+                "bar",
+                Item::Rect(RectItem {
+                    width: 100.0.into(),
+                    height: 10.0.into(),
+                    color: Fill::Gradient(Gradient::Linear(sigil_core::LinearGradient { angle: 90.0, stops })),
+                    border_radius: 0.0.into(),
+                    stroke: None,
+                }),
+            )],
+        };
+
+        let data = renderer.render_raw(&sigil, &HashMap::new()).unwrap();
+        let pixel = |x: usize, y: usize| &data[(y * 100 + x) * 4..(y * 100 + x) * 4 + 4];
+
+        // Linear, left to right
+        assert!(pixel(1, 5)[0] > 240 && pixel(1, 5)[2] < 15, "{:?}", pixel(1, 5));
+        assert!(pixel(98, 5)[2] > 240 && pixel(98, 5)[0] < 15, "{:?}", pixel(98, 5));
+        // Radial background, red in the center and blue towards the corners
+        assert!(pixel(50, 50)[0] > 240, "{:?}", pixel(50, 50));
+        assert!(pixel(99, 99)[2] > 200, "{:?}", pixel(99, 99));
+
+        if renderer.font_system.db().faces().next().is_none() {
+            return;
+        }
+
+        // Gradient text is painted through the glyph coverage
+        let mut sigil = sigil;
+        sigil.background = "#000000".into();
+        let Item::Rect(rect) = &sigil.layers[0].item else { unreachable!() };
+        let text_fill = rect.color.clone();
+        sigil.layers[0].item = Item::Text(TextItem {
+            text: "HHHHHH".to_string(),
+            font_size: 30.0.into(),
+            color: text_fill,
+            font_family: "Sans Serif".to_string(),
+            ..Default::default()
+        });
+
+        let data = renderer.render_raw(&sigil, &HashMap::new()).unwrap();
+        let reddish = data.chunks(4).filter(|p| p[0] > 150 && p[2] < 100).count();
+        let bluish = data.chunks(4).filter(|p| p[2] > 150 && p[0] < 100).count();
+        assert!(reddish > 0 && bluish > 0);
+    }
+
+    #[test]
+    fn test_css_colors_render() {
+        let mut renderer = Renderer::new();
+        let sigil = Sigil {
+            version: FORMAT_VERSION,
+            width: 10,
+            height: 10,
+            background: "white".into(),
+            variables: Vec::new(),
+            layers: vec![Layer::new(
+                "tint",
+                Item::Rect(RectItem { width: 10.0.into(), height: 10.0.into(), color: "rgba(255, 0, 0, 0.5)".into(), border_radius: 0.0.into(), stroke: None }),
+            )],
+        };
+
+        let data = renderer.render_raw(&sigil, &HashMap::new()).unwrap();
+        assert_eq!(data[0], 255);
+        assert!((126..=129).contains(&data[1]), "{:?}", &data[..4]);
+
+        let mut invalid = sigil.clone();
+        invalid.layers[0].item = Item::Rect(RectItem { width: 10.0.into(), height: 10.0.into(), color: "rgb(1, 2)".into(), border_radius: 0.0.into(), stroke: None });
+        assert!(matches!(renderer.render_raw(&invalid, &HashMap::new()), Err(RenderError::InvalidColorFormat(_))));
+    }
+
+    #[test]
+    fn test_shape_strokes() {
+        let mut renderer = Renderer::new();
+        let rect = |stroke| {
+            let item = Item::Rect(RectItem { width: 20.0.into(), height: 20.0.into(), color: "#ff0000".into(), border_radius: 0.0.into(), stroke: Some(stroke) });
+            canvas(vec![layer("box", 10.0, 10.0, item)])
+        };
+        let stroke = ShapeStroke { color: "#00ff00".to_string(), width: 4.0.into(), align: StrokeAlign::Outside, dash: Vec::new() };
+
+        // Outside: the stroke surrounds the rect, which keeps its fill up to the edge.
+        let data = renderer.render_raw(&rect(stroke.clone()), &HashMap::new()).unwrap();
+        assert_eq!(pixel(data, 8, 20), [0, 255, 0]);
+        assert_eq!(pixel(data, 11, 20), [255, 0, 0]);
+        assert_eq!(pixel(data, 5, 20), [0, 0, 0]);
+
+        // Inside: the stroke covers the edge of the rect and nothing outside it.
+        let inside = ShapeStroke { align: StrokeAlign::Inside, ..stroke.clone() };
+        let data = renderer.render_raw(&rect(inside), &HashMap::new()).unwrap();
+        assert_eq!(pixel(data, 8, 20), [0, 0, 0]);
+        assert_eq!(pixel(data, 11, 20), [0, 255, 0]);
+        assert_eq!(pixel(data, 20, 20), [255, 0, 0]);
+
+        // Dashed strokes leave gaps along the edge.
+        let dashed = ShapeStroke { dash: vec![4.0], ..stroke };
+        let data = renderer.render_raw(&rect(dashed), &HashMap::new()).unwrap();
+        let row: Vec<[u8; 3]> = (10..30).map(|x| pixel(data, x, 8)).collect();
+        assert!(row.contains(&[0, 255, 0]));
+        assert!(row.contains(&[0, 0, 0]));
+    }
+
+    #[test]
+    fn test_layer_effects() {
+        let mut renderer = Renderer::new();
+        let card = |color: &str, effects: Vec<Effect>| Layer {
+            effects,
+            ..layer("card", 10.0, 10.0, Item::Rect(RectItem { width: 10.0.into(), height: 10.0.into(), color: color.into(), border_radius: 0.0.into(), stroke: None }))
+        };
+        let render_on_white = |renderer: &mut Renderer, layers: Vec<Layer>| {
+            render(renderer, &Sigil { background: "#ffffff".into(), ..canvas(layers) }, &HashMap::new()).unwrap()
+        };
+        let shadow = |offset_x: f32, spread: f32| Shadow { color: "#000000".to_string(), offset_x: offset_x.into(), offset_y: offset_x.into(), blur: 0.0.into(), spread: spread.into() };
+
+        // A drop shadow shows below and right of the card, which still covers it
+        let data = render_on_white(&mut renderer, vec![card("#ff0000", vec![Effect::DropShadow(shadow(5.0, 0.0))])]);
+        assert_eq!(pixel(&data, 12, 12), [255, 0, 0]);
+        assert_eq!(pixel(&data, 22, 22), [0, 0, 0]);
+        assert_eq!(pixel(&data, 8, 8), [255, 255, 255]);
+
+        // Spread grows the shadow's shape on all sides
+        let data = render_on_white(&mut renderer, vec![card("#ff0000", vec![Effect::DropShadow(shadow(0.0, 2.0))])]);
+        assert_eq!(pixel(&data, 8, 15), [0, 0, 0]);
+        assert_eq!(pixel(&data, 6, 15), [255, 255, 255]);
+
+        // An inner shadow darkens the edge it is cast from and stays inside the card
+        let data = render_on_white(&mut renderer, vec![card("#ff0000", vec![Effect::InnerShadow(shadow(3.0, 0.0))])]);
+        assert_eq!(pixel(&data, 11, 15), [0, 0, 0]);
+        assert_eq!(pixel(&data, 16, 16), [255, 0, 0]);
+        assert_eq!(pixel(&data, 8, 15), [255, 255, 255]);
+
+        // A layer blur softens the card's edges
+        let data = render_on_white(&mut renderer, vec![card("#ff0000", vec![Effect::LayerBlur { radius: 4.0.into() }])]);
+        let edge = pixel(&data, 10, 15);
+        assert!(edge[1] > 0 && edge[1] < 255, "{:?}", edge);
+
+        // A background blur mixes the black and white halves behind a nearly transparent card
+        let mut black = card("#000000", Vec::new());
+        black.x = 0.0.into();
+        black.y = 0.0.into();
+        black.item = Item::Rect(RectItem { width: 15.0.into(), height: 40.0.into(), color: "#000000".into(), border_radius: 0.0.into(), stroke: None });
+        let glass = card("rgba(255, 255, 255, 0.01)", vec![Effect::BackgroundBlur { radius: 8.0.into() }]);
+        let data = render_on_white(&mut renderer, vec![black.clone(), glass]);
+        let inside = pixel(&data, 14, 15);
+        assert!(inside[0] > 40 && inside[0] < 220, "{:?}", inside);
+        assert_eq!(pixel(&data, 14, 5), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_vector_shapes() {
+        let mut renderer = Renderer::new();
+        let shape = |item: Item| canvas(vec![layer("shape", 10.0, 10.0, item)]);
+        let stroke = ShapeStroke { color: "#00ff00".to_string(), width: 2.0.into(), align: StrokeAlign::Outside, dash: Vec::new() };
+
+        // The ellipse leaves the corners of its box empty; an outside stroke rings it
+        let data = render(&mut renderer, &shape(Item::Ellipse(EllipseItem { width: 20.0.into(), height: 20.0.into(), color: "#ff0000".into(), stroke: Some(stroke.clone()) })), &HashMap::new()).unwrap();
+        assert_eq!(pixel(&data, 20, 20), [255, 0, 0]);
+        assert_eq!(pixel(&data, 10, 10), [0, 0, 0]);
+        assert_eq!(pixel(&data, 8, 20), [0, 255, 0]);
+        assert_eq!(pixel(&data, 11, 20), [255, 0, 0]);
+
+        // An inside stroke on a polygon stays within its edges
+        let triangle = PolygonItem {
+            points: vec![[0.0, 0.0], [20.0, 0.0], [0.0, 20.0]],
+            color: "#ff0000".into(),
+            stroke: Some(ShapeStroke { align: StrokeAlign::Inside, ..stroke.clone() }),
+        };
+        let data = render(&mut renderer, &shape(Item::Polygon(triangle)), &HashMap::new()).unwrap();
+        assert_eq!(pixel(&data, 10, 15), [0, 255, 0]);
+        assert_eq!(pixel(&data, 8, 15), [0, 0, 0]);
+        assert_eq!(pixel(&data, 14, 14), [255, 0, 0]);
+        assert_eq!(pixel(&data, 26, 26), [0, 0, 0]);
+
+        // The star's top tip reaches the top of its box, its inner corners are pulled in
+        let star = StarItem { width: 20.0.into(), height: 20.0.into(), points: 5.into(), inner_ratio: 0.4.into(), color: "#ff0000".into(), stroke: None };
+        assert_eq!(star.vertices().len(), 10);
+        let data = render(&mut renderer, &shape(Item::Star(star)), &HashMap::new()).unwrap();
+        assert_eq!(pixel(&data, 20, 14), [255, 0, 0]);
+        assert_eq!(pixel(&data, 20, 20), [255, 0, 0]);
+        assert_eq!(pixel(&data, 20, 28), [0, 0, 0]);
+
+        let hexagon = RegularPolygonItem { width: 20.0.into(), height: 20.0.into(), sides: 6.into(), color: "#ff0000".into(), stroke: None };
+        assert_eq!(hexagon.vertices().len(), 6);
+        let data = render(&mut renderer, &shape(Item::RegularPolygon(hexagon)), &HashMap::new()).unwrap();
+        assert_eq!(pixel(&data, 20, 20), [255, 0, 0]);
+        assert_eq!(pixel(&data, 11, 11), [0, 0, 0]);
+
+        // A dashed horizontal line; square caps reach past its ends
+        let line = LineItem { x1: 0.0.into(), y1: 0.0.into(), x2: 20.0.into(), y2: 0.0.into(), color: "#ffffff".to_string(), stroke_width: 4.0.into(), cap: LineCap::Butt, dash: vec![4.0, 4.0] };
+        let data = render(&mut renderer, &shape(Item::Line(line.clone())), &HashMap::new()).unwrap();
+        assert_eq!(pixel(&data, 11, 10), [255, 255, 255]);
+        assert_eq!(pixel(&data, 16, 10), [0, 0, 0]);
+        assert_eq!(pixel(&data, 9, 10), [0, 0, 0]);
+        assert_eq!(pixel(&data, 11, 13), [0, 0, 0]);
+
+        let data = render(&mut renderer, &shape(Item::Line(LineItem { cap: LineCap::Square, dash: Vec::new(), ..line })), &HashMap::new()).unwrap();
+        assert_eq!(pixel(&data, 9, 10), [255, 255, 255]);
+    }
+
+    #[test]
+    fn test_paths_and_svg_images() {
+        let mut renderer = Renderer::new();
+        let vector = |item: Item| canvas(vec![layer("vector", 10.0, 10.0, item)]);
+
+        // A square with a square hole, drawn in a 10-unit view box scaled up to 20px
+        let mut path = PathItem {
+            d: "M0 0 H10 V10 H0 Z M3 3 H7 V7 H3 Z".to_string(),
+            width: 20.0.into(),
+            height: 20.0.into(),
+            view_box: Some([0.0, 0.0, 10.0, 10.0]),
+            fill: Some("#ff0000".into()),
+            fill_rule: sigil_core::FillRule::EvenOdd,
+            stroke: None,
+        };
+        let data = render(&mut renderer, &vector(Item::Path(path.clone())), &HashMap::new()).unwrap();
+        assert_eq!(pixel(&data, 12, 12), [255, 0, 0]);
+        assert_eq!(pixel(&data, 20, 20), [0, 0, 0]);
+        assert_eq!(pixel(&data, 31, 31), [0, 0, 0]);
+
+        path.fill_rule = sigil_core::FillRule::Nonzero;
+        let data = render(&mut renderer, &vector(Item::Path(path.clone())), &HashMap::new()).unwrap();
+        assert_eq!(pixel(&data, 20, 20), [255, 0, 0]);
+
+        // Unfilled, only the stroke is drawn
+        path.fill = None;
+        path.stroke = Some(ShapeStroke { color: "#00ff00".to_string(), width: 2.0.into(), align: StrokeAlign::Inside, dash: Vec::new() });
+        let data = render(&mut renderer, &vector(Item::Path(path.clone())), &HashMap::new()).unwrap();
+        assert_eq!(pixel(&data, 10, 20), [0, 255, 0]);
+        assert_eq!(pixel(&data, 13, 20), [0, 0, 0]);
+
+        path.d = "M0 0 L10".to_string();
+        let sigil = canvas(vec![Layer::new("broken", Item::Path(path))]);
+        assert!(matches!(renderer.render_raw(&sigil, &HashMap::new()), Err(RenderError::InvalidPathData(_))));
+
+        // SVG resources are rendered at the image size, whatever size the document declares
+        let svg = br##"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"><rect width="5" height="10" fill="#0000ff"/></svg>"##;
+        let resources = HashMap::from([("logo".to_string(), svg.to_vec())]);
+        let image = ImageItem {
+            source: "logo".to_string(),
+            width: 20.0.into(),
+            height: 20.0.into(),
+            border_radius: 0.0.into(),
+            stroke: None,
+            fit: ImageFit::Fill,
+            focal_point: [0.5.into(), 0.5.into()],
+            crop: None,
+            filters: Vec::new(),
+        };
+        let data = render(&mut renderer, &vector(Item::Image(image)), &resources).unwrap();
+        assert_eq!(pixel(&data, 11, 28), [0, 0, 255]);
+        assert_eq!(pixel(&data, 18, 20), [0, 0, 255]);
+        assert_eq!(pixel(&data, 22, 20), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_groups_compose_transforms_and_clip() {
+        let mut renderer = Renderer::new();
+        let square = |color: &str, size: f32| Item::Rect(RectItem { width: size.into(), height: size.into(), color: color.into(), border_radius: 0.0.into(), stroke: None });
+
+        // A bar in the top half of a 20x20 group ends up in the right half once the group turns 90 degrees
+        let bar = layer("bar", 0.0, 0.0, Item::Rect(RectItem { width: 20.0.into(), height: 10.0.into(), color: "#ff0000".into(), border_radius: 0.0.into(), stroke: None }));
+        let group = GroupItem { width: 20.0.into(), height: 20.0.into(), clip: false.into(), children: vec![bar] };
+        let mut sigil = canvas(vec![Layer { rotation: 90.0.into(), ..layer("group", 10.0, 10.0, Item::Group(group)) }]);
+        let data = render(&mut renderer, &sigil, &HashMap::new()).unwrap();
+        assert_eq!(pixel(&data, 25, 20), [255, 0, 0]);
+        assert_eq!(pixel(&data, 15, 20), [0, 0, 0]);
+
+        // Children are offset by the group position and, when clipping, cut off at the group's edge
+        let overflowing = layer("overflowing", 10.0, 10.0, square("#00ff00", 20.0));
+        let mut group = GroupItem { width: 20.0.into(), height: 20.0.into(), clip: false.into(), children: vec![overflowing] };
+        sigil.layers = vec![layer("group", 5.0, 5.0, Item::Group(group.clone()))];
+        let data = render(&mut renderer, &sigil, &HashMap::new()).unwrap();
+        assert_eq!(pixel(&data, 20, 20), [0, 255, 0]);
+        assert_eq!(pixel(&data, 30, 30), [0, 255, 0]);
+        assert_eq!(pixel(&data, 10, 10), [0, 0, 0]);
+
+        group.clip = true.into();
+        sigil.layers = vec![layer("group", 5.0, 5.0, Item::Group(group))];
+        let data = render(&mut renderer, &sigil, &HashMap::new()).unwrap();
+        assert_eq!(pixel(&data, 20, 20), [0, 255, 0]);
+        assert_eq!(pixel(&data, 30, 30), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_layer_masks() {
+        let mut renderer = Renderer::new();
+        let masked = |id: &str, item: Item, mask: Option<LayerMask>| Layer { mask, ..layer(id, 10.0, 10.0, item) };
+        let square = |color: Fill| Item::Rect(RectItem { width: 20.0.into(), height: 20.0.into(), color, border_radius: 0.0.into(), stroke: None });
+
+        // A translucent blue hexagon clips a red square at full strength and is not drawn itself
+        let hexagon = Item::RegularPolygon(RegularPolygonItem { width: 20.0.into(), height: 20.0.into(), sides: 6.into(), color: "#0000ff40".into(), stroke: None });
+        let clip = LayerMask { source: MaskSource::Layer { id: "hexagon".to_string() }, mode: MaskMode::Clip };
+        let data = render(&mut renderer, &canvas(vec![masked("hexagon", hexagon, None), masked("avatar", square("#ff0000".into()), Some(clip))]), &HashMap::new()).unwrap();
+        assert_eq!(pixel(&data, 20, 20), [255, 0, 0]);
+        assert_eq!(pixel(&data, 11, 11), [0, 0, 0]);
+
+        // An inline gradient fades the square out from left to right, by alpha or by luminance
+        let fade = |from: &str, to: &str| Fill::Gradient(Gradient::Linear(LinearGradient {
+            angle: 90.0,
+            stops: vec![GradientStop { offset: 0.0, color: from.to_string() }, GradientStop { offset: 1.0, color: to.to_string() }],
+        }));
+        let shape_mask = |fill: Fill, mode: MaskMode| LayerMask {
+            source: MaskSource::Shape { x: 10.0, y: 10.0, rotation: 0.0, item: Box::new(square(fill)) },
+            mode,
+        };
+        for mask in [shape_mask(fade("#ffffff", "#ffffff00"), MaskMode::Alpha), shape_mask(fade("#ffffff", "#000000"), MaskMode::Luminance)] {
+            let data = render(&mut renderer, &canvas(vec![masked("photo", square("#ff0000".into()), Some(mask))]), &HashMap::new()).unwrap();
+            assert!(pixel(&data, 11, 20)[0] > 220);
+            assert!(pixel(&data, 20, 20)[0] > 100 && pixel(&data, 20, 20)[0] < 160);
+            assert!(pixel(&data, 28, 20)[0] < 40);
+        }
+
+        let missing = LayerMask { source: MaskSource::Layer { id: "nothing".to_string() }, mode: MaskMode::Alpha };
+        let result = render(&mut renderer, &canvas(vec![masked("photo", square("#ff0000".into()), Some(missing))]), &HashMap::new());
+        assert!(matches!(result, Err(RenderError::InvalidMask(_))));
+    }
+
+    #[test]
+    fn test_image_fit_focal_point_and_crop() {
+        let mut renderer = Renderer::new();
+        // A 20x10 PNG, red on the left half and blue on the right
+        let mut png = Vec::new();
+        image::RgbaImage::from_fn(20, 10, |x, _| if x < 10 { image::Rgba([255, 0, 0, 255]) } else { image::Rgba([0, 0, 255, 255]) })
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let resources = HashMap::from([("photo".to_string(), png)]);
+        let mut render_image = |fit: ImageFit, focal_point: [f32; 2], crop: Option<ImageCrop>| {
+            let image = ImageItem { source: "photo".to_string(), width: 20.0.into(), height: 20.0.into(), border_radius: 0.0.into(), stroke: None, fit, focal_point: focal_point.map(Into::into), crop, filters: Vec::new() };
+            render(&mut renderer, &canvas(vec![layer("photo", 10.0, 10.0, Item::Image(image))]), &resources)
+        };
+
+        // The rectangles asserted against CSS in sigil-core: fill stretches, contain and none letterbox
+        let data = render_image(ImageFit::Fill, [0.5, 0.5], None).unwrap();
+        assert_eq!(pixel(&data, 12, 11), [255, 0, 0]);
+        assert_eq!(pixel(&data, 28, 28), [0, 0, 255]);
+        for fit in [ImageFit::Contain, ImageFit::None, ImageFit::ScaleDown] {
+            let data = render_image(fit, [0.5, 0.5], None).unwrap();
+            assert_eq!(pixel(&data, 12, 12), [0, 0, 0], "{}", fit.css_name());
+            assert_eq!(pixel(&data, 12, 20), [255, 0, 0], "{}", fit.css_name());
+            assert_eq!(pixel(&data, 28, 27), [0, 0, 0], "{}", fit.css_name());
+        }
+        let data = render_image(ImageFit::Contain, [0.5, 0.0], None).unwrap();
+        assert_eq!(pixel(&data, 12, 11), [255, 0, 0]);
+        assert_eq!(pixel(&data, 12, 25), [0, 0, 0]);
+
+        // Cover fills the box and keeps the focal point in view
+        let data = render_image(ImageFit::Cover, [0.0, 0.5], None).unwrap();
+        assert_eq!(pixel(&data, 12, 12), [255, 0, 0]);
+        assert_eq!(pixel(&data, 27, 27), [255, 0, 0]);
+        let data = render_image(ImageFit::Cover, [1.0, 0.5], None).unwrap();
+        assert_eq!(pixel(&data, 12, 12), [0, 0, 255]);
+
+        // A crop of the blue half is fitted like a whole image
+        let data = render_image(ImageFit::Fill, [0.5, 0.5], Some(ImageCrop { x: 10.0.into(), y: 0.0.into(), width: 10.0.into(), height: 10.0.into() })).unwrap();
+        assert_eq!(pixel(&data, 11, 11), [0, 0, 255]);
+        assert_eq!(pixel(&data, 28, 28), [0, 0, 255]);
+
+        // A crop narrower than a pixel, or past the image's edge, shows nothing and is an error
+        for crop in [ImageCrop { x: 4.0.into(), y: 0.0.into(), width: 0.3.into(), height: 10.0.into() }, ImageCrop { x: 25.0.into(), y: 0.0.into(), width: 5.0.into(), height: 10.0.into() }] {
+            let result = render_image(ImageFit::Fill, [0.5, 0.5], Some(crop.clone()));
+            assert!(matches!(result, Err(RenderError::InvalidDimensions(_))), "{crop:?}");
+        }
+    }
+
+    #[test]
+    fn test_image_and_background_filters() {
+        let mut renderer = Renderer::new();
+        let mut png = Vec::new();
+        image::RgbaImage::from_pixel(10, 10, image::Rgba([255, 0, 0, 255]))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let resources = HashMap::from([("photo".to_string(), png)]);
+        let mut render_filtered = |filters: Vec<ImageFilter>, background_filters: Vec<ImageFilter>| {
+            let image = ImageItem {
+                source: "photo".to_string(),
+                width: 20.0.into(),
+                height: 20.0.into(),
+                border_radius: 0.0.into(),
+                stroke: None,
+                fit: ImageFit::Fill,
+                focal_point: [0.5.into(), 0.5.into()],
+                crop: None,
+                filters,
+            };
+            let background = Background::Image(BackgroundImage { filters: background_filters, ..BackgroundImage::new("photo") });
+            let sigil = Sigil { background, ..canvas(vec![layer("photo", 10.0, 10.0, Item::Image(image))]) };
+            render(&mut renderer, &sigil, &resources).unwrap()
+        };
+
+        // Grayscale keeps red's luminance; the background image is filtered separately
+        let data = render_filtered(vec![ImageFilter::Grayscale { amount: 1.0 }], vec![ImageFilter::Brightness { amount: 0.5 }]);
+        assert_eq!(pixel(&data, 20, 20), [54, 54, 54]);
+        assert_eq!(pixel(&data, 2, 2), [128, 0, 0]);
+
+        // Filters apply in order: a gray image tinted blue
+        let tint = ImageFilter::Tint { color: "#0000ff".to_string(), amount: 1.0 };
+        let data = render_filtered(vec![ImageFilter::Invert { amount: 1.0 }, tint], Vec::new());
+        assert_eq!(pixel(&data, 20, 20), [0, 0, 201]);
+
+        // Blur fades the image out towards the edges of its box, over the unfiltered background
+        let data = render_filtered(vec![ImageFilter::Blur { radius: 8.0 }, ImageFilter::Grayscale { amount: 1.0 }], Vec::new());
+        let center = pixel(&data, 20, 20);
+        assert!(center.iter().all(|c| c.abs_diff(54) <= 2), "{center:?}");
+        let edge = pixel(&data, 10, 20);
+        assert!(edge[0] > 150 && edge[1] > 20 && edge[1] < 54, "{edge:?}");
+    }
+
+    #[test]
+    fn test_backgrounds() {
+        let mut renderer = Renderer::new();
+        let png = |image: image::RgbaImage| {
+            let mut data = Vec::new();
+            image.write_to(&mut std::io::Cursor::new(&mut data), image::ImageFormat::Png).unwrap();
+            data
+        };
+        let red = image::Rgba([255, 0, 0, 255]);
+        let blue = image::Rgba([0, 0, 255, 255]);
+        let resources = HashMap::from([
+            ("banner".to_string(), png(image::RgbaImage::from_pixel(20, 10, red))),
+            ("checks".to_string(), png(image::RgbaImage::from_fn(2, 1, |x, _| if x == 0 { red } else { blue }))),
+        ]);
+        let mut render_background = |background: Background| render(&mut renderer, &Sigil { background, ..canvas(Vec::new()) }, &resources);
+        let pixel = |data: &[u8], x: usize, y: usize| {
+            let i = (y * 40 + x) * 4;
+            [data[i], data[i + 1], data[i + 2], data[i + 3]]
+        };
+
+        // A mistyped color is an error rather than a missing image drawn as black
+        assert!(matches!(render_background(Background::Solid("#ff00zz".to_string())), Err(RenderError::InvalidColorFormat(_))));
+        assert!(matches!(render_background(Background::Image(BackgroundImage::new("nowhere"))), Err(RenderError::ResourceNotFound(_))));
+        let missing = BackgroundPattern { source: "nowhere".to_string(), tile_size: None };
+        assert!(matches!(render_background(Background::Pattern(missing)), Err(RenderError::ResourceNotFound(_))));
+
+        // A contained banner leaves bands above and below, and the overlay covers everything
+        let banner = BackgroundImage { fit: ImageFit::Contain, overlay: Some("#0000ff80".into()), ..BackgroundImage::new("banner") };
+        let data = render_background(Background::Image(banner)).unwrap();
+        let band = pixel(&data, 20, 5);
+        assert!(band[0] == 0 && band[2].abs_diff(128) <= 1 && band[3].abs_diff(128) <= 1, "{band:?}");
+        let image = pixel(&data, 20, 20);
+        assert!(image[0].abs_diff(127) <= 1 && image[2].abs_diff(128) <= 1 && image[3] == 255, "{image:?}");
+
+        // Patterns repeat at the image's own size or the given tile size
+        let data = render_background(Background::Pattern(BackgroundPattern { source: "checks".to_string(), tile_size: None })).unwrap();
+        assert_eq!([pixel(&data, 0, 0), pixel(&data, 1, 5), pixel(&data, 2, 0)], [[255, 0, 0, 255], [0, 0, 255, 255], [255, 0, 0, 255]]);
+        let data = render_background(Background::Pattern(BackgroundPattern { source: "checks".to_string(), tile_size: Some([8.0.into(), 8.0.into()]) })).unwrap();
+        let [left, right, repeated] = [pixel(&data, 1, 2), pixel(&data, 6, 2), pixel(&data, 9, 9)];
+        assert!(left[0] > 200 && right[2] > 200 && repeated[0] > 200, "{left:?} {right:?} {repeated:?}");
+    }
+
+    #[test]
+    fn test_bound_fields_render_resolved_values() {
+        let slider = SliderItem {
+            width: Bindable::bound("{40 - offset}"),
+            height: 10.0.into(),
+            value: Bindable::bound("{xp}"),
+            max_value: Bindable::bound("{max_xp}"),
+            background_color: "#0000ff".to_string(),
+            fill_color: "#ff0000".into(),
+            border_radius: 0.0.into(),
+            stroke: None,
+        };
+        let bar = Layer { x: Bindable::bound("{offset}"), visible: Bindable::bound("{show_bar}"), ..Layer::new("bar", Item::Slider(slider)) };
+        let template = Sigil { version: FORMAT_VERSION, width: 40, height: 10, background: "#000000".into(), variables: Vec::new(), layers: vec![bar] };
+        let mut renderer = Renderer::new();
+        let mut render = |vars: &[(&str, &str)]| {
+            let vars = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            renderer.render_raw(&template.resolve(&vars), &HashMap::new()).unwrap().to_vec()
+        };
+        let pixel = |data: &[u8], x: usize| data[(5 * 40 + x) * 4..(5 * 40 + x) * 4 + 4].to_vec();
+
+        // A quarter of a 30 pixel track starting at x = 10
+        let data = render(&[("offset", "10"), ("show_bar", "true"), ("xp", "25"), ("max_xp", "100")]);
+        assert_eq!(pixel(&data, 5), [0, 0, 0, 255]);
+        assert_eq!(pixel(&data, 12), [255, 0, 0, 255]);
+        assert_eq!(pixel(&data, 30), [0, 0, 255, 255]);
+
+        let data = render(&[("offset", "10"), ("show_bar", "false"), ("xp", "25"), ("max_xp", "100")]);
+        assert_eq!(pixel(&data, 12), [0, 0, 0, 255]);
+
+        // A font size left unresolved is an error, and validation says which binding it was
+        let mut text = template.clone();
+        text.layers[0].visible = true.into();
+        text.layers[0].item = Item::Text(TextItem { font_size: Bindable::bound("{size}"), color: "#ffffff".into(), ..Default::default() });
+        let resolved = text.resolve(&HashMap::new());
+        assert!(matches!(renderer.render_raw(&resolved, &HashMap::new()), Err(RenderError::InvalidDimensions(_))));
+        let issues = renderer.validate(&resolved, &HashMap::new());
+        assert!(issues.iter().any(|issue| issue.path == "layers[0].item.data.font_size"), "{issues:?}");
+    }
+
+    #[test]
+    fn test_repeaters_draw_a_cell_per_entry() {
+        let swatch = Item::Rect(RectItem { width: 10.0.into(), height: 4.0.into(), color: "{item.color}".into(), border_radius: 0.0.into(), stroke: None });
+        let list = Item::Repeat(RepeatItem {
+            source: "swatches".to_string(),
+            width: 10.0.into(),
+            height: 4.0.into(),
+            direction: RepeatDirection::Vertical,
+            gap: 2.0.into(),
+            max_items: Some(2),
+            template: vec![layer("swatch", 0.0, 0.0, swatch)],
+        });
+        let template = Sigil { version: FORMAT_VERSION, width: 10, height: 20, background: "#000000".into(), variables: Vec::new(), layers: vec![layer("list", 0.0, 4.0, list)] };
+        let vars = HashMap::from([(
+            "swatches".to_string(),
+            r##"[{"color": "#ff0000"}, {"color": "#00ff00"}, {"color": "#0000ff"}]"##.to_string(),
+        )]);
+
+        let mut renderer = Renderer::new();
+        let data = renderer.render_raw(&template.resolve(&vars), &HashMap::new()).unwrap().to_vec();
+        let row = |y: usize| data[(y * 10 + 5) * 4..(y * 10 + 5) * 4 + 4].to_vec();
+        // Cells at y = 4 and y = 10, and no third one past `max_items`
+        assert_eq!(row(2), [0, 0, 0, 255]);
+        assert_eq!(row(5), [255, 0, 0, 255]);
+        assert_eq!(row(9), [0, 0, 0, 255]);
+        assert_eq!(row(11), [0, 255, 0, 255]);
+        assert_eq!(row(17), [0, 0, 0, 255]);
+
+        // Unresolved, the repeater has no entries to draw
+        let data = renderer.render_raw(&template, &HashMap::new()).unwrap().to_vec();
+        assert!(data.chunks(4).all(|pixel| pixel == [0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_letter_spacing_and_weight_affect_layout() {
+        let mut renderer = Renderer::new();
+        if renderer.font_system.db().faces().next().is_none() {
+            return;
+        }
+
+        let mut text_item = TextItem {
+            text: "spacing".to_string(),
+            font_size: 20.0.into(),
+            color: "#ffffff".into(),
+            font_family: "Sans Serif".to_string(),
+            ..Default::default()
+        };
+        let plain = renderer.layout_text(&text_item).width;
+
+        text_item.letter_spacing = 4.0.into();
+        let spaced = renderer.layout_text(&text_item).width;
+        assert!((spaced - plain - 4.0 * 7.0).abs() < 1.0);
+
+        text_item.letter_spacing = 0.0.into();
+        text_item.text_transform = TextTransform::Uppercase;
+        text_item.font_weight = Some(700.into());
+        text_item.underline = true.into();
+        let layout = renderer.layout_text(&text_item);
+        let run = layout.buffer.layout_runs().next().unwrap();
+        assert_eq!(run.text, "SPACING");
+
+        let sigil = Sigil {
+            version: FORMAT_VERSION,
+            width: 200,
+            height: 60,
+            background: "#000000".into(),
+            variables: Vec::new(),
+            layers: vec![layer("label", 10.0, 10.0, Item::Text(text_item))],
+        };
+        assert!(renderer.render(&sigil, &HashMap::new()).is_ok());
+    }
 }
@@ -0,0 +1,175 @@
+/*
+    Sigil - dynamic image synthesis engine
+    Copyright (C) 2025 meetzli
+
+    This program is free software: you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License as published
+    by the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+*/
+
+//! Pixel-level helpers for offscreen layers: Gaussian blur, alpha tinting, spreading and bounds.
+
+use tiny_skia::{Color, IntRect, Pixmap, Rect, Transform};
+
+/// Device-space bounds of `rect` under `transform`, grown by `pad` and clipped to a `width` x `height` canvas.
+pub(crate) fn device_bounds(rect: Rect, transform: Transform, pad: f32, width: u32, height: u32) -> Option<IntRect> {
+    let bounds = rect.transform(transform)?;
+    let left = (bounds.left() - pad).floor().max(0.0) as i32;
+    let top = (bounds.top() - pad).floor().max(0.0) as i32;
+    let right = (bounds.right() + pad).ceil().min(width as f32) as i32;
+    let bottom = (bounds.bottom() + pad).ceil().min(height as f32) as i32;
+
+    IntRect::from_ltrb(left, top, right, bottom)
+}
+
+/// Replaces the color of every pixel with `color`, keeping the pixel's coverage.
+pub(crate) fn tint(pixmap: &mut Pixmap, color: Color) {
+    let color = color.premultiply();
+    for pixel in pixmap.pixels_mut() {
+        let coverage = pixel.alpha() as f32 / 255.0;
+        *pixel = tiny_skia::PremultipliedColorU8::from_rgba(
+            (color.red() * coverage * 255.0).round() as u8,
+            (color.green() * coverage * 255.0).round() as u8,
+            (color.blue() * coverage * 255.0).round() as u8,
+            (color.alpha() * coverage * 255.0).round() as u8,
+        )
+        .unwrap();
+    }
+}
+
+/// Opaque black wherever `pixmap` has any coverage, so that faint fills still count as part of the shape.
+pub(crate) fn solid_coverage(pixmap: &Pixmap) -> Pixmap {
+    let mut shape = pixmap.clone();
+    for pixel in shape.pixels_mut() {
+        let alpha = if pixel.alpha() > 0 { 255 } else { 0 };
+        *pixel = tiny_skia::PremultipliedColorU8::from_rgba(0, 0, 0, alpha).unwrap();
+    }
+    shape
+}
+
+/// Replaces every pixel with black at the inverse of its coverage, turning a shape into a hole.
+pub(crate) fn invert_alpha(pixmap: &mut Pixmap) {
+    for pixel in pixmap.pixels_mut() {
+        *pixel = tiny_skia::PremultipliedColorU8::from_rgba(0, 0, 0, 255 - pixel.alpha()).unwrap();
+    }
+}
+
+/// Grows the covered area by `amount` pixels in every direction, or shrinks it when `amount` is negative.
+///
+/// Takes the per-channel maximum (or minimum) over a square window, which keeps premultiplied pixels valid.
+pub(crate) fn spread(pixmap: &mut Pixmap, amount: f32) {
+    let r = amount.abs().round() as usize;
+    if r == 0 {
+        return;
+    }
+
+    let grow = amount > 0.0;
+    let width = pixmap.width() as usize;
+    let height = pixmap.height() as usize;
+    let data = pixmap.data_mut();
+    let mut scratch = vec![0u8; data.len()];
+    let pick = |values: &mut dyn Iterator<Item = u8>| if grow { values.max() } else { values.min() }.unwrap_or(0);
+
+    for y in 0..height {
+        for x in 0..width {
+            let (lo, hi) = (x.saturating_sub(r), (x + r).min(width - 1));
+            for channel in 0..4 {
+                scratch[(y * width + x) * 4 + channel] = pick(&mut (lo..=hi).map(|sx| data[(y * width + sx) * 4 + channel]));
+            }
+        }
+    }
+
+    for x in 0..width {
+        for y in 0..height {
+            let (lo, hi) = (y.saturating_sub(r), (y + r).min(height - 1));
+            for channel in 0..4 {
+                data[(y * width + x) * 4 + channel] = pick(&mut (lo..=hi).map(|sy| scratch[(sy * width + x) * 4 + channel]));
+            }
+        }
+    }
+}
+
+/// Approximates a Gaussian blur with three box blur passes. Pixels outside the pixmap count as transparent.
+///
+/// `radius` follows the CSS convention of `blur(radius)` / `text-shadow` blur, i.e. a standard deviation of `radius / 2`.
+pub(crate) fn blur(pixmap: &mut Pixmap, radius: f32) {
+    let width = pixmap.width() as usize;
+    let height = pixmap.height() as usize;
+    blur_data(pixmap.data_mut(), width, height, radius);
+}
+
+/// [`blur`] on raw premultiplied RGBA pixels.
+pub(crate) fn blur_data(data: &mut [u8], width: usize, height: usize, radius: f32) {
+    let sigma = radius / 2.0;
+    if sigma < 0.5 {
+        return;
+    }
+
+    let mut scratch = vec![0u8; data.len()];
+
+    for box_size in box_sizes(sigma, 3) {
+        let r = (box_size - 1) / 2;
+        box_blur_horizontal(data, &mut scratch, width, height, r);
+        box_blur_vertical(&scratch, data, width, height, r);
+    }
+}
+
+/// Box widths whose successive application approximates a Gaussian with the given deviation.
+fn box_sizes(sigma: f32, passes: usize) -> Vec<usize> {
+    let n = passes as f32;
+    let ideal = ((12.0 * sigma * sigma / n) + 1.0).sqrt();
+    let mut lower = ideal.floor() as usize;
+    if lower.is_multiple_of(2) {
+        lower -= 1;
+    }
+    let upper = lower + 2;
+
+    let lower_f = lower as f32;
+    let m = ((12.0 * sigma * sigma - n * lower_f * lower_f - 4.0 * n * lower_f - 3.0 * n) / (-4.0 * lower_f - 4.0)).round();
+
+    (0..passes).map(|i| if (i as f32) < m { lower } else { upper }).collect()
+}
+
+fn box_blur_horizontal(src: &[u8], dst: &mut [u8], width: usize, height: usize, r: usize) {
+    let window = (2 * r + 1) as u32;
+    for y in 0..height {
+        let row = y * width * 4;
+        for channel in 0..4 {
+            let at = |x: isize| -> u32 {
+                if x < 0 || x >= width as isize {
+                    return 0;
+                }
+                src[row + x as usize * 4 + channel] as u32
+            };
+
+            let mut sum: u32 = (-(r as isize)..=r as isize).map(at).sum();
+            for x in 0..width {
+                dst[row + x * 4 + channel] = ((sum + window / 2) / window) as u8;
+                sum += at(x as isize + r as isize + 1);
+                sum -= at(x as isize - r as isize);
+            }
+        }
+    }
+}
+
+fn box_blur_vertical(src: &[u8], dst: &mut [u8], width: usize, height: usize, r: usize) {
+    let window = (2 * r + 1) as u32;
+    for x in 0..width {
+        for channel in 0..4 {
+            let at = |y: isize| -> u32 {
+                if y < 0 || y >= height as isize {
+                    return 0;
+                }
+                src[(y as usize * width + x) * 4 + channel] as u32
+            };
+
+            let mut sum: u32 = (-(r as isize)..=r as isize).map(at).sum();
+            for y in 0..height {
+                dst[(y * width + x) * 4 + channel] = ((sum + window / 2) / window) as u8;
+                sum += at(y as isize + r as isize + 1);
+                sum -= at(y as isize - r as isize);
+            }
+        }
+    }
+}